@@ -0,0 +1,105 @@
+-- Create org_role enum type
+CREATE TYPE org_role AS ENUM ('owner', 'admin', 'editor', 'viewer');
+
+-- Create organizations table
+CREATE TABLE IF NOT EXISTS organizations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) NOT NULL,
+    tier_id UUID NOT NULL,
+    created_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TRIGGER update_organizations_updated_at
+    BEFORE UPDATE ON organizations
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE organizations
+ADD CONSTRAINT fk_organizations_tier_id
+FOREIGN KEY (tier_id) REFERENCES tiers(id);
+
+ALTER TABLE organizations
+ADD CONSTRAINT fk_organizations_created_by
+FOREIGN KEY (created_by) REFERENCES users(id)
+ON DELETE SET NULL;
+
+-- Create organization_members table
+CREATE TABLE IF NOT EXISTS organization_members (
+    organization_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    role org_role NOT NULL DEFAULT 'viewer',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (organization_id, user_id)
+);
+
+-- Create index on user_id for listing a user's organizations
+CREATE INDEX idx_organization_members_user_id ON organization_members(user_id);
+
+CREATE TRIGGER update_organization_members_updated_at
+    BEFORE UPDATE ON organization_members
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE organization_members
+ADD CONSTRAINT fk_organization_members_organization_id
+FOREIGN KEY (organization_id) REFERENCES organizations(id)
+ON DELETE CASCADE;
+
+ALTER TABLE organization_members
+ADD CONSTRAINT fk_organization_members_user_id
+FOREIGN KEY (user_id) REFERENCES users(id)
+ON DELETE CASCADE;
+
+-- Create organization_invitations table
+CREATE TABLE IF NOT EXISTS organization_invitations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    organization_id UUID NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    role org_role NOT NULL DEFAULT 'viewer',
+    invited_by UUID,
+    expires_at TIMESTAMPTZ NOT NULL,
+    accepted_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Only one open invitation per email and organization
+CREATE UNIQUE INDEX idx_organization_invitations_org_email
+    ON organization_invitations(organization_id, email) WHERE accepted_at IS NULL;
+
+-- Create index on email for listing a user's pending invitations
+CREATE INDEX idx_organization_invitations_email ON organization_invitations(email);
+
+ALTER TABLE organization_invitations
+ADD CONSTRAINT fk_organization_invitations_organization_id
+FOREIGN KEY (organization_id) REFERENCES organizations(id)
+ON DELETE CASCADE;
+
+ALTER TABLE organization_invitations
+ADD CONSTRAINT fk_organization_invitations_invited_by
+FOREIGN KEY (invited_by) REFERENCES users(id)
+ON DELETE SET NULL;
+
+-- Variables may be owned by an organization; user_id then records the creator
+ALTER TABLE variables ADD COLUMN organization_id UUID;
+
+ALTER TABLE variables
+ADD CONSTRAINT fk_variables_organization_id
+FOREIGN KEY (organization_id) REFERENCES organizations(id)
+ON DELETE CASCADE;
+
+DROP INDEX IF EXISTS idx_variables_user_name;
+CREATE UNIQUE INDEX idx_variables_user_key ON variables(user_id, key) WHERE organization_id IS NULL;
+CREATE UNIQUE INDEX idx_variables_org_key ON variables(organization_id, key) WHERE organization_id IS NOT NULL;
+
+-- API keys may be owned by an organization as well
+ALTER TABLE api_keys ADD COLUMN organization_id UUID;
+
+CREATE INDEX idx_api_keys_organization_id ON api_keys(organization_id);
+
+ALTER TABLE api_keys
+ADD CONSTRAINT fk_api_keys_organization_id
+FOREIGN KEY (organization_id) REFERENCES organizations(id)
+ON DELETE CASCADE;
@@ -0,0 +1,98 @@
+-- Create projects table
+CREATE TABLE IF NOT EXISTS projects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    organization_id UUID,
+    name VARCHAR(100) NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Project names are unique per owner (user or organization)
+CREATE UNIQUE INDEX idx_projects_user_name ON projects(user_id, name) WHERE organization_id IS NULL;
+CREATE UNIQUE INDEX idx_projects_org_name ON projects(organization_id, name) WHERE organization_id IS NOT NULL;
+
+CREATE TRIGGER update_projects_updated_at
+    BEFORE UPDATE ON projects
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE projects
+ADD CONSTRAINT fk_projects_user_id
+FOREIGN KEY (user_id) REFERENCES users(id)
+ON DELETE CASCADE;
+
+ALTER TABLE projects
+ADD CONSTRAINT fk_projects_organization_id
+FOREIGN KEY (organization_id) REFERENCES organizations(id)
+ON DELETE CASCADE;
+
+-- Create environments table
+CREATE TABLE IF NOT EXISTS environments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    project_id UUID NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    description TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_environments_project_name ON environments(project_id, name);
+
+CREATE TRIGGER update_environments_updated_at
+    BEFORE UPDATE ON environments
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE environments
+ADD CONSTRAINT fk_environments_project_id
+FOREIGN KEY (project_id) REFERENCES projects(id)
+ON DELETE CASCADE;
+
+-- Move existing variables into a "default" project and environment per owner
+INSERT INTO projects (user_id, organization_id, name, description)
+SELECT DISTINCT user_id, NULL::UUID, 'default', 'Default project'
+FROM variables
+WHERE organization_id IS NULL;
+
+INSERT INTO projects (user_id, organization_id, name, description)
+SELECT DISTINCT ON (organization_id) user_id, organization_id, 'default', 'Default project'
+FROM variables
+WHERE organization_id IS NOT NULL
+ORDER BY organization_id, created_at;
+
+INSERT INTO environments (project_id, name, description)
+SELECT id, 'default', 'Default environment'
+FROM projects
+WHERE name = 'default';
+
+ALTER TABLE variables ADD COLUMN environment_id UUID;
+
+UPDATE variables v
+SET environment_id = e.id
+FROM projects p
+JOIN environments e ON e.project_id = p.id AND e.name = 'default'
+WHERE p.name = 'default'
+  AND ((v.organization_id IS NULL AND p.organization_id IS NULL AND p.user_id = v.user_id)
+    OR (v.organization_id IS NOT NULL AND p.organization_id = v.organization_id));
+
+ALTER TABLE variables ALTER COLUMN environment_id SET NOT NULL;
+
+ALTER TABLE variables
+ADD CONSTRAINT fk_variables_environment_id
+FOREIGN KEY (environment_id) REFERENCES environments(id)
+ON DELETE CASCADE;
+
+-- Keys are now unique per environment instead of per owner
+DROP INDEX IF EXISTS idx_variables_user_key;
+DROP INDEX IF EXISTS idx_variables_org_key;
+CREATE UNIQUE INDEX idx_variables_environment_key ON variables(environment_id, key);
+
+-- API keys may be restricted to a single environment
+ALTER TABLE api_keys ADD COLUMN environment_id UUID;
+
+ALTER TABLE api_keys
+ADD CONSTRAINT fk_api_keys_environment_id
+FOREIGN KEY (environment_id) REFERENCES environments(id)
+ON DELETE CASCADE;
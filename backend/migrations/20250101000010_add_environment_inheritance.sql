@@ -0,0 +1,14 @@
+-- Allow an environment to inherit variables from a parent environment of the same project
+ALTER TABLE environments
+ADD COLUMN parent_id UUID;
+
+ALTER TABLE environments
+ADD CONSTRAINT fk_environments_parent_id
+FOREIGN KEY (parent_id) REFERENCES environments(id)
+ON DELETE SET NULL;
+
+ALTER TABLE environments
+ADD CONSTRAINT chk_environments_parent_not_self
+CHECK (parent_id IS NULL OR parent_id <> id);
+
+CREATE INDEX idx_environments_parent_id ON environments(parent_id);
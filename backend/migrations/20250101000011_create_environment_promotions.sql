@@ -0,0 +1,35 @@
+-- Record of variables promoted from one environment to another
+CREATE TABLE IF NOT EXISTS environment_promotions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    project_id UUID NOT NULL,
+    source_environment_id UUID,
+    target_environment_id UUID,
+    promoted_by UUID,
+    added INTEGER NOT NULL DEFAULT 0,
+    changed INTEGER NOT NULL DEFAULT 0,
+    removed INTEGER NOT NULL DEFAULT 0,
+    changes JSONB NOT NULL DEFAULT '[]'::jsonb,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_environment_promotions_project_id ON environment_promotions(project_id, created_at DESC);
+
+ALTER TABLE environment_promotions
+ADD CONSTRAINT fk_environment_promotions_project_id
+FOREIGN KEY (project_id) REFERENCES projects(id)
+ON DELETE CASCADE;
+
+ALTER TABLE environment_promotions
+ADD CONSTRAINT fk_environment_promotions_source_environment_id
+FOREIGN KEY (source_environment_id) REFERENCES environments(id)
+ON DELETE SET NULL;
+
+ALTER TABLE environment_promotions
+ADD CONSTRAINT fk_environment_promotions_target_environment_id
+FOREIGN KEY (target_environment_id) REFERENCES environments(id)
+ON DELETE SET NULL;
+
+ALTER TABLE environment_promotions
+ADD CONSTRAINT fk_environment_promotions_promoted_by
+FOREIGN KEY (promoted_by) REFERENCES users(id)
+ON DELETE SET NULL;
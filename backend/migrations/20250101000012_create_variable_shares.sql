@@ -0,0 +1,30 @@
+-- Create share permission enum
+CREATE TYPE share_permission AS ENUM ('read', 'write');
+
+-- Grants giving another user access to a single variable
+CREATE TABLE IF NOT EXISTS variable_shares (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    variable_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    permission share_permission NOT NULL DEFAULT 'read',
+    granted_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (variable_id, user_id)
+);
+
+CREATE INDEX idx_variable_shares_user_id ON variable_shares(user_id);
+
+ALTER TABLE variable_shares
+ADD CONSTRAINT fk_variable_shares_variable_id
+FOREIGN KEY (variable_id) REFERENCES variables(id)
+ON DELETE CASCADE;
+
+ALTER TABLE variable_shares
+ADD CONSTRAINT fk_variable_shares_user_id
+FOREIGN KEY (user_id) REFERENCES users(id)
+ON DELETE CASCADE;
+
+ALTER TABLE variable_shares
+ADD CONSTRAINT fk_variable_shares_granted_by
+FOREIGN KEY (granted_by) REFERENCES users(id)
+ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+-- Staff roles with a subset of admin permissions
+ALTER TYPE user_role ADD VALUE IF NOT EXISTS 'support';
+ALTER TYPE user_role ADD VALUE IF NOT EXISTS 'billing';
+ALTER TYPE user_role ADD VALUE IF NOT EXISTS 'auditor';
@@ -0,0 +1,10 @@
+-- Columns the API key model relies on: a lookup prefix, an optional expiry
+-- and the key's scopes, stored as a JSON array of strings for policies
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS prefix VARCHAR(16);
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+ALTER TABLE api_keys ADD COLUMN IF NOT EXISTS permissions JSONB;
+
+UPDATE api_keys SET prefix = '' WHERE prefix IS NULL;
+ALTER TABLE api_keys ALTER COLUMN prefix SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_api_keys_prefix ON api_keys(prefix);
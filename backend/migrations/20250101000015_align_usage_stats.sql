@@ -0,0 +1,28 @@
+-- Align usage_stats with the counters the application records
+ALTER TABLE usage_stats RENAME COLUMN api_calls_count TO requests_count;
+ALTER TABLE usage_stats RENAME COLUMN storage_bytes_used TO total_bytes_stored;
+ALTER TABLE usage_stats DROP COLUMN IF EXISTS variables_count;
+ALTER TABLE usage_stats ADD COLUMN IF NOT EXISTS variables_created INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE usage_stats ADD COLUMN IF NOT EXISTS variables_updated INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE usage_stats ADD COLUMN IF NOT EXISTS variables_deleted INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE usage_stats ADD COLUMN IF NOT EXISTS variables_read INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE usage_stats ADD COLUMN IF NOT EXISTS total_bytes_transferred BIGINT NOT NULL DEFAULT 0;
+
+-- Organizations accrue usage of their own; rows belong to either a user or an organization
+ALTER TABLE usage_stats ALTER COLUMN user_id DROP NOT NULL;
+ALTER TABLE usage_stats ADD COLUMN IF NOT EXISTS organization_id UUID;
+
+ALTER TABLE usage_stats
+ADD CONSTRAINT fk_usage_stats_organization_id
+FOREIGN KEY (organization_id) REFERENCES organizations(id)
+ON DELETE CASCADE;
+
+ALTER TABLE usage_stats
+ADD CONSTRAINT chk_usage_stats_owner
+CHECK ((user_id IS NULL) <> (organization_id IS NULL));
+
+DROP INDEX IF EXISTS idx_usage_stats_user_date;
+CREATE UNIQUE INDEX idx_usage_stats_user_date ON usage_stats(user_id, date)
+    WHERE organization_id IS NULL;
+CREATE UNIQUE INDEX idx_usage_stats_org_date ON usage_stats(organization_id, date)
+    WHERE organization_id IS NOT NULL;
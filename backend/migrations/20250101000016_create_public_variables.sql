@@ -0,0 +1,30 @@
+-- Variables served without authentication under /public/{owner}/{key}
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS is_public BOOLEAN NOT NULL DEFAULT false;
+
+-- A public key must be unambiguous across the owner's environments
+CREATE UNIQUE INDEX idx_variables_public_key
+    ON variables(COALESCE(organization_id, user_id), key)
+    WHERE is_public;
+
+-- Time-limited links to a single variable, authenticated by an HMAC signature
+CREATE TABLE IF NOT EXISTS variable_share_links (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    variable_id UUID NOT NULL,
+    created_by UUID,
+    expires_at TIMESTAMPTZ NOT NULL,
+    use_count INTEGER NOT NULL DEFAULT 0,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_variable_share_links_variable_id ON variable_share_links(variable_id);
+
+ALTER TABLE variable_share_links
+ADD CONSTRAINT fk_variable_share_links_variable_id
+FOREIGN KEY (variable_id) REFERENCES variables(id)
+ON DELETE CASCADE;
+
+ALTER TABLE variable_share_links
+ADD CONSTRAINT fk_variable_share_links_created_by
+FOREIGN KEY (created_by) REFERENCES users(id)
+ON DELETE SET NULL;
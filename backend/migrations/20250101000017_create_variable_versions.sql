@@ -0,0 +1,33 @@
+-- How many versions of each variable a tier keeps; -1 keeps every version
+ALTER TABLE tiers ADD COLUMN IF NOT EXISTS max_versions INTEGER NOT NULL DEFAULT 10;
+
+UPDATE tiers SET max_versions = 5 WHERE name = 'free';
+UPDATE tiers SET max_versions = 20 WHERE name = 'basic';
+UPDATE tiers SET max_versions = 100 WHERE name = 'pro';
+UPDATE tiers SET max_versions = -1 WHERE name = 'enterprise';
+
+-- Immutable snapshots of every value a variable has held
+CREATE TABLE IF NOT EXISTS variable_versions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    variable_id UUID NOT NULL,
+    version INTEGER NOT NULL,
+    storage_path TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    description TEXT,
+    tags JSONB,
+    is_encrypted BOOLEAN NOT NULL DEFAULT false,
+    author_id UUID,
+    message TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE (variable_id, version)
+);
+
+ALTER TABLE variable_versions
+ADD CONSTRAINT fk_variable_versions_variable_id
+FOREIGN KEY (variable_id) REFERENCES variables(id)
+ON DELETE CASCADE;
+
+ALTER TABLE variable_versions
+ADD CONSTRAINT fk_variable_versions_author_id
+FOREIGN KEY (author_id) REFERENCES users(id)
+ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+-- Tags are filtered with containment (@>) and overlap (?|), both served by a GIN index
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS tags JSONB;
+
+CREATE INDEX IF NOT EXISTS idx_variables_tags ON variables USING GIN (tags);
@@ -0,0 +1,15 @@
+-- String leaves of a variable's data, written by the application; NULL for encrypted
+-- variables, whose contents are never indexed
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS content_text TEXT;
+
+-- Full-text document over the key, description, tags and indexed contents, weighted in
+-- that order of importance. Separators in keys are spaced out so each segment is a word.
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS search_vector TSVECTOR
+    GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', translate(coalesce(key, ''), '._-/:', '     ')), 'A') ||
+        setweight(to_tsvector('english', coalesce(description, '')), 'B') ||
+        setweight(jsonb_to_tsvector('english', coalesce(tags, '[]'::jsonb), '["string"]'), 'B') ||
+        setweight(to_tsvector('english', coalesce(content_text, '')), 'C')
+    ) STORED;
+
+CREATE INDEX IF NOT EXISTS idx_variables_search ON variables USING GIN (search_vector);
@@ -0,0 +1,45 @@
+-- Named JSON Schemas (draft 2020-12), reusable by every variable of their owner
+CREATE TABLE IF NOT EXISTS json_schemas (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    organization_id UUID,
+    name VARCHAR(100) NOT NULL,
+    description TEXT,
+    schema JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Schema names are unique per owner (user or organization)
+CREATE UNIQUE INDEX idx_json_schemas_user_name ON json_schemas(user_id, name) WHERE organization_id IS NULL;
+CREATE UNIQUE INDEX idx_json_schemas_org_name ON json_schemas(organization_id, name) WHERE organization_id IS NOT NULL;
+
+CREATE TRIGGER update_json_schemas_updated_at
+    BEFORE UPDATE ON json_schemas
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE json_schemas
+ADD CONSTRAINT fk_json_schemas_user_id
+FOREIGN KEY (user_id) REFERENCES users(id)
+ON DELETE CASCADE;
+
+ALTER TABLE json_schemas
+ADD CONSTRAINT fk_json_schemas_organization_id
+FOREIGN KEY (organization_id) REFERENCES organizations(id)
+ON DELETE CASCADE;
+
+-- A variable conforms to either a named schema or one of its own; schemas in use can't be deleted
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS schema_id UUID;
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS inline_schema JSONB;
+
+ALTER TABLE variables
+ADD CONSTRAINT fk_variables_schema_id
+FOREIGN KEY (schema_id) REFERENCES json_schemas(id)
+ON DELETE RESTRICT;
+
+ALTER TABLE variables
+ADD CONSTRAINT chk_variables_single_schema
+CHECK (schema_id IS NULL OR inline_schema IS NULL);
+
+CREATE INDEX idx_variables_schema_id ON variables(schema_id) WHERE schema_id IS NOT NULL;
@@ -0,0 +1,38 @@
+-- Create key rotation status enum
+CREATE TYPE key_rotation_status AS ENUM ('running', 'completed', 'failed');
+
+-- Background jobs re-wrapping the data keys of encrypted variables under a new master key.
+-- Variables are processed in id order, so a job resumes after its last processed variable.
+CREATE TABLE IF NOT EXISTS key_rotations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    key_version INTEGER NOT NULL,
+    status key_rotation_status NOT NULL DEFAULT 'running',
+    total_variables BIGINT NOT NULL DEFAULT 0,
+    processed_variables BIGINT NOT NULL DEFAULT 0,
+    rewrapped_keys BIGINT NOT NULL DEFAULT 0,
+    encrypted_variables BIGINT NOT NULL DEFAULT 0,
+    last_variable_id UUID,
+    verified_variables INTEGER,
+    error TEXT,
+    started_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+-- At most one rotation runs at a time
+CREATE UNIQUE INDEX idx_key_rotations_running ON key_rotations((status)) WHERE status = 'running';
+CREATE INDEX idx_key_rotations_created_at ON key_rotations(created_at DESC);
+
+CREATE TRIGGER update_key_rotations_updated_at
+    BEFORE UPDATE ON key_rotations
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE key_rotations
+ADD CONSTRAINT fk_key_rotations_started_by
+FOREIGN KEY (started_by) REFERENCES users(id)
+ON DELETE SET NULL;
+
+-- Lets the rotation walk encrypted variables in id order
+CREATE INDEX idx_variables_encrypted_id ON variables(id) WHERE is_encrypted;
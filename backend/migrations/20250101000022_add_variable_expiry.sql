@@ -0,0 +1,4 @@
+-- Variables may expire; expired variables are hidden at once and deleted by a sweeper
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ;
+
+CREATE INDEX idx_variables_expires_at ON variables(expires_at) WHERE expires_at IS NOT NULL;
@@ -0,0 +1,29 @@
+-- Deleted variables move to a trash (is_active = false) and can be restored until purged
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS deleted_by UUID;
+
+ALTER TABLE variables
+ADD CONSTRAINT fk_variables_deleted_by
+FOREIGN KEY (deleted_by) REFERENCES users(id)
+ON DELETE SET NULL;
+
+-- Trashed variables no longer hold their key
+DROP INDEX IF EXISTS idx_variables_environment_key;
+CREATE UNIQUE INDEX idx_variables_environment_key
+    ON variables(environment_id, key)
+    WHERE is_active;
+
+DROP INDEX IF EXISTS idx_variables_public_key;
+CREATE UNIQUE INDEX idx_variables_public_key
+    ON variables(COALESCE(organization_id, user_id), key)
+    WHERE is_public AND is_active;
+
+CREATE INDEX idx_variables_deleted_at ON variables(deleted_at) WHERE NOT is_active;
+
+-- Days trashed variables are kept before being purged; -1 keeps them until purged by hand
+ALTER TABLE tiers ADD COLUMN IF NOT EXISTS trash_retention_days INTEGER NOT NULL DEFAULT 30;
+
+UPDATE tiers SET trash_retention_days = 7 WHERE name = 'free';
+UPDATE tiers SET trash_retention_days = 30 WHERE name = 'basic';
+UPDATE tiers SET trash_retention_days = 30 WHERE name = 'pro';
+UPDATE tiers SET trash_retention_days = 90 WHERE name = 'enterprise';
@@ -0,0 +1,36 @@
+-- Create scheduled change status enum
+CREATE TYPE scheduled_change_status AS ENUM ('pending', 'applied', 'cancelled', 'failed');
+
+-- Data staged in storage to replace a variable's data at `apply_at`
+CREATE TABLE IF NOT EXISTS scheduled_changes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    variable_id UUID NOT NULL,
+    storage_path TEXT NOT NULL,
+    apply_at TIMESTAMPTZ NOT NULL,
+    message TEXT,
+    status scheduled_change_status NOT NULL DEFAULT 'pending',
+    applied_version INTEGER,
+    error TEXT,
+    created_by UUID,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    applied_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_scheduled_changes_variable_id ON scheduled_changes(variable_id, apply_at);
+CREATE INDEX idx_scheduled_changes_due ON scheduled_changes(apply_at) WHERE status = 'pending';
+
+CREATE TRIGGER update_scheduled_changes_updated_at
+    BEFORE UPDATE ON scheduled_changes
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE scheduled_changes
+ADD CONSTRAINT fk_scheduled_changes_variable_id
+FOREIGN KEY (variable_id) REFERENCES variables(id)
+ON DELETE CASCADE;
+
+ALTER TABLE scheduled_changes
+ADD CONSTRAINT fk_scheduled_changes_created_by
+FOREIGN KEY (created_by) REFERENCES users(id)
+ON DELETE SET NULL;
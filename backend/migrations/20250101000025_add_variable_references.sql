@@ -0,0 +1,5 @@
+-- Keys of the variables a variable's data references, written by the application; empty
+-- for encrypted variables, whose contents are never indexed
+ALTER TABLE variables ADD COLUMN IF NOT EXISTS referenced_keys TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS idx_variables_referenced_keys ON variables USING GIN (referenced_keys);
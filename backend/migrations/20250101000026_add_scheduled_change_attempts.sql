@@ -0,0 +1,3 @@
+-- Changes that hit a transient error are retried with a backoff, up to a limit
+ALTER TABLE scheduled_changes ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE scheduled_changes ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMPTZ;
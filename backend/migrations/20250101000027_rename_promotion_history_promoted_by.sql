@@ -0,0 +1,2 @@
+-- Align promotion history with the column the application reads and writes
+ALTER TABLE promotion_history RENAME COLUMN promoted_by_user_id TO promoted_by;
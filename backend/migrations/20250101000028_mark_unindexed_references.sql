@@ -0,0 +1,7 @@
+-- Variables written before references were recorded have none indexed yet; NULL marks them
+-- for the background backfill, which records the keys their data references
+ALTER TABLE variables ALTER COLUMN referenced_keys DROP DEFAULT;
+ALTER TABLE variables ALTER COLUMN referenced_keys DROP NOT NULL;
+
+UPDATE variables SET referenced_keys = NULL
+WHERE referenced_keys = '{}' AND NOT is_encrypted;
@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::dto::{EncryptionStatusResponse, KeyRotationListResponse, KeyRotationResponse};
+use crate::error::{AppError, Result};
+use crate::repositories::KeyRotationRepository;
+use crate::services::{resume_rotation, start_rotation};
+use crate::storage::{FileStorage, VariableStore};
+use crate::utils::Claims;
+
+/// Requires `Permission::EncryptionRead`.
+pub async fn get_encryption_status(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+) -> Result<Json<EncryptionStatusResponse>> {
+    let running_rotation = KeyRotationRepository::new(pool).find_running().await?;
+
+    Ok(Json(EncryptionStatusResponse {
+        enabled: storage.key_version().is_some(),
+        current_key_version: storage.key_version(),
+        running_rotation,
+    }))
+}
+
+/// Requires `Permission::EncryptionWrite`. Starts re-wrapping every data key under the
+/// current master key in the background; poll the rotation for progress.
+pub async fn create_key_rotation(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    Extension(claims): Extension<Claims>,
+) -> Result<(StatusCode, Json<KeyRotationResponse>)> {
+    let rotation = start_rotation(&pool, &storage, claims.user_id()?).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(KeyRotationResponse { rotation })))
+}
+
+/// Requires `Permission::EncryptionRead`.
+pub async fn list_key_rotations(
+    State(pool): State<Pool<Postgres>>,
+) -> Result<Json<KeyRotationListResponse>> {
+    let rotations = KeyRotationRepository::new(pool).list().await?;
+
+    Ok(Json(KeyRotationListResponse { rotations }))
+}
+
+/// Requires `Permission::EncryptionRead`.
+pub async fn get_key_rotation(
+    State(pool): State<Pool<Postgres>>,
+    Path(rotation_id): Path<Uuid>,
+) -> Result<Json<KeyRotationResponse>> {
+    let rotation = KeyRotationRepository::new(pool)
+        .find_by_id(rotation_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Key rotation not found".to_string()))?;
+
+    Ok(Json(KeyRotationResponse { rotation }))
+}
+
+/// Requires `Permission::EncryptionWrite`. Continues a failed rotation after the last
+/// variable it processed.
+pub async fn resume_key_rotation(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    Path(rotation_id): Path<Uuid>,
+) -> Result<(StatusCode, Json<KeyRotationResponse>)> {
+    let rotation = resume_rotation(&pool, &storage, rotation_id).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(KeyRotationResponse { rotation })))
+}
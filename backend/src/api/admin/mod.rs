@@ -1,7 +1,9 @@
+pub mod encryption;
 pub mod promotions;
 pub mod tiers;
 pub mod users;
 
+pub use encryption::*;
 pub use promotions::*;
 pub use tiers::*;
 pub use users::*;
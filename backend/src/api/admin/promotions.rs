@@ -7,11 +7,12 @@ use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::dto::{PromoteUserRequest, PromotionResponse};
+use crate::dto::{PromoteUserRequest, PromotionHistoryResponse, PromotionResponse};
 use crate::error::{AppError, Result};
 use crate::repositories::{PromotionRepository, UserRepository};
 use crate::utils::Claims;
 
+/// Requires `Permission::BillingWrite`.
 pub async fn promote_user(
     State(pool): State<Pool<Postgres>>,
     Extension(claims): Extension<Claims>,
@@ -57,3 +58,14 @@ pub async fn promote_user(
         }),
     ))
 }
+
+/// Requires `Permission::BillingRead`.
+pub async fn list_user_promotions(
+    State(pool): State<Pool<Postgres>>,
+    Path(user_id): Path<Uuid>,
+) -> Result<Json<PromotionHistoryResponse>> {
+    let promo_repo = PromotionRepository::new(pool);
+    let promotions = promo_repo.list_by_user(user_id).await?;
+
+    Ok(Json(PromotionHistoryResponse { promotions }))
+}
@@ -11,6 +11,7 @@ use crate::dto::{CreateTierRequest, TierListResponse, TierResponse, UpdateTierRe
 use crate::error::{AppError, Result};
 use crate::repositories::TierRepository;
 
+/// Requires `Permission::TiersWrite`.
 pub async fn create_tier(
     State(pool): State<Pool<Postgres>>,
     Json(payload): Json<CreateTierRequest>,
@@ -27,6 +28,8 @@ pub async fn create_tier(
             payload.max_variable_size_mb,
             payload.max_requests_per_day,
             payload.max_api_keys,
+            payload.max_versions,
+            payload.trash_retention_days,
             payload.price_monthly,
         )
         .await?;
@@ -34,6 +37,7 @@ pub async fn create_tier(
     Ok((StatusCode::CREATED, Json(TierResponse { tier })))
 }
 
+/// Requires `Permission::TiersRead`.
 pub async fn list_tiers(
     State(pool): State<Pool<Postgres>>,
 ) -> Result<Json<TierListResponse>> {
@@ -43,6 +47,7 @@ pub async fn list_tiers(
     Ok(Json(TierListResponse { tiers }))
 }
 
+/// Requires `Permission::TiersRead`.
 pub async fn get_tier(
     State(pool): State<Pool<Postgres>>,
     Path(tier_id): Path<Uuid>,
@@ -57,6 +62,7 @@ pub async fn get_tier(
     Ok(Json(TierResponse { tier }))
 }
 
+/// Requires `Permission::TiersWrite`.
 pub async fn update_tier(
     State(pool): State<Pool<Postgres>>,
     Path(tier_id): Path<Uuid>,
@@ -75,6 +81,8 @@ pub async fn update_tier(
             payload.max_variable_size_mb,
             payload.max_requests_per_day,
             payload.max_api_keys,
+            payload.max_versions,
+            payload.trash_retention_days,
             payload.price_monthly,
             payload.is_active,
         )
@@ -83,6 +91,7 @@ pub async fn update_tier(
     Ok(Json(TierResponse { tier }))
 }
 
+/// Requires `Permission::TiersWrite`.
 pub async fn delete_tier(
     State(pool): State<Pool<Postgres>>,
     Path(tier_id): Path<Uuid>,
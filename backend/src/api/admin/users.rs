@@ -1,15 +1,20 @@
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
-    Json,
+    Extension, Json,
 };
 use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
-use crate::dto::{UpdateUserRequest, UserManagementResponse, UserQueryParams};
-use crate::error::Result;
+use crate::dto::{
+    UpdateUserRequest, UpdateUserRoleRequest, UserManagementResponse, UserQueryParams,
+};
+use crate::error::{AppError, Result};
+use crate::models::PublicUser;
 use crate::repositories::UserRepository;
+use crate::utils::Claims;
 
+/// Requires `Permission::UsersRead`.
 pub async fn list_users(
     State(pool): State<Pool<Postgres>>,
     Query(params): Query<UserQueryParams>,
@@ -17,7 +22,7 @@ pub async fn list_users(
     let user_repo = UserRepository::new(pool);
 
     let page = params.page.unwrap_or(1).max(1);
-    let page_size = params.page_size.unwrap_or(20).min(100).max(1);
+    let page_size = params.page_size.unwrap_or(20).clamp(1, 100);
 
     let (users, total) = user_repo
         .list(page, page_size, params.search.as_deref())
@@ -33,6 +38,7 @@ pub async fn list_users(
     }))
 }
 
+/// Requires `Permission::UsersWrite`.
 pub async fn update_user(
     State(pool): State<Pool<Postgres>>,
     Path(user_id): Path<Uuid>,
@@ -47,6 +53,7 @@ pub async fn update_user(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Requires `Permission::UsersDelete`.
 pub async fn delete_user(
     State(pool): State<Pool<Postgres>>,
     Path(user_id): Path<Uuid>,
@@ -56,3 +63,21 @@ pub async fn delete_user(
 
     Ok(StatusCode::NO_CONTENT)
 }
+
+/// Requires `Permission::UsersManageRoles`.
+pub async fn update_user_role(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<Uuid>,
+    Json(payload): Json<UpdateUserRoleRequest>,
+) -> Result<Json<PublicUser>> {
+    // Changing one's own role could lock the last admin out
+    if claims.user_id()? == user_id {
+        return Err(AppError::BadRequest("Cannot change your own role".to_string()));
+    }
+
+    let user_repo = UserRepository::new(pool);
+    let user = user_repo.update_role(user_id, payload.role).await?;
+
+    Ok(Json(user.sanitize()))
+}
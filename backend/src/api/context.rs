@@ -0,0 +1,314 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{FromRef, FromRequestParts, RawPathParams},
+    http::{request::Parts, HeaderMap},
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{AccessibleVariable, Environment, OrgRole, Owner, Project, Tier};
+use crate::policy::{Action, PolicyEngine, PolicyRequest, Principal, Resource};
+use crate::repositories::{
+    EnvironmentRepository, OrganizationRepository, ProjectRepository, TierRepository,
+    UserRepository,
+};
+use crate::utils::Claims;
+
+/// Header selecting the organization a request acts on; absent means the caller's own account
+pub const ORGANIZATION_HEADER: &str = "x-organization-id";
+
+/// The owner a request acts on, together with the caller's role and the tier that applies
+#[derive(Debug, Clone)]
+pub struct OwnerContext {
+    pub owner: Owner,
+    pub user_id: Uuid,
+    pub role: OrgRole,
+    pub tier_id: Uuid,
+}
+
+impl OwnerContext {
+    /// Resolve the owner from the `X-Organization-Id` header, verifying membership.
+    /// Without the header the caller acts on their own account with full rights.
+    /// API keys always act on the owner they were issued for, with editor rights.
+    pub async fn resolve(pool: &Pool<Postgres>, claims: &Claims, headers: &HeaderMap) -> Result<Self> {
+        let user_id = claims.user_id()?;
+
+        if claims.is_api_key() {
+            let mut ctx = match claims.organization_id {
+                Some(org_id) => Self::for_organization(pool, org_id, user_id).await?,
+                None => Self {
+                    owner: Owner::User(user_id),
+                    user_id,
+                    role: OrgRole::Owner,
+                    tier_id: claims.tier_id()?,
+                },
+            };
+            ctx.role = ctx.role.min(OrgRole::Editor);
+            return Ok(ctx);
+        }
+
+        let org_id = match headers.get(ORGANIZATION_HEADER) {
+            Some(value) => value
+                .to_str()
+                .ok()
+                .and_then(|v| Uuid::parse_str(v.trim()).ok())
+                .ok_or_else(|| AppError::BadRequest("Invalid organization ID".to_string()))?,
+            None => {
+                return Ok(Self {
+                    owner: Owner::User(user_id),
+                    user_id,
+                    role: OrgRole::Owner,
+                    tier_id: claims.tier_id()?,
+                });
+            }
+        };
+
+        Self::for_organization(pool, org_id, user_id).await
+    }
+
+    /// Resolve the context for an explicit organization, verifying membership
+    pub async fn for_organization(pool: &Pool<Postgres>, org_id: Uuid, user_id: Uuid) -> Result<Self> {
+        let org_repo = OrganizationRepository::new(pool.clone());
+
+        let member = org_repo
+            .find_member(org_id, user_id)
+            .await?
+            .ok_or_else(|| AppError::Authorization("Not a member of this organization".to_string()))?;
+
+        let organization = org_repo
+            .find_by_id(org_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Organization not found".to_string()))?;
+
+        Ok(Self {
+            owner: Owner::Organization(org_id),
+            user_id,
+            role: member.role,
+            tier_id: organization.tier_id,
+        })
+    }
+
+    /// Fail unless the caller holds at least `role` for this owner
+    pub fn require(&self, role: OrgRole) -> Result<()> {
+        if self.role < role {
+            return Err(AppError::Authorization(format!(
+                "Organization role '{}' required",
+                role
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// The tier whose limits apply to variables of `owner`
+pub async fn owner_tier_id(pool: &Pool<Postgres>, owner: Owner) -> Result<Uuid> {
+    match owner {
+        Owner::User(user_id) => UserRepository::new(pool.clone())
+            .find_by_id(user_id)
+            .await?
+            .map(|user| user.tier_id)
+            .ok_or_else(|| AppError::NotFound("User not found".to_string())),
+        Owner::Organization(org_id) => OrganizationRepository::new(pool.clone())
+            .find_by_id(org_id)
+            .await?
+            .map(|org| org.tier_id)
+            .ok_or_else(|| AppError::NotFound("Organization not found".to_string())),
+    }
+}
+
+/// The environment a variables request operates on.
+///
+/// Routes nested under `/api/projects/{project}/envs/{env}` address an environment by
+/// name; the flat `/api/variables` routes use the owner's default environment, or the
+/// environment an API key is restricted to.
+#[derive(Debug, Clone)]
+pub struct VariableScope {
+    pub ctx: OwnerContext,
+    pub project: Project,
+    pub environment: Environment,
+    /// Whether variables shared with the caller are visible alongside the environment's own
+    pub shares_visible: bool,
+    /// The caller, holding their role for the owner
+    pub principal: Principal,
+    pub policy: Arc<PolicyEngine>,
+}
+
+impl VariableScope {
+    pub async fn resolve(
+        pool: &Pool<Postgres>,
+        policy: Arc<PolicyEngine>,
+        claims: &Claims,
+        headers: &HeaderMap,
+        names: Option<(&str, &str)>,
+    ) -> Result<Self> {
+        let ctx = OwnerContext::resolve(pool, claims, headers).await?;
+
+        let project_repo = ProjectRepository::new(pool.clone());
+        let env_repo = EnvironmentRepository::new(pool.clone());
+
+        let (project, environment) = match (names, claims.environment_id) {
+            (Some((project_name, env_name)), _) => {
+                let project = project_repo
+                    .find_by_name(ctx.owner, project_name)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?;
+                let environment = env_repo
+                    .find_by_name(project.id, env_name)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("Environment not found".to_string()))?;
+                (project, environment)
+            }
+            (None, Some(env_id)) => {
+                let environment = env_repo
+                    .find_by_id(env_id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("Environment not found".to_string()))?;
+                let project = project_repo
+                    .find_by_id(environment.project_id)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?;
+                (project, environment)
+            }
+            (None, None) => env_repo.get_or_create_default(ctx.owner, ctx.user_id).await?,
+        };
+
+        if let Some(env_id) = claims.environment_id
+            && env_id != environment.id
+        {
+            return Err(AppError::Authorization(
+                "API key is restricted to another environment".to_string(),
+            ));
+        }
+
+        // Shares are granted to a user, so they surface on the flat routes of their
+        // personal account but not inside projects, organizations or restricted keys
+        let shares_visible = names.is_none()
+            && claims.environment_id.is_none()
+            && matches!(ctx.owner, Owner::User(_));
+
+        let mut principal = Principal::from_claims(claims)?;
+        principal.org_role = Some(ctx.role);
+
+        Ok(Self {
+            ctx,
+            project,
+            environment,
+            shares_visible,
+            principal,
+            policy,
+        })
+    }
+
+    /// The user whose shares apply to this request, if any
+    pub fn shared_with(&self) -> Option<Uuid> {
+        self.shares_visible.then_some(self.ctx.user_id)
+    }
+
+    /// The tier whose limits apply to a variable; shared ones count against their owner's
+    pub async fn variable_tier(
+        &self,
+        pool: &Pool<Postgres>,
+        accessible: &AccessibleVariable,
+    ) -> Result<Tier> {
+        let tier_id = if accessible.is_shared() {
+            owner_tier_id(pool, accessible.variable.owner()).await?
+        } else {
+            self.ctx.tier_id
+        };
+
+        TierRepository::new(pool.clone())
+            .find_by_id(tier_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))
+    }
+
+    /// A resource in this scope's environment
+    pub fn resource(&self, key: Option<&str>, tags: Vec<String>) -> Resource {
+        Resource {
+            key: key.map(str::to_string),
+            tags,
+            project: Some(self.project.name.clone()),
+            environment: Some(self.environment.name.clone()),
+        }
+    }
+
+    /// Fail unless policies allow the caller `action` on `resource`
+    pub fn authorize(&self, action: Action, resource: Resource) -> Result<()> {
+        self.policy.authorize(&PolicyRequest {
+            principal: self.principal.clone(),
+            action,
+            resource,
+        })
+    }
+
+    /// Authorize `action` on a variable, through its share when it isn't the scope's own
+    pub fn authorize_variable(
+        &self,
+        action: Action,
+        accessible: &AccessibleVariable,
+    ) -> Result<()> {
+        let variable = &accessible.variable;
+
+        match accessible.share_permission {
+            Some(permission) => {
+                let mut principal = self.principal.clone();
+                principal.org_role = None;
+                principal.share = Some(permission);
+
+                self.policy.authorize(&PolicyRequest {
+                    principal,
+                    action,
+                    resource: Resource::for_variable(variable, None, None),
+                })
+            }
+            None => self.authorize(
+                action,
+                self.resource(Some(&variable.key), variable.tag_list()),
+            ),
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for VariableScope
+where
+    Pool<Postgres>: FromRef<S>,
+    Arc<PolicyEngine>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self> {
+        let pool = Pool::<Postgres>::from_ref(state);
+        let policy = Arc::<PolicyEngine>::from_ref(state);
+
+        let claims = parts
+            .extensions
+            .get::<Claims>()
+            .cloned()
+            .ok_or_else(|| AppError::Authentication("User not authenticated".to_string()))?;
+
+        let params = RawPathParams::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        };
+
+        let names = param("project").zip(param("env"));
+
+        Self::resolve(
+            &pool,
+            policy,
+            &claims,
+            &parts.headers,
+            names.as_ref().map(|(p, e)| (p.as_str(), e.as_str())),
+        )
+        .await
+    }
+}
@@ -0,0 +1,165 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use std::sync::Arc;
+
+use sqlx::{Pool, Postgres};
+use validator::Validate;
+
+use crate::api::context::OwnerContext;
+use crate::dto::{
+    ApplyPromotionRequest, PromotionListResponse, PromotionPlan, PromotionPlanRequest,
+};
+use crate::error::{AppError, Result};
+use crate::models::{Environment, EnvironmentPromotion, OrgRole, Project, Variable};
+use crate::policy::{Action, PolicyEngine, PolicyRequest, Principal, Resource};
+use crate::repositories::{
+    EnvironmentPromotionRepository, EnvironmentRepository, ProjectRepository, TierRepository,
+};
+use crate::services::{apply_promotion, plan_promotion};
+use crate::storage::FileStorage;
+use crate::utils::Claims;
+
+/// Resolve the caller's context and the project and both environments of a promotion
+async fn promotion_scope(
+    pool: &Pool<Postgres>,
+    claims: &Claims,
+    headers: &HeaderMap,
+    project_name: &str,
+    request: &PromotionPlanRequest,
+) -> Result<(OwnerContext, Project, Environment, Environment)> {
+    request.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    if request.source == request.target {
+        return Err(AppError::BadRequest(
+            "Source and target environments must differ".to_string(),
+        ));
+    }
+
+    // Promotions span environments, which an environment-restricted key can't see
+    if claims.environment_id.is_some() {
+        return Err(AppError::Authorization(
+            "API key is restricted to a single environment".to_string(),
+        ));
+    }
+
+    let ctx = OwnerContext::resolve(pool, claims, headers).await?;
+
+    let project = ProjectRepository::new(pool.clone())
+        .find_by_name(ctx.owner, project_name)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?;
+
+    let env_repo = EnvironmentRepository::new(pool.clone());
+    let source = env_repo
+        .find_by_name(project.id, &request.source)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Source environment not found".to_string()))?;
+    let target = env_repo
+        .find_by_name(project.id, &request.target)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Target environment not found".to_string()))?;
+
+    Ok((ctx, project, source, target))
+}
+
+/// Check each step of a promotion against the policy for the caller
+fn promotion_authorizer<'a>(
+    policy: &'a PolicyEngine,
+    claims: &Claims,
+    ctx: &OwnerContext,
+    project: &'a Project,
+) -> Result<impl Fn(Action, &Variable, &Environment) -> Result<()> + 'a> {
+    let mut principal = Principal::from_claims(claims)?;
+    principal.org_role = Some(ctx.role);
+
+    Ok(move |action, variable: &Variable, environment: &Environment| {
+        policy.authorize(&PolicyRequest {
+            principal: principal.clone(),
+            action,
+            resource: Resource::for_variable(
+                variable,
+                Some(&project.name),
+                Some(&environment.name),
+            ),
+        })
+    })
+}
+
+pub async fn plan_environment_promotion(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    State(policy): State<Arc<PolicyEngine>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(project_name): Path<String>,
+    Json(payload): Json<PromotionPlanRequest>,
+) -> Result<Json<PromotionPlan>> {
+    let (ctx, project, source, target) =
+        promotion_scope(&pool, &claims, &headers, &project_name, &payload).await?;
+    let authorize = promotion_authorizer(&policy, &claims, &ctx, &project)?;
+
+    let plan = plan_promotion(&pool, &storage, &payload, &source, &target, authorize).await?;
+
+    Ok(Json(plan))
+}
+
+pub async fn apply_environment_promotion(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    State(policy): State<Arc<PolicyEngine>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(project_name): Path<String>,
+    Json(payload): Json<ApplyPromotionRequest>,
+) -> Result<(StatusCode, Json<EnvironmentPromotion>)> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let (ctx, project, source, target) =
+        promotion_scope(&pool, &claims, &headers, &project_name, &payload.plan).await?;
+    ctx.require(OrgRole::Editor)?;
+    let authorize = promotion_authorizer(&policy, &claims, &ctx, &project)?;
+
+    let tier = TierRepository::new(pool.clone())
+        .find_by_id(ctx.tier_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))?;
+
+    let promotion = apply_promotion(
+        &pool,
+        &storage,
+        ctx.owner,
+        ctx.user_id,
+        &tier,
+        &project,
+        &source,
+        &target,
+        &payload.plan,
+        &payload.fingerprint,
+        authorize,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(promotion)))
+}
+
+pub async fn list_environment_promotions(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(project_name): Path<String>,
+) -> Result<Json<PromotionListResponse>> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+
+    let project = ProjectRepository::new(pool.clone())
+        .find_by_name(ctx.owner, &project_name)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?;
+
+    let promotion_repo = EnvironmentPromotionRepository::new(pool);
+    let promotions = promotion_repo.list_by_project(project.id).await?;
+
+    Ok(Json(PromotionListResponse { promotions }))
+}
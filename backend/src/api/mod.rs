@@ -1,11 +1,45 @@
 pub mod admin;
 pub mod auth;
+pub mod context;
+pub mod environment_promotions;
 pub mod health;
+pub mod organizations;
+pub mod policy;
+pub mod projects;
+pub mod public;
+pub mod schemas;
+pub mod tags;
+pub mod trash;
 pub mod users;
+pub mod variable_batch;
+pub mod variable_keys;
+pub mod variable_query;
+pub mod variable_references;
+pub mod variable_schedules;
+pub mod variable_schemas;
+pub mod variable_shares;
+pub mod variable_versions;
 pub mod variables;
 
 pub use admin::*;
 pub use auth::*;
+pub use context::*;
+pub use environment_promotions::*;
 pub use health::*;
+pub use organizations::*;
+pub use policy::*;
+pub use projects::*;
+pub use public::*;
+pub use schemas::*;
+pub use tags::*;
+pub use trash::*;
 pub use users::*;
+pub use variable_batch::*;
+pub use variable_keys::*;
+pub use variable_query::*;
+pub use variable_references::*;
+pub use variable_schedules::*;
+pub use variable_schemas::*;
+pub use variable_shares::*;
+pub use variable_versions::*;
 pub use variables::*;
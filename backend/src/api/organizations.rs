@@ -0,0 +1,348 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::context::OwnerContext;
+use crate::dto::{
+    CreateInvitationRequest, CreateOrganizationRequest, InvitationListResponse,
+    MemberListResponse, OrganizationListResponse, OrganizationResponse, UpdateMemberRequest,
+    UpdateOrganizationRequest,
+};
+use crate::error::{AppError, Result};
+use crate::models::{OrgRole, OrganizationInvitation, OrganizationMember};
+use crate::repositories::{OrganizationRepository, TierRepository, VariableRepository};
+use crate::storage::{FileStorage, VariableStore};
+use crate::utils::Claims;
+
+const DEFAULT_INVITATION_EXPIRY_DAYS: i64 = 7;
+
+pub async fn create_organization(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateOrganizationRequest>,
+) -> Result<(StatusCode, Json<OrganizationResponse>)> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let user_id = claims.user_id()?;
+    let org_repo = OrganizationRepository::new(pool.clone());
+    let tier_repo = TierRepository::new(pool);
+
+    // New organizations start on the free/default tier
+    let tiers = tier_repo.list_active().await?;
+    let default_tier = tiers
+        .into_iter()
+        .find(|t| t.price_monthly == 0)
+        .ok_or_else(|| AppError::InternalServer("No default tier available".to_string()))?;
+
+    let organization = org_repo
+        .create(&payload.name, default_tier.id, user_id)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(OrganizationResponse {
+            organization,
+            role: OrgRole::Owner,
+        }),
+    ))
+}
+
+pub async fn list_organizations(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<OrganizationListResponse>> {
+    let user_id = claims.user_id()?;
+    let org_repo = OrganizationRepository::new(pool);
+
+    let organizations = org_repo.list_by_member(user_id).await?;
+
+    Ok(Json(OrganizationListResponse { organizations }))
+}
+
+pub async fn get_organization(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path(org_id): Path<Uuid>,
+) -> Result<Json<OrganizationResponse>> {
+    let ctx = OwnerContext::for_organization(&pool, org_id, claims.user_id()?).await?;
+    let org_repo = OrganizationRepository::new(pool);
+
+    let organization = org_repo
+        .find_by_id(org_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Organization not found".to_string()))?;
+
+    Ok(Json(OrganizationResponse {
+        organization,
+        role: ctx.role,
+    }))
+}
+
+pub async fn update_organization(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path(org_id): Path<Uuid>,
+    Json(payload): Json<UpdateOrganizationRequest>,
+) -> Result<Json<OrganizationResponse>> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let ctx = OwnerContext::for_organization(&pool, org_id, claims.user_id()?).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    let org_repo = OrganizationRepository::new(pool);
+    let organization = org_repo.update_name(org_id, &payload.name).await?;
+
+    Ok(Json(OrganizationResponse {
+        organization,
+        role: ctx.role,
+    }))
+}
+
+pub async fn delete_organization(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    Extension(claims): Extension<Claims>,
+    Path(org_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    let ctx = OwnerContext::for_organization(&pool, org_id, claims.user_id()?).await?;
+    ctx.require(OrgRole::Owner)?;
+
+    let var_repo = VariableRepository::new(pool.clone());
+    let org_repo = OrganizationRepository::new(pool);
+
+    // Variable rows cascade with the organization, but their files must be removed explicitly
+    let storage_paths = var_repo.list_storage_paths(ctx.owner).await?;
+
+    org_repo.delete(org_id).await?;
+
+    for path in storage_paths {
+        storage.delete(&path).await?;
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_members(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path(org_id): Path<Uuid>,
+) -> Result<Json<MemberListResponse>> {
+    OwnerContext::for_organization(&pool, org_id, claims.user_id()?).await?;
+    let org_repo = OrganizationRepository::new(pool);
+
+    let members = org_repo.list_members(org_id).await?;
+
+    Ok(Json(MemberListResponse { members }))
+}
+
+pub async fn update_member(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path((org_id, member_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<UpdateMemberRequest>,
+) -> Result<Json<OrganizationMember>> {
+    let ctx = OwnerContext::for_organization(&pool, org_id, claims.user_id()?).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    let org_repo = OrganizationRepository::new(pool);
+
+    let member = org_repo
+        .find_member(org_id, member_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+
+    // Only owners may grant or take away ownership
+    if (member.role.is_owner() || payload.role.is_owner()) && !ctx.role.is_owner() {
+        return Err(AppError::Authorization(
+            "Only owners can change ownership".to_string(),
+        ));
+    }
+
+    if member.role.is_owner()
+        && !payload.role.is_owner()
+        && org_repo.count_owners(org_id).await? <= 1
+    {
+        return Err(AppError::Conflict(
+            "An organization must keep at least one owner".to_string(),
+        ));
+    }
+
+    let member = org_repo
+        .update_member_role(org_id, member_id, payload.role)
+        .await?;
+
+    Ok(Json(member))
+}
+
+pub async fn remove_member(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path((org_id, member_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    let ctx = OwnerContext::for_organization(&pool, org_id, claims.user_id()?).await?;
+    let org_repo = OrganizationRepository::new(pool);
+
+    let member = org_repo
+        .find_member(org_id, member_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+
+    // Members may always leave; removing others needs admin, and owners only by owners
+    if member_id != ctx.user_id {
+        ctx.require(OrgRole::Admin)?;
+        if member.role > ctx.role {
+            return Err(AppError::Authorization(
+                "Cannot remove a member with a higher role".to_string(),
+            ));
+        }
+    }
+
+    if member.role.is_owner() && org_repo.count_owners(org_id).await? <= 1 {
+        return Err(AppError::Conflict(
+            "An organization must keep at least one owner".to_string(),
+        ));
+    }
+
+    org_repo.remove_member(org_id, member_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn create_invitation(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path(org_id): Path<Uuid>,
+    Json(payload): Json<CreateInvitationRequest>,
+) -> Result<(StatusCode, Json<OrganizationInvitation>)> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let ctx = OwnerContext::for_organization(&pool, org_id, claims.user_id()?).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    if payload.role > ctx.role {
+        return Err(AppError::Authorization(
+            "Cannot invite with a role higher than your own".to_string(),
+        ));
+    }
+
+    let org_repo = OrganizationRepository::new(pool);
+
+    let invitation = org_repo
+        .create_invitation(
+            org_id,
+            &payload.email,
+            payload.role,
+            ctx.user_id,
+            payload.expires_in_days.unwrap_or(DEFAULT_INVITATION_EXPIRY_DAYS),
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(invitation)))
+}
+
+pub async fn list_invitations(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path(org_id): Path<Uuid>,
+) -> Result<Json<InvitationListResponse>> {
+    let ctx = OwnerContext::for_organization(&pool, org_id, claims.user_id()?).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    let org_repo = OrganizationRepository::new(pool);
+    let invitations = org_repo.list_invitations(org_id).await?;
+
+    Ok(Json(InvitationListResponse { invitations }))
+}
+
+pub async fn revoke_invitation(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path((org_id, invitation_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode> {
+    let ctx = OwnerContext::for_organization(&pool, org_id, claims.user_id()?).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    let org_repo = OrganizationRepository::new(pool);
+    org_repo.delete_invitation(invitation_id, org_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_my_invitations(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<InvitationListResponse>> {
+    let org_repo = OrganizationRepository::new(pool);
+    let invitations = org_repo
+        .list_pending_invitations_for_email(&claims.email)
+        .await?;
+
+    Ok(Json(InvitationListResponse { invitations }))
+}
+
+/// Load an invitation addressed to the caller that can still be acted upon
+async fn find_pending_invitation(
+    org_repo: &OrganizationRepository,
+    invitation_id: Uuid,
+    claims: &Claims,
+) -> Result<OrganizationInvitation> {
+    let invitation = org_repo
+        .find_invitation(invitation_id)
+        .await?
+        .filter(|inv| inv.email.eq_ignore_ascii_case(&claims.email))
+        .ok_or_else(|| AppError::NotFound("Invitation not found".to_string()))?;
+
+    if !invitation.is_pending() {
+        return Err(AppError::Conflict(
+            "Invitation has expired or was already accepted".to_string(),
+        ));
+    }
+
+    Ok(invitation)
+}
+
+pub async fn accept_invitation(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path(invitation_id): Path<Uuid>,
+) -> Result<Json<OrganizationMember>> {
+    let user_id = claims.user_id()?;
+    let org_repo = OrganizationRepository::new(pool);
+
+    let invitation = find_pending_invitation(&org_repo, invitation_id, &claims).await?;
+
+    // Accepting must never demote an existing member
+    if let Some(existing) = org_repo
+        .find_member(invitation.organization_id, user_id)
+        .await?
+        && existing.role >= invitation.role
+    {
+        return Err(AppError::Conflict(
+            "Already a member of this organization".to_string(),
+        ));
+    }
+
+    let member = org_repo.accept_invitation(&invitation, user_id).await?;
+
+    Ok(Json(member))
+}
+
+pub async fn decline_invitation(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    Path(invitation_id): Path<Uuid>,
+) -> Result<StatusCode> {
+    let org_repo = OrganizationRepository::new(pool);
+
+    let invitation = find_pending_invitation(&org_repo, invitation_id, &claims).await?;
+    org_repo
+        .delete_invitation(invitation.id, invitation.organization_id)
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,51 @@
+use std::sync::Arc;
+
+use axum::{extract::State, http::HeaderMap, Extension, Json};
+use sqlx::{Pool, Postgres};
+
+use crate::api::context::OwnerContext;
+use crate::dto::{ExplainPolicyRequest, ExplainPolicyResponse};
+use crate::error::Result;
+use crate::models::OrgRole;
+use crate::policy::{PolicyEngine, PolicyRequest, Principal};
+use crate::utils::Claims;
+
+/// Dry run: evaluate a request against the policy without performing it,
+/// reporting which rules applied and why
+pub async fn explain_policy(
+    State(pool): State<Pool<Postgres>>,
+    State(policy): State<Arc<PolicyEngine>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Json(payload): Json<ExplainPolicyRequest>,
+) -> Result<Json<ExplainPolicyResponse>> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+
+    let mut principal = Principal::from_claims(&claims)?;
+    principal.org_role = Some(ctx.role);
+
+    // API keys never act with more than editor rights
+    if let Some(scopes) = payload.api_key_scopes {
+        principal.api_key = true;
+        principal.scopes = scopes;
+        principal.org_role = Some(ctx.role.min(OrgRole::Editor));
+    }
+    if let Some(permission) = payload.share {
+        principal.org_role = None;
+        principal.share = Some(permission);
+    }
+
+    let request = PolicyRequest {
+        principal,
+        action: payload.action,
+        resource: payload.resource,
+    };
+    let decision = policy.evaluate(&request);
+
+    Ok(Json(ExplainPolicyResponse {
+        action: request.action,
+        principal: request.principal,
+        resource: request.resource,
+        decision,
+    }))
+}
@@ -0,0 +1,319 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::context::{OwnerContext, VariableScope};
+use crate::dto::{
+    CreateEnvironmentRequest, CreateProjectRequest, EnvironmentListResponse, ProjectListResponse,
+    ProjectResponse, ResolveQueryParams, ResolveResponse, UpdateEnvironmentRequest,
+};
+use crate::error::{AppError, Result};
+use crate::models::{Environment, OrgRole, Project, Variable, MAX_ENVIRONMENT_DEPTH};
+use crate::policy::{Action, Resource};
+use crate::repositories::{EnvironmentRepository, ProjectRepository, VariableRepository};
+use crate::services::resolve_environment;
+use crate::storage::{FileStorage, VariableStore};
+use crate::utils::{validate_namespace_name, Claims};
+
+/// Look up one of the owner's projects by name
+async fn find_project(pool: &Pool<Postgres>, ctx: &OwnerContext, name: &str) -> Result<Project> {
+    ProjectRepository::new(pool.clone())
+        .find_by_name(ctx.owner, name)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Project not found".to_string()))
+}
+
+/// Look up an environment of `project` by name
+async fn find_environment(
+    env_repo: &EnvironmentRepository,
+    project: &Project,
+    name: &str,
+) -> Result<Environment> {
+    env_repo
+        .find_by_name(project.id, name)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Environment not found".to_string()))
+}
+
+/// Resolve the parent environment named `parent_name` for `environment_id`
+/// (`None` when creating), rejecting cycles and chains that grow too deep
+async fn find_parent(
+    env_repo: &EnvironmentRepository,
+    project: &Project,
+    environment_id: Option<Uuid>,
+    parent_name: &str,
+) -> Result<Uuid> {
+    let parent = find_environment(env_repo, project, parent_name)
+        .await
+        .map_err(|_| AppError::BadRequest("Parent environment not found".to_string()))?;
+
+    let ancestry = env_repo.ancestry(parent.id).await?;
+    if let Some(id) = environment_id
+        && ancestry.iter().any(|e| e.id == id)
+    {
+        return Err(AppError::BadRequest(
+            "Parent would create an inheritance cycle".to_string(),
+        ));
+    }
+
+    let height = match environment_id {
+        Some(id) => env_repo.subtree_height(id).await?,
+        None => 1,
+    };
+    if ancestry.len() + height > MAX_ENVIRONMENT_DEPTH {
+        return Err(AppError::BadRequest(format!(
+            "Inheritance chains are limited to {} environments",
+            MAX_ENVIRONMENT_DEPTH
+        )));
+    }
+
+    Ok(parent.id)
+}
+
+/// Delete the data files of every variable in the given environments
+async fn delete_environment_files(
+    pool: &Pool<Postgres>,
+    storage: &FileStorage,
+    environments: &[Environment],
+) -> Result<()> {
+    let var_repo = VariableRepository::new(pool.clone());
+    let environment_ids: Vec<_> = environments.iter().map(|e| e.id).collect();
+
+    for path in var_repo.list_storage_paths_in(&environment_ids).await? {
+        storage.delete(&path).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn create_project(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateProjectRequest>,
+) -> Result<(StatusCode, Json<ProjectResponse>)> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+    validate_namespace_name(&payload.name).map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    let project_repo = ProjectRepository::new(pool);
+    let project = project_repo
+        .create(
+            ctx.owner,
+            ctx.user_id,
+            &payload.name,
+            payload.description.as_deref(),
+        )
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ProjectResponse {
+            project,
+            environments: Vec::new(),
+        }),
+    ))
+}
+
+pub async fn list_projects(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+) -> Result<Json<ProjectListResponse>> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    let project_repo = ProjectRepository::new(pool);
+
+    let projects = project_repo.list_by_owner(ctx.owner).await?;
+
+    Ok(Json(ProjectListResponse { projects }))
+}
+
+pub async fn get_project(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(project_name): Path<String>,
+) -> Result<Json<ProjectResponse>> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    let project = find_project(&pool, &ctx, &project_name).await?;
+
+    let env_repo = EnvironmentRepository::new(pool);
+    let environments = env_repo.list_by_project(project.id).await?;
+
+    Ok(Json(ProjectResponse {
+        project,
+        environments,
+    }))
+}
+
+pub async fn delete_project(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(project_name): Path<String>,
+) -> Result<StatusCode> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    let project = find_project(&pool, &ctx, &project_name).await?;
+
+    let env_repo = EnvironmentRepository::new(pool.clone());
+    let environments = env_repo.list_by_project(project.id).await?;
+    delete_environment_files(&pool, &storage, &environments).await?;
+
+    // Environments and their variables cascade with the project
+    let project_repo = ProjectRepository::new(pool);
+    project_repo.delete(project.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn create_environment(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(project_name): Path<String>,
+    Json(payload): Json<CreateEnvironmentRequest>,
+) -> Result<(StatusCode, Json<Environment>)> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+    validate_namespace_name(&payload.name).map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    let project = find_project(&pool, &ctx, &project_name).await?;
+
+    let env_repo = EnvironmentRepository::new(pool);
+    let parent_id = match payload.parent.as_deref() {
+        Some(parent) => Some(find_parent(&env_repo, &project, None, parent).await?),
+        None => None,
+    };
+
+    let environment = env_repo
+        .create(
+            project.id,
+            parent_id,
+            &payload.name,
+            payload.description.as_deref(),
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(environment)))
+}
+
+pub async fn update_environment(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path((project_name, env_name)): Path<(String, String)>,
+    Json(payload): Json<UpdateEnvironmentRequest>,
+) -> Result<Json<Environment>> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    let project = find_project(&pool, &ctx, &project_name).await?;
+
+    let env_repo = EnvironmentRepository::new(pool);
+    let environment = find_environment(&env_repo, &project, &env_name).await?;
+
+    let parent_id = match payload.parent {
+        Some(Some(ref parent)) => {
+            Some(find_parent(&env_repo, &project, Some(environment.id), parent).await?)
+        }
+        Some(None) => None,
+        None => environment.parent_id,
+    };
+    let description = payload.description.or(environment.description);
+
+    let environment = env_repo
+        .update(environment.id, parent_id, description.as_deref())
+        .await?;
+
+    Ok(Json(environment))
+}
+
+/// Effective variables of an environment after applying its parents
+pub async fn resolve_variables(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Query(params): Query<ResolveQueryParams>,
+) -> Result<Json<ResolveResponse>> {
+    scope.authorize(Action::VariableList, scope.resource(None, Vec::new()))?;
+
+    // Inherited values are only included where the caller may read them
+    let readable = |environment: &Environment, variable: &Variable| {
+        let resource = Resource::for_variable(
+            variable,
+            Some(&scope.project.name),
+            Some(&environment.name),
+        );
+        scope.authorize(Action::VariableRead, resource).is_ok()
+    };
+
+    let (chain, variables) = resolve_environment(
+        &pool,
+        &storage,
+        scope.environment.id,
+        params.mode,
+        params.key.as_deref(),
+        readable,
+    )
+    .await?;
+
+    Ok(Json(ResolveResponse {
+        project: scope.project.name,
+        environment: scope.environment.name,
+        mode: params.mode,
+        chain: chain.into_iter().map(|e| e.name).collect(),
+        variables,
+    }))
+}
+
+pub async fn list_environments(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(project_name): Path<String>,
+) -> Result<Json<EnvironmentListResponse>> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    let project = find_project(&pool, &ctx, &project_name).await?;
+
+    let env_repo = EnvironmentRepository::new(pool);
+    let environments = env_repo.list_by_project(project.id).await?;
+
+    Ok(Json(EnvironmentListResponse { environments }))
+}
+
+pub async fn delete_environment(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path((project_name, env_name)): Path<(String, String)>,
+) -> Result<StatusCode> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Admin)?;
+
+    let project = find_project(&pool, &ctx, &project_name).await?;
+
+    let env_repo = EnvironmentRepository::new(pool.clone());
+    let environment = find_environment(&env_repo, &project, &env_name).await?;
+
+    delete_environment_files(&pool, &storage, std::slice::from_ref(&environment)).await?;
+
+    // Variables cascade with the environment; children stop inheriting from it
+    env_repo.delete(environment.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,105 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::dto::{PublicVariablePath, ShareLinkQuery};
+use crate::error::{AppError, Result};
+use crate::models::Variable;
+use crate::repositories::{UsageRepository, VariableRepository, VariableShareLinkRepository};
+use crate::storage::{FileStorage, VariableStore};
+use crate::utils::{etag_matches, variable_etag, LinkSigner};
+
+/// How long shared caches may serve a public variable before revalidating
+const PUBLIC_CACHE_MAX_AGE_SECONDS: u32 = 60;
+
+fn last_modified(variable: &Variable) -> String {
+    variable
+        .updated_at
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+/// Serve a public variable's value without authentication.
+/// Responses are cacheable and revalidate with `If-None-Match`.
+pub async fn get_public_variable(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    Path(PublicVariablePath { owner, key }): Path<PublicVariablePath>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let variable = VariableRepository::new(pool.clone())
+        .find_public(owner, &key)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+
+    let etag = variable_etag(&variable);
+    let cache_headers = [
+        (
+            header::CACHE_CONTROL,
+            format!("public, max-age={}", PUBLIC_CACHE_MAX_AGE_SECONDS),
+        ),
+        (header::ETAG, etag.clone()),
+        (header::LAST_MODIFIED, last_modified(&variable)),
+    ];
+
+    let usage_repo = UsageRepository::new(pool);
+
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| etag_matches(v, &etag));
+    if not_modified {
+        usage_repo.record_read(variable.owner(), 0).await?;
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
+
+    let data = storage.retrieve(&variable.storage_path).await?;
+    usage_repo
+        .record_read(variable.owner(), variable.size_bytes)
+        .await?;
+
+    Ok((cache_headers, Json(data)).into_response())
+}
+
+/// Serve the variable behind a signed share link, while it is unexpired and not revoked
+pub async fn get_linked_variable(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    Path(link_id): Path<Uuid>,
+    Query(query): Query<ShareLinkQuery>,
+) -> Result<Response> {
+    if !LinkSigner::from_env().verify(link_id, query.expires, &query.signature) {
+        return Err(AppError::Authorization("Invalid link signature".to_string()));
+    }
+    if query.expires <= Utc::now().timestamp() {
+        return Err(AppError::Authorization("Link has expired".to_string()));
+    }
+
+    // Revoking deletes the link, which invalidates its signature
+    let link_repo = VariableShareLinkRepository::new(pool.clone());
+    let link = link_repo
+        .find_by_id(link_id)
+        .await?
+        .filter(|link| link.expires_at.timestamp() == query.expires && !link.is_expired())
+        .ok_or_else(|| AppError::NotFound("Link not found".to_string()))?;
+
+    let variable = VariableRepository::new(pool.clone())
+        .find(link.variable_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+
+    let data = storage.retrieve(&variable.storage_path).await?;
+
+    link_repo.record_use(link.id).await?;
+    UsageRepository::new(pool)
+        .record_read(variable.owner(), variable.size_bytes)
+        .await?;
+
+    Ok(([(header::CACHE_CONTROL, "private, no-store")], Json(data)).into_response())
+}
@@ -0,0 +1,138 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use sqlx::{Pool, Postgres};
+use validator::Validate;
+
+use crate::api::context::OwnerContext;
+use crate::dto::{
+    CreateJsonSchemaRequest, JsonSchemaListResponse, JsonSchemaPath, UpdateJsonSchemaRequest,
+    ValidateDataRequest, ValidateDataResponse,
+};
+use crate::error::{AppError, Result};
+use crate::models::{JsonSchema, OrgRole};
+use crate::repositories::JsonSchemaRepository;
+use crate::services::{compile_schema, schema_violations};
+use crate::utils::{validate_namespace_name, Claims};
+
+async fn find_schema(
+    pool: &Pool<Postgres>,
+    ctx: &OwnerContext,
+    path: JsonSchemaPath,
+) -> Result<JsonSchema> {
+    JsonSchemaRepository::new(pool.clone())
+        .find_by_id(ctx.owner, path.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Schema not found".to_string()))
+}
+
+/// Register a named schema variables of the owner can conform to
+pub async fn create_schema(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateJsonSchemaRequest>,
+) -> Result<(StatusCode, Json<JsonSchema>)> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+    validate_namespace_name(&payload.name).map_err(|e| AppError::Validation(e.to_string()))?;
+    compile_schema(&payload.schema)?;
+
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Editor)?;
+
+    let json_schema = JsonSchemaRepository::new(pool)
+        .create(
+            ctx.owner,
+            ctx.user_id,
+            &payload.name,
+            payload.description.as_deref(),
+            &payload.schema,
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(json_schema)))
+}
+
+pub async fn list_schemas(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+) -> Result<Json<JsonSchemaListResponse>> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+
+    let schemas = JsonSchemaRepository::new(pool).list_by_owner(ctx.owner).await?;
+
+    Ok(Json(JsonSchemaListResponse { schemas }))
+}
+
+pub async fn get_schema(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(path): Path<JsonSchemaPath>,
+) -> Result<Json<JsonSchema>> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+
+    Ok(Json(find_schema(&pool, &ctx, path).await?))
+}
+
+pub async fn update_schema(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(JsonSchemaPath { id }): Path<JsonSchemaPath>,
+    Json(payload): Json<UpdateJsonSchemaRequest>,
+) -> Result<Json<JsonSchema>> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+    if let Some(ref schema) = payload.schema {
+        compile_schema(schema)?;
+    }
+
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Editor)?;
+
+    let json_schema = JsonSchemaRepository::new(pool)
+        .update(ctx.owner, id, payload.description.as_deref(), payload.schema.as_ref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Schema not found".to_string()))?;
+
+    Ok(Json(json_schema))
+}
+
+/// Delete a named schema; fails while variables still conform to it
+pub async fn delete_schema(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(JsonSchemaPath { id }): Path<JsonSchemaPath>,
+) -> Result<StatusCode> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Editor)?;
+
+    if !JsonSchemaRepository::new(pool).delete(ctx.owner, id).await? {
+        return Err(AppError::NotFound("Schema not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Check data against a named schema without writing anything
+pub async fn validate_against_schema(
+    State(pool): State<Pool<Postgres>>,
+    Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
+    Path(path): Path<JsonSchemaPath>,
+    Json(payload): Json<ValidateDataRequest>,
+) -> Result<Json<ValidateDataResponse>> {
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    let json_schema = find_schema(&pool, &ctx, path).await?;
+
+    let violations = schema_violations(&compile_schema(&json_schema.schema)?, &payload.data);
+
+    Ok(Json(ValidateDataResponse {
+        valid: violations.is_empty(),
+        violations,
+    }))
+}
@@ -0,0 +1,89 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use sqlx::{Pool, Postgres};
+use validator::Validate;
+
+use crate::api::context::VariableScope;
+use crate::dto::{RenameTagRequest, TagChangeResponse, TagListResponse, TagPath};
+use crate::error::{AppError, Result};
+use crate::models::Tier;
+use crate::policy::Action;
+use crate::repositories::{TierRepository, VariableRepository};
+use crate::services::{renamed_tags, retag_environment};
+use crate::storage::FileStorage;
+
+async fn find_tier(pool: &Pool<Postgres>, scope: &VariableScope) -> Result<Tier> {
+    TierRepository::new(pool.clone())
+        .find_by_id(scope.ctx.tier_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))
+}
+
+/// Tags used in the scope's environment, with how many variables carry each
+pub async fn list_tags(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+) -> Result<Json<TagListResponse>> {
+    scope.authorize(Action::VariableList, scope.resource(None, Vec::new()))?;
+
+    let tags = VariableRepository::new(pool)
+        .tag_counts(scope.environment.id)
+        .await?;
+
+    Ok(Json(TagListResponse { tags }))
+}
+
+/// Rename a tag on every variable of the scope's environment carrying it
+pub async fn rename_tag(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Json(payload): Json<RenameTagRequest>,
+) -> Result<Json<TagChangeResponse>> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+    if payload.from == payload.to {
+        return Err(AppError::BadRequest(format!("Tag is already named {}", payload.to)));
+    }
+
+    let tier = find_tier(&pool, &scope).await?;
+    let updated = retag_environment(
+        &pool,
+        &storage,
+        &scope.environment,
+        &tier,
+        scope.ctx.user_id,
+        &payload.from,
+        &format!("Renamed tag {} to {}", payload.from, payload.to),
+        |tags| renamed_tags(tags, &payload.from, &payload.to),
+        |action, key, tags| scope.authorize(action, scope.resource(Some(key), tags)),
+    )
+    .await?;
+
+    Ok(Json(TagChangeResponse { updated }))
+}
+
+/// Remove a tag from every variable of the scope's environment carrying it
+pub async fn delete_tag(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(TagPath { tag }): Path<TagPath>,
+) -> Result<Json<TagChangeResponse>> {
+    let tier = find_tier(&pool, &scope).await?;
+    let updated = retag_environment(
+        &pool,
+        &storage,
+        &scope.environment,
+        &tier,
+        scope.ctx.user_id,
+        &tag,
+        &format!("Removed tag {}", tag),
+        |tags| tags.iter().filter(|t| **t != tag).cloned().collect(),
+        |action, key, tags| scope.authorize(action, scope.resource(Some(key), tags)),
+    )
+    .await?;
+
+    Ok(Json(TagChangeResponse { updated }))
+}
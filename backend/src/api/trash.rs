@@ -0,0 +1,102 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::{Pool, Postgres};
+
+use crate::api::context::VariableScope;
+use crate::dto::{
+    EmptyTrashResponse, TrashItem, TrashListResponse, VariablePath, VariableResponse,
+};
+use crate::error::{AppError, Result};
+use crate::models::{Tier, Variable};
+use crate::policy::Action;
+use crate::repositories::{TierRepository, VariableRepository};
+use crate::services::{purge_trash, purge_variable, restore_variable};
+use crate::storage::{FileStorage, VariableStore};
+
+async fn find_tier(pool: &Pool<Postgres>, scope: &VariableScope) -> Result<Tier> {
+    TierRepository::new(pool.clone())
+        .find_by_id(scope.ctx.tier_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))
+}
+
+fn authorize_trashed(scope: &VariableScope, action: Action, variable: &Variable) -> Result<()> {
+    scope.authorize(action, scope.resource(Some(&variable.key), variable.tag_list()))
+}
+
+/// Variables in the trash of the scope's environment, most recently deleted first
+pub async fn list_trash(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+) -> Result<Json<TrashListResponse>> {
+    scope.authorize(Action::VariableList, scope.resource(None, Vec::new()))?;
+
+    let retention = find_tier(&pool, &scope).await?.trash_retention();
+    let variables = VariableRepository::new(pool)
+        .list_trashed(scope.environment.id)
+        .await?
+        .into_iter()
+        .map(|variable| TrashItem::new(variable, retention))
+        .collect();
+
+    Ok(Json(TrashListResponse { variables }))
+}
+
+/// Take a variable out of the trash; fails with a conflict while another variable holds
+/// its key
+pub async fn restore_trashed_variable(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+) -> Result<Json<VariableResponse>> {
+    let tier = find_tier(&pool, &scope).await?;
+    let variable = restore_variable(
+        &pool,
+        &storage,
+        scope.ctx.owner,
+        &tier,
+        scope.environment.id,
+        id,
+        |variable| authorize_trashed(&scope, Action::VariableWrite, variable),
+    )
+    .await?;
+    let data = storage.retrieve(&variable.storage_path).await?;
+
+    Ok(Json(VariableResponse {
+        variable,
+        data: Some(data),
+    }))
+}
+
+/// Delete a variable in the trash for good, with its data and versions
+pub async fn purge_trashed_variable(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+) -> Result<StatusCode> {
+    purge_variable(&pool, &storage, scope.environment.id, id, |variable| {
+        authorize_trashed(&scope, Action::VariableDelete, variable)
+    })
+    .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delete every variable in the trash of the scope's environment for good
+pub async fn empty_trash(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+) -> Result<Json<EmptyTrashResponse>> {
+    let purged = purge_trash(&pool, &storage, scope.environment.id, |variable| {
+        authorize_trashed(&scope, Action::VariableDelete, variable)
+    })
+    .await?;
+
+    Ok(Json(EmptyTrashResponse { purged }))
+}
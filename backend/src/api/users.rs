@@ -1,6 +1,6 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     Extension, Json,
 };
 use sqlx::{Pool, Postgres};
@@ -11,8 +11,13 @@ use crate::dto::{
     ApiKeyListResponse, ApiKeyResponse, ChangePasswordRequest, CreateApiKeyRequest,
     UserProfileResponse,
 };
+use crate::api::context::OwnerContext;
 use crate::error::{AppError, Result};
-use crate::repositories::{ApiKeyRepository, TierRepository, UserRepository, VariableRepository};
+use crate::models::{OrgRole, Owner};
+use crate::repositories::{
+    ApiKeyRepository, EnvironmentRepository, ProjectRepository, TierRepository, UserRepository,
+    VariableRepository,
+};
 use crate::utils::{extract_key_prefix, generate_api_key, hash_password, verify_password, Claims};
 
 pub async fn get_profile(
@@ -36,8 +41,8 @@ pub async fn get_profile(
         .await?
         .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))?;
 
-    let variables_count = var_repo.count_by_user(user_id).await?;
-    let api_keys_count = key_repo.count_by_user(user_id).await?;
+    let variables_count = var_repo.count_by_owner(Owner::User(user_id)).await?;
+    let api_keys_count = key_repo.count_by_owner(Owner::User(user_id)).await?;
 
     Ok(Json(UserProfileResponse {
         user: user.sanitize(),
@@ -79,23 +84,24 @@ pub async fn change_password(
 pub async fn create_api_key(
     State(pool): State<Pool<Postgres>>,
     Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
     Json(payload): Json<CreateApiKeyRequest>,
 ) -> Result<(StatusCode, Json<ApiKeyResponse>)> {
     payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
 
-    let user_id = claims.user_id()?;
-    let tier_id = claims.tier_id()?;
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Admin)?;
 
     let key_repo = ApiKeyRepository::new(pool.clone());
-    let tier_repo = TierRepository::new(pool);
+    let tier_repo = TierRepository::new(pool.clone());
 
     // Check API key count limit
     let tier = tier_repo
-        .find_by_id(tier_id)
+        .find_by_id(ctx.tier_id)
         .await?
         .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))?;
 
-    let key_count = key_repo.count_by_user(user_id).await?;
+    let key_count = key_repo.count_by_owner(ctx.owner).await?;
     if !tier.can_create_api_key(key_count) {
         return Err(AppError::TierLimitExceeded(format!(
             "Maximum {} API keys allowed",
@@ -103,6 +109,22 @@ pub async fn create_api_key(
         )));
     }
 
+    // An environment restriction must point at one of the owner's environments
+    if let Some(env_id) = payload.environment_id {
+        let environment = EnvironmentRepository::new(pool.clone())
+            .find_by_id(env_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Environment not found".to_string()))?;
+        let project = ProjectRepository::new(pool.clone())
+            .find_by_id(environment.project_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?;
+
+        if project.owner() != ctx.owner {
+            return Err(AppError::NotFound("Environment not found".to_string()));
+        }
+    }
+
     // Generate API key
     let api_key_secret = generate_api_key();
     let prefix = extract_key_prefix(&api_key_secret);
@@ -111,11 +133,14 @@ pub async fn create_api_key(
     // Create API key
     let api_key = key_repo
         .create(
-            user_id,
+            ctx.owner,
+            ctx.user_id,
+            payload.environment_id,
             &payload.name,
             &key_hash,
             &prefix,
             payload.expires_in_days,
+            payload.permissions.as_ref(),
         )
         .await?;
 
@@ -131,11 +156,13 @@ pub async fn create_api_key(
 pub async fn list_api_keys(
     State(pool): State<Pool<Postgres>>,
     Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
 ) -> Result<Json<ApiKeyListResponse>> {
-    let user_id = claims.user_id()?;
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Admin)?;
     let key_repo = ApiKeyRepository::new(pool);
 
-    let api_keys = key_repo.list_by_user(user_id).await?;
+    let api_keys = key_repo.list_by_owner(ctx.owner).await?;
 
     Ok(Json(ApiKeyListResponse {
         total: api_keys.len() as i32,
@@ -146,12 +173,14 @@ pub async fn list_api_keys(
 pub async fn revoke_api_key(
     State(pool): State<Pool<Postgres>>,
     Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
     Path(key_id): Path<Uuid>,
 ) -> Result<StatusCode> {
-    let user_id = claims.user_id()?;
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Admin)?;
     let key_repo = ApiKeyRepository::new(pool);
 
-    key_repo.revoke(key_id, user_id).await?;
+    key_repo.revoke(key_id, ctx.owner).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -159,12 +188,14 @@ pub async fn revoke_api_key(
 pub async fn delete_api_key(
     State(pool): State<Pool<Postgres>>,
     Extension(claims): Extension<Claims>,
+    headers: HeaderMap,
     Path(key_id): Path<Uuid>,
 ) -> Result<StatusCode> {
-    let user_id = claims.user_id()?;
+    let ctx = OwnerContext::resolve(&pool, &claims, &headers).await?;
+    ctx.require(OrgRole::Admin)?;
     let key_repo = ApiKeyRepository::new(pool);
 
-    key_repo.delete(key_id, user_id).await?;
+    key_repo.delete(key_id, ctx.owner).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
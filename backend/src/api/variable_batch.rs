@@ -0,0 +1,98 @@
+use axum::{extract::State, Json};
+use sqlx::{Pool, Postgres};
+use validator::Validate;
+
+use crate::api::context::VariableScope;
+use crate::dto::{
+    BatchGetItem, BatchGetRequest, BatchGetResponse, BatchItemStatus, BatchWriteRequest,
+    BatchWriteResponse,
+};
+use crate::error::{AppError, Result};
+use crate::models::AccessibleVariable;
+use crate::policy::Action;
+use crate::repositories::{TierRepository, VariableRepository};
+use crate::services::apply_batch_write;
+use crate::storage::{FileStorage, VariableStore};
+
+/// Read many variables at once. Items that are missing or not readable are reported
+/// per item rather than failing the batch.
+pub async fn batch_get_variables(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Json(payload): Json<BatchGetRequest>,
+) -> Result<Json<BatchGetResponse>> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let found = VariableRepository::new(pool)
+        .find_many(scope.environment.id, scope.shared_with(), &payload.ids, &payload.keys)
+        .await?;
+
+    // Keys only name variables of the environment itself, never shared ones
+    let by_id = |id| found.iter().find(|v| v.variable.id == id);
+    let by_key = |key: &str| {
+        found
+            .iter()
+            .find(|v| v.variable.key == key && v.variable.environment_id == scope.environment.id)
+    };
+
+    let mut items = Vec::with_capacity(payload.ids.len() + payload.keys.len());
+    for id in payload.ids {
+        let (status, variable, data) = read_item(&scope, &storage, by_id(id)).await?;
+        items.push(BatchGetItem { id: Some(id), key: None, status, variable, data });
+    }
+    for key in payload.keys {
+        let (status, variable, data) = read_item(&scope, &storage, by_key(&key)).await?;
+        items.push(BatchGetItem { id: None, key: Some(key), status, variable, data });
+    }
+
+    Ok(Json(BatchGetResponse { items }))
+}
+
+async fn read_item(
+    scope: &VariableScope,
+    storage: &FileStorage,
+    accessible: Option<&AccessibleVariable>,
+) -> Result<(BatchItemStatus, Option<AccessibleVariable>, Option<serde_json::Value>)> {
+    let Some(accessible) = accessible else {
+        return Ok((BatchItemStatus::NotFound, None, None));
+    };
+
+    match scope.authorize_variable(Action::VariableRead, accessible) {
+        Ok(()) => {
+            let data = storage.retrieve(&accessible.variable.storage_path).await?;
+            Ok((BatchItemStatus::Found, Some(accessible.clone()), Some(data)))
+        }
+        Err(AppError::Authorization(_)) => Ok((BatchItemStatus::Forbidden, None, None)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Apply many upserts and deletes in the scope's environment, all or nothing
+pub async fn batch_write_variables(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Json(payload): Json<BatchWriteRequest>,
+) -> Result<Json<BatchWriteResponse>> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let tier = TierRepository::new(pool.clone())
+        .find_by_id(scope.ctx.tier_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))?;
+
+    let results = apply_batch_write(
+        &pool,
+        &storage,
+        scope.ctx.owner,
+        scope.ctx.user_id,
+        &tier,
+        &scope.environment,
+        &payload,
+        |action, key, tags| scope.authorize(action, scope.resource(Some(key), tags)),
+    )
+    .await?;
+
+    Ok(Json(BatchWriteResponse { results }))
+}
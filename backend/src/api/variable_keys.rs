@@ -0,0 +1,195 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::context::VariableScope;
+use crate::api::variable_schemas::validate_variable_data;
+use crate::api::variables::{
+    create_variable, delete_variable, get_variable, update_variable, written,
+};
+use crate::dto::{
+    CreateVariableRequest, GetVariableParams, UpdateVariableRequest, UpsertVariableRequest,
+    ValidateDataRequest, ValidateDataResponse, VariableKeyPath, VariablePath,
+};
+use crate::error::{AppError, Result};
+use crate::models::Variable;
+use crate::repositories::VariableRepository;
+use crate::storage::FileStorage;
+use crate::utils::{etag_matches, variable_etag};
+
+/// The variable with `key` in the scope's environment
+async fn find_variable(
+    pool: &Pool<Postgres>,
+    scope: &VariableScope,
+    key: &str,
+) -> Result<Option<Variable>> {
+    VariableRepository::new(pool.clone())
+        .find_by_key(key, scope.environment.id)
+        .await
+}
+
+/// Id of the variable with `key` in the scope's environment
+async fn find_id(
+    pool: &Pool<Postgres>,
+    scope: &VariableScope,
+    key: &str,
+) -> Result<Option<Uuid>> {
+    let variable = find_variable(pool, scope, key).await?;
+
+    Ok(variable.map(|v| v.id))
+}
+
+async fn require_id(pool: &Pool<Postgres>, scope: &VariableScope, key: &str) -> Result<Uuid> {
+    find_id(pool, scope, key)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))
+}
+
+pub async fn get_variable_by_key(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariableKeyPath { key }): Path<VariableKeyPath>,
+    params: Query<GetVariableParams>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let id = require_id(&pool, &scope, &key).await?;
+
+    get_variable(State(pool), State(storage), scope, Path(VariablePath { id }), params, headers)
+        .await
+}
+
+pub async fn update_variable_by_key(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariableKeyPath { key }): Path<VariableKeyPath>,
+    headers: HeaderMap,
+    payload: Json<UpdateVariableRequest>,
+) -> Result<Response> {
+    let id = require_id(&pool, &scope, &key).await?;
+
+    update_variable(
+        State(pool),
+        State(storage),
+        scope,
+        Path(VariablePath { id }),
+        headers,
+        payload,
+    )
+    .await
+}
+
+/// Create the variable if its key is missing, otherwise replace it. An existing variable
+/// keeps its encryption and schema, so a request asking for others is rejected, and loses
+/// its expiry unless the request gives one. `If-Match` only matches an existing variable;
+/// `If-None-Match` rejects an existing variable when it is `*` or names the variable's
+/// current `ETag`.
+pub async fn put_variable_by_key(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariableKeyPath { key }): Path<VariableKeyPath>,
+    headers: HeaderMap,
+    Json(payload): Json<UpsertVariableRequest>,
+) -> Result<Response> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    match find_variable(&pool, &scope, &key).await? {
+        Some(variable) => {
+            let if_none_match = headers
+                .get(header::IF_NONE_MATCH)
+                .and_then(|v| v.to_str().ok());
+            if if_none_match.is_some_and(|v| etag_matches(v, &variable_etag(&variable))) {
+                return Err(AppError::PreconditionFailed(
+                    "Variable already exists".to_string(),
+                ));
+            }
+
+            if payload.is_encrypted != variable.is_encrypted {
+                return Err(AppError::Conflict(
+                    "is_encrypted differs from the existing variable's".to_string(),
+                ));
+            }
+            if payload.schema_id != variable.schema_id || payload.schema != variable.inline_schema
+            {
+                return Err(AppError::Conflict(
+                    "Schema differs from the existing variable's; change it with \
+                     PUT /{id}/schema"
+                        .to_string(),
+                ));
+            }
+
+            // Without either, the replaced variable no longer expires
+            let update = UpdateVariableRequest {
+                description: payload.description,
+                data: Some(payload.data),
+                tags: payload.tags,
+                message: payload.message,
+                expires_at: payload.ttl_seconds.is_none().then_some(payload.expires_at),
+                ttl_seconds: payload.ttl_seconds,
+            };
+            update_variable(
+                State(pool),
+                State(storage),
+                scope,
+                Path(VariablePath { id: variable.id }),
+                headers,
+                Json(update),
+            )
+            .await
+        }
+        None => {
+            if headers.contains_key(header::IF_MATCH) {
+                return Err(AppError::PreconditionFailed(
+                    "Variable does not exist".to_string(),
+                ));
+            }
+
+            let create = CreateVariableRequest {
+                key,
+                description: payload.description,
+                data: payload.data,
+                tags: payload.tags,
+                is_encrypted: payload.is_encrypted,
+                message: payload.message,
+                schema_id: payload.schema_id,
+                schema: payload.schema,
+                expires_at: payload.expires_at,
+                ttl_seconds: payload.ttl_seconds,
+            };
+            let (status, Json(created)) =
+                create_variable(State(pool), State(storage), scope, Json(create)).await?;
+
+            Ok((status, written(created.variable, created.data)).into_response())
+        }
+    }
+}
+
+pub async fn delete_variable_by_key(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariableKeyPath { key }): Path<VariableKeyPath>,
+    headers: HeaderMap,
+) -> Result<StatusCode> {
+    let id = require_id(&pool, &scope, &key).await?;
+
+    delete_variable(State(pool), scope, Path(VariablePath { id }), headers).await
+}
+
+pub async fn validate_variable_data_by_key(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariableKeyPath { key }): Path<VariableKeyPath>,
+    Json(payload): Json<ValidateDataRequest>,
+) -> Result<Json<ValidateDataResponse>> {
+    let id = require_id(&pool, &scope, &key).await?;
+
+    validate_variable_data(State(pool), scope, Path(VariablePath { id }), Json(payload)).await
+}
@@ -0,0 +1,87 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use sqlx::{Pool, Postgres};
+use validator::Validate;
+
+use crate::api::context::VariableScope;
+use crate::dto::{
+    DataQueryMatch, DataQueryRequest, DataQueryResponse, TaggedDataQueryRequest,
+    TaggedDataQueryResponse, VariablePath, MAX_QUERY_VARIABLES,
+};
+use crate::error::{AppError, Result};
+use crate::models::VariableFilter;
+use crate::policy::Action;
+use crate::repositories::VariableRepository;
+use crate::services::DataQuery;
+use crate::storage::{FileStorage, VariableStore};
+
+/// Run a JSONPath expression over a variable's data, returning only what it selects
+pub async fn query_variable(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    Json(payload): Json<DataQueryRequest>,
+) -> Result<Json<DataQueryResponse>> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+    let query = DataQuery::parse(&payload.path)?;
+
+    let accessible = VariableRepository::new(pool)
+        .find_by_id(id, scope.environment.id, scope.shared_with())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    scope.authorize_variable(Action::VariableRead, &accessible)?;
+
+    let data = storage.retrieve(&accessible.variable.storage_path).await?;
+
+    Ok(Json(DataQueryResponse { values: query.run(&data) }))
+}
+
+/// Run a JSONPath expression over every readable variable carrying the given tags.
+/// Variables the caller may not read are left out.
+pub async fn query_tagged_variables(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Json(payload): Json<TaggedDataQueryRequest>,
+) -> Result<Json<TaggedDataQueryResponse>> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+    let query = DataQuery::parse(&payload.path)?;
+    scope.authorize(Action::VariableList, scope.resource(None, Vec::new()))?;
+
+    let filter = VariableFilter {
+        tags: payload.tags,
+        tag_match: payload.tag_match,
+        ..Default::default()
+    };
+    let (variables, total) = VariableRepository::new(pool)
+        .list(scope.environment.id, scope.shared_with(), 1, MAX_QUERY_VARIABLES, &filter)
+        .await?;
+
+    let mut matches = Vec::new();
+    for accessible in variables {
+        match scope.authorize_variable(Action::VariableRead, &accessible) {
+            Ok(()) => {}
+            Err(AppError::Authorization(_)) => continue,
+            Err(e) => return Err(e),
+        }
+
+        let data = storage.retrieve(&accessible.variable.storage_path).await?;
+        let values = query.run(&data);
+        if !values.is_empty() {
+            matches.push(DataQueryMatch {
+                id: accessible.variable.id,
+                key: accessible.variable.key,
+                values,
+            });
+        }
+    }
+    matches.sort_by(|a, b| a.key.cmp(&b.key));
+
+    Ok(Json(TaggedDataQueryResponse {
+        matches,
+        truncated: total > i64::from(MAX_QUERY_VARIABLES),
+    }))
+}
@@ -0,0 +1,40 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use sqlx::{Pool, Postgres};
+
+use crate::api::context::VariableScope;
+use crate::dto::{ReferrersResponse, VariableKeyPath};
+use crate::error::Result;
+use crate::policy::Action;
+use crate::services::find_referrers;
+
+/// Variables of the environment whose data references `key`, directly or through other
+/// variables, to show what an edit of it affects. The key need not exist.
+pub async fn list_referrers(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariableKeyPath { key }): Path<VariableKeyPath>,
+) -> Result<Json<ReferrersResponse>> {
+    scope.authorize(Action::VariableList, scope.resource(None, Vec::new()))?;
+
+    let (referrers, hidden): (Vec<_>, Vec<_>) = find_referrers(&pool, scope.environment.id, &key)
+        .await?
+        .into_iter()
+        .partition(|referrer| {
+            let variable = &referrer.variable;
+            scope
+                .authorize(
+                    Action::VariableRead,
+                    scope.resource(Some(&variable.key), variable.tag_list()),
+                )
+                .is_ok()
+        });
+
+    Ok(Json(ReferrersResponse {
+        key,
+        referrers,
+        hidden: hidden.len(),
+    }))
+}
@@ -0,0 +1,89 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::context::VariableScope;
+use crate::dto::{
+    ScheduleChangeRequest, ScheduledChangeListResponse, ScheduledChangePath, VariablePath,
+};
+use crate::error::{AppError, Result};
+use crate::models::{AccessibleVariable, ScheduledChange};
+use crate::policy::Action;
+use crate::repositories::{ScheduledChangeRepository, VariableRepository};
+use crate::services::{cancel_change, schedule_change};
+use crate::storage::FileStorage;
+
+/// Find a variable the caller may use with `action`, including ones shared with them
+async fn find_accessible(
+    pool: &Pool<Postgres>,
+    scope: &VariableScope,
+    id: Uuid,
+    action: Action,
+) -> Result<AccessibleVariable> {
+    let accessible = VariableRepository::new(pool.clone())
+        .find_by_id(id, scope.environment.id, scope.shared_with())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    scope.authorize_variable(action, &accessible)?;
+
+    Ok(accessible)
+}
+
+/// Stage new data for a variable, written as a new version once `apply_at` has passed
+pub async fn create_scheduled_change(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    Json(payload): Json<ScheduleChangeRequest>,
+) -> Result<(StatusCode, Json<ScheduledChange>)> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let accessible = find_accessible(&pool, &scope, id, Action::VariableWrite).await?;
+    let tier = scope.variable_tier(&pool, &accessible).await?;
+
+    let change = schedule_change(
+        &pool,
+        &storage,
+        &accessible.variable,
+        &tier,
+        &payload.data,
+        payload.apply_at,
+        payload.message.as_deref(),
+        scope.ctx.user_id,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(change)))
+}
+
+pub async fn list_scheduled_changes(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+) -> Result<Json<ScheduledChangeListResponse>> {
+    find_accessible(&pool, &scope, id, Action::VariableRead).await?;
+
+    let changes = ScheduledChangeRepository::new(pool).list(id).await?;
+
+    Ok(Json(ScheduledChangeListResponse { changes }))
+}
+
+/// Cancel a pending change; applied, failed and cancelled changes are kept as history
+pub async fn cancel_scheduled_change(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(ScheduledChangePath { id, change_id }): Path<ScheduledChangePath>,
+) -> Result<Json<ScheduledChange>> {
+    find_accessible(&pool, &scope, id, Action::VariableWrite).await?;
+
+    let change = cancel_change(&pool, &storage, id, change_id).await?;
+
+    Ok(Json(change))
+}
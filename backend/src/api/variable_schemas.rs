@@ -0,0 +1,136 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Response,
+    Json,
+};
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::context::VariableScope;
+use crate::api::variables::{if_match, precondition_failed, written};
+use crate::dto::{AttachSchemaRequest, ValidateDataRequest, ValidateDataResponse, VariablePath};
+use crate::error::{AppError, Result};
+use crate::models::{AccessibleVariable, Variable};
+use crate::policy::Action;
+use crate::repositories::VariableRepository;
+use crate::services::{
+    check_schema, compile_schema, requested_schema, schema_violations, variable_schema,
+};
+use crate::storage::{FileStorage, VariableStore};
+use crate::utils::validate_json_data;
+
+async fn find_accessible(
+    pool: &Pool<Postgres>,
+    scope: &VariableScope,
+    id: Uuid,
+    action: Action,
+) -> Result<AccessibleVariable> {
+    let accessible = VariableRepository::new(pool.clone())
+        .find_by_id(id, scope.environment.id, scope.shared_with())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    scope.authorize_variable(action, &accessible)?;
+
+    Ok(accessible)
+}
+
+/// Lock a live variable until the transaction on `conn` ends, provided it is still at a
+/// version `If-Match` names
+async fn lock_matching(
+    conn: &mut PgConnection,
+    headers: &HeaderMap,
+    id: Uuid,
+) -> Result<Variable> {
+    let variable = VariableRepository::lock_live_tx(conn, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    if if_match(headers, id).is_some_and(|versions| !versions.contains(&variable.version)) {
+        return Err(precondition_failed());
+    }
+
+    Ok(variable)
+}
+
+/// Attach a named or inline schema to a variable, provided its current data conforms;
+/// honours `If-Match`
+pub async fn put_variable_schema(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    headers: HeaderMap,
+    Json(payload): Json<AttachSchemaRequest>,
+) -> Result<Response> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let accessible = find_accessible(&pool, &scope, id, Action::VariableWrite).await?;
+    let schema = requested_schema(
+        &pool,
+        accessible.variable.owner(),
+        payload.schema_id,
+        payload.schema.as_ref(),
+    )
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Give either schema_id or schema".to_string()))?;
+
+    // Lock the variable so no write slips in between the check and attaching the schema
+    let mut tx = pool.begin().await?;
+    let variable = lock_matching(&mut tx, &headers, id).await?;
+    let data = storage.retrieve(&variable.storage_path).await?;
+    check_schema(&schema, &data)?;
+
+    let variable = VariableRepository::set_schema_tx(
+        &mut tx,
+        id,
+        payload.schema_id,
+        payload.schema.as_ref(),
+    )
+    .await?;
+    let variable = VariableRepository::bump_version_tx(&mut tx, variable.id).await?;
+    tx.commit().await?;
+
+    Ok(written(variable, None))
+}
+
+/// Stop enforcing a schema on a variable; honours `If-Match`
+pub async fn delete_variable_schema(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    find_accessible(&pool, &scope, id, Action::VariableWrite).await?;
+
+    let mut tx = pool.begin().await?;
+    lock_matching(&mut tx, &headers, id).await?;
+    VariableRepository::set_schema_tx(&mut tx, id, None, None).await?;
+    let variable = VariableRepository::bump_version_tx(&mut tx, id).await?;
+    tx.commit().await?;
+
+    Ok(written(variable, None))
+}
+
+/// Check whether data would be accepted as a variable's new value, without writing it
+pub async fn validate_variable_data(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    Json(payload): Json<ValidateDataRequest>,
+) -> Result<Json<ValidateDataResponse>> {
+    let accessible = find_accessible(&pool, &scope, id, Action::VariableRead).await?;
+
+    let tier = scope.variable_tier(&pool, &accessible).await?;
+    validate_json_data(&payload.data, tier.max_variable_size_mb)?;
+
+    let violations = match variable_schema(&pool, &accessible.variable).await? {
+        Some(schema) => schema_violations(&compile_schema(&schema)?, &payload.data),
+        None => Vec::new(),
+    };
+
+    Ok(Json(ValidateDataResponse {
+        valid: violations.is_empty(),
+        violations,
+    }))
+}
@@ -0,0 +1,168 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::api::context::VariableScope;
+use crate::dto::{
+    CreateShareLinkRequest, CreateShareRequest, SetVisibilityRequest, ShareLinkListResponse,
+    ShareLinkPath, ShareLinkResponse, SharePath, ShareListResponse, VariablePath,
+};
+use crate::error::{AppError, Result};
+use crate::models::{Variable, VariableShare};
+use crate::policy::Action;
+use crate::repositories::{
+    UserRepository, VariableRepository, VariableShareLinkRepository, VariableShareRepository,
+};
+use crate::utils::LinkSigner;
+
+/// Find a variable the caller may expose to others with `action`: one of the
+/// scope's own, never one that was itself shared with them
+async fn find_shareable(
+    pool: &Pool<Postgres>,
+    scope: &VariableScope,
+    id: Uuid,
+    action: Action,
+) -> Result<Variable> {
+    let accessible = VariableRepository::new(pool.clone())
+        .find_by_id(id, scope.environment.id, None)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    scope.authorize_variable(action, &accessible)?;
+
+    Ok(accessible.variable)
+}
+
+pub async fn create_share(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    Json(payload): Json<CreateShareRequest>,
+) -> Result<(StatusCode, Json<VariableShare>)> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let variable = find_shareable(&pool, &scope, id, Action::VariableShare).await?;
+
+    let recipient = UserRepository::new(pool.clone())
+        .find_by_email(&payload.email)
+        .await?
+        .filter(|user| user.is_active)
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    if recipient.id == scope.ctx.user_id {
+        return Err(AppError::BadRequest(
+            "Cannot share a variable with yourself".to_string(),
+        ));
+    }
+
+    let share_repo = VariableShareRepository::new(pool);
+    let share = share_repo
+        .upsert(variable.id, recipient.id, payload.permission, scope.ctx.user_id)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(share)))
+}
+
+pub async fn list_shares(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+) -> Result<Json<ShareListResponse>> {
+    let variable = find_shareable(&pool, &scope, id, Action::VariableShare).await?;
+
+    let share_repo = VariableShareRepository::new(pool);
+    let shares = share_repo.list_by_variable(variable.id).await?;
+
+    Ok(Json(ShareListResponse { shares }))
+}
+
+pub async fn revoke_share(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(SharePath { id, share_id }): Path<SharePath>,
+) -> Result<StatusCode> {
+    let variable = find_shareable(&pool, &scope, id, Action::VariableShare).await?;
+
+    let share_repo = VariableShareRepository::new(pool);
+    share_repo.delete(share_id, variable.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Mark a variable public or private; public variables are served without
+/// authentication under `/public/{owner}/{key}`
+pub async fn set_visibility(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    Json(payload): Json<SetVisibilityRequest>,
+) -> Result<Json<Variable>> {
+    let variable = find_shareable(&pool, &scope, id, Action::VariablePublish).await?;
+
+    let var_repo = VariableRepository::new(pool);
+    let variable = var_repo
+        .set_public(variable.id, variable.environment_id, payload.is_public)
+        .await?;
+
+    Ok(Json(variable))
+}
+
+pub async fn create_share_link(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    Json(payload): Json<CreateShareLinkRequest>,
+) -> Result<(StatusCode, Json<ShareLinkResponse>)> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let variable = find_shareable(&pool, &scope, id, Action::VariableShare).await?;
+
+    // Whole seconds, so the expiry in the URL matches the stored one exactly
+    let expires = (Utc::now() + Duration::minutes(payload.expires_in_minutes)).timestamp();
+    let expires_at = DateTime::from_timestamp(expires, 0)
+        .ok_or_else(|| AppError::BadRequest("Invalid expiry".to_string()))?;
+
+    let link_repo = VariableShareLinkRepository::new(pool);
+    let link = link_repo
+        .create(variable.id, scope.ctx.user_id, expires_at)
+        .await?;
+
+    let signature = LinkSigner::from_env().sign(link.id, expires);
+    let url = format!(
+        "/public/links/{}?expires={}&signature={}",
+        link.id, expires, signature
+    );
+
+    Ok((StatusCode::CREATED, Json(ShareLinkResponse { link, url })))
+}
+
+pub async fn list_share_links(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+) -> Result<Json<ShareLinkListResponse>> {
+    let variable = find_shareable(&pool, &scope, id, Action::VariableShare).await?;
+
+    let link_repo = VariableShareLinkRepository::new(pool);
+    let links = link_repo.list_by_variable(variable.id).await?;
+
+    Ok(Json(ShareLinkListResponse { links }))
+}
+
+pub async fn revoke_share_link(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(ShareLinkPath { id, link_id }): Path<ShareLinkPath>,
+) -> Result<StatusCode> {
+    let variable = find_shareable(&pool, &scope, id, Action::VariableShare).await?;
+
+    let link_repo = VariableShareLinkRepository::new(pool);
+    link_repo.delete(link_id, variable.id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
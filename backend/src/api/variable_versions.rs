@@ -0,0 +1,138 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Response,
+    Json,
+};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::api::context::VariableScope;
+use crate::api::variables::{commit_write, if_match, precondition_failed, written};
+use crate::dto::{
+    VariablePath, VariableVersionListResponse, VariableVersionPath, VariableVersionResponse,
+};
+use crate::error::{AppError, Result};
+use crate::models::{AccessibleVariable, VariableVersion};
+use crate::policy::Action;
+use crate::repositories::{VariableRepository, VariableVersionRepository};
+use crate::services::{enforce_schema, preserve_current, prune_versions};
+use crate::storage::{FileStorage, VariableStore};
+use crate::utils::validate_json_data;
+
+/// Find a variable the caller may use with `action`, including ones shared with them
+async fn find_accessible(
+    pool: &Pool<Postgres>,
+    scope: &VariableScope,
+    id: Uuid,
+    action: Action,
+) -> Result<AccessibleVariable> {
+    let accessible = VariableRepository::new(pool.clone())
+        .find_by_id(id, scope.environment.id, scope.shared_with())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    scope.authorize_variable(action, &accessible)?;
+
+    Ok(accessible)
+}
+
+async fn find_version(
+    pool: &Pool<Postgres>,
+    variable_id: Uuid,
+    version: i32,
+) -> Result<VariableVersion> {
+    VariableVersionRepository::new(pool.clone())
+        .find(variable_id, version)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Version not found".to_string()))
+}
+
+pub async fn list_versions(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+) -> Result<Json<VariableVersionListResponse>> {
+    find_accessible(&pool, &scope, id, Action::VariableRead).await?;
+
+    let versions = VariableVersionRepository::new(pool).list(id).await?;
+
+    Ok(Json(VariableVersionListResponse { versions }))
+}
+
+pub async fn get_version(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariableVersionPath { id, version }): Path<VariableVersionPath>,
+) -> Result<Json<VariableVersionResponse>> {
+    find_accessible(&pool, &scope, id, Action::VariableRead).await?;
+
+    let version = find_version(&pool, id, version).await?;
+    let data = storage.retrieve(&version.storage_path).await?;
+
+    Ok(Json(VariableVersionResponse { version, data }))
+}
+
+/// Restore the data and metadata of an earlier version as a new version; with `If-Match`,
+/// only while the variable is still at a matching version
+pub async fn rollback_variable(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariableVersionPath { id, version }): Path<VariableVersionPath>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let accessible = find_accessible(&pool, &scope, id, Action::VariableWrite).await?;
+    let target = find_version(&pool, id, version).await?;
+
+    // The restored tags must be allowed too
+    let mut restored = accessible.clone();
+    restored.variable.tags = target.tags.clone();
+    scope.authorize_variable(Action::VariableWrite, &restored)?;
+
+    let tier = scope.variable_tier(&pool, &accessible).await?;
+    let expected_versions = if_match(&headers, id);
+
+    let data = storage.retrieve(&target.storage_path).await?;
+    validate_json_data(&data, tier.max_variable_size_mb)?;
+
+    let mut tx = pool.begin().await?;
+    let variable = VariableRepository::lock_live_tx(&mut tx, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    if expected_versions.is_some_and(|versions| !versions.contains(&variable.version)) {
+        return Err(precondition_failed());
+    }
+
+    // The restored data must still conform to the variable's current schema
+    enforce_schema(&pool, &variable, &data).await?;
+
+    let current_data = storage.retrieve(&variable.storage_path).await?;
+    preserve_current(&mut tx, &storage, &variable, &current_data).await?;
+    let updated_variable = VariableRepository::overwrite_tx(
+        &mut tx,
+        id,
+        variable.environment_id,
+        target.description.as_deref(),
+        target.size_bytes,
+        // Data encrypted since the target version stays encrypted
+        target.is_encrypted || variable.is_encrypted,
+        target.tags.clone(),
+    )
+    .await?;
+
+    commit_write(
+        tx,
+        &storage,
+        &updated_variable,
+        &current_data,
+        &data,
+        scope.ctx.user_id,
+        Some(&format!("Rolled back to version {}", target.version)),
+    )
+    .await?;
+
+    prune_versions(&pool, &storage, id, &tier).await;
+
+    Ok(written(updated_variable, Some(data)))
+}
@@ -1,41 +1,112 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
-    Extension, Json,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
 };
-use sqlx::{Pool, Postgres};
+use axum::body::Bytes;
+use chrono::Utc;
+use serde_json::Value;
+use sqlx::{Pool, Postgres, Transaction};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::dto::{CreateVariableRequest, UpdateVariableRequest, VariableListResponse, VariableQueryParams, VariableResponse};
+use crate::api::context::VariableScope;
+use crate::dto::{
+    AtomicRequest, AtomicResponse, CreateVariableRequest, GetVariableParams,
+    UpdateVariableRequest, VariableListResponse, VariablePath, VariablePointerPath,
+    VariableQueryParams, VariableResponse,
+};
 use crate::error::{AppError, Result};
+use crate::policy::Action;
 use crate::repositories::{TierRepository, VariableRepository};
+use crate::models::Variable;
+use crate::services::{
+    apply_atomic, check_schema, enforce_schema, preserve_current, prune_versions,
+    purge_expired_key_tx, record_version, remove_purged, requested_schema, resolve_variable_data,
+    DataPatch,
+};
 use crate::storage::{FileStorage, VariableStore};
-use crate::utils::{validate_json_data, validate_variable_key, Claims};
+use crate::utils::{
+    etag_matches, if_match_versions, validate_json_data, validate_variable_key, variable_etag,
+};
+
+/// Versions of variable `id` the request's `If-Match` header accepts, or `None` when it
+/// sets no precondition. A header that isn't valid text accepts none.
+pub(crate) fn if_match(headers: &HeaderMap, id: Uuid) -> Option<Vec<i32>> {
+    let value = headers.get(header::IF_MATCH)?;
+
+    match value.to_str() {
+        Ok(value) => if_match_versions(value, id),
+        Err(_) => Some(Vec::new()),
+    }
+}
+
+/// The JSON Pointer a `/data/{*pointer}` route addresses; the wildcard omits the leading `/`
+fn json_pointer(captured: &str) -> String {
+    format!("/{}", captured)
+}
+
+fn no_value_at(pointer: &str) -> AppError {
+    AppError::NotFound(format!("No value at {}", pointer))
+}
+
+pub(crate) fn precondition_failed() -> AppError {
+    AppError::PreconditionFailed("Variable was modified since it was read".to_string())
+}
+
+/// Record `updated` as a new version holding `data`, write `data` over `previous` and
+/// commit. The stored data is restored if the commit fails.
+pub(crate) async fn commit_write(
+    mut tx: Transaction<'_, Postgres>,
+    storage: &FileStorage,
+    updated: &Variable,
+    previous: &Value,
+    data: &Value,
+    author_id: Uuid,
+    message: Option<&str>,
+) -> Result<()> {
+    record_version(&mut tx, storage, updated, data, Some(author_id), message).await?;
+
+    if data != previous {
+        storage.update(&updated.storage_path, data).await?;
+    }
+    if let Err(e) = tx.commit().await {
+        if let Err(e) = storage.update(&updated.storage_path, previous).await {
+            tracing::warn!("Failed to restore data of {}: {}", updated.storage_path, e);
+        }
+        return Err(e.into());
+    }
+
+    Ok(())
+}
 
 pub async fn create_variable(
     State(pool): State<Pool<Postgres>>,
     State(storage): State<FileStorage>,
-    Extension(claims): Extension<Claims>,
+    scope: VariableScope,
     Json(payload): Json<CreateVariableRequest>,
 ) -> Result<(StatusCode, Json<VariableResponse>)> {
     payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
     validate_variable_key(&payload.key).map_err(|e| AppError::Validation(e.to_string()))?;
 
-    let user_id = claims.user_id()?;
-    let tier_id = claims.tier_id()?;
+    let ctx = &scope.ctx;
+    scope.authorize(
+        Action::VariableWrite,
+        scope.resource(Some(&payload.key), payload.tags.clone().unwrap_or_default()),
+    )?;
 
     let var_repo = VariableRepository::new(pool.clone());
-    let tier_repo = TierRepository::new(pool);
+    let tier_repo = TierRepository::new(pool.clone());
 
-    // Get user's tier limits
+    // Get the owner's tier limits
     let tier = tier_repo
-        .find_by_id(tier_id)
+        .find_by_id(ctx.tier_id)
         .await?
         .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))?;
 
     // Check variable count limit
-    let var_count = var_repo.count_by_user(user_id).await?;
+    let var_count = var_repo.count_by_owner(ctx.owner).await?;
     if !tier.can_create_variable(var_count) {
         return Err(AppError::TierLimitExceeded(format!(
             "Maximum {} variables allowed",
@@ -44,32 +115,74 @@ pub async fn create_variable(
     }
 
     // Check if variable key already exists
-    if var_repo.find_by_key(&payload.key, user_id).await?.is_some() {
+    if var_repo.find_by_key(&payload.key, scope.environment.id).await?.is_some() {
         return Err(AppError::Conflict("Variable key already exists".to_string()));
     }
 
-    // Validate data size
+    // Validate data size and conformance to the requested schema
     validate_json_data(&payload.data, tier.max_variable_size_mb)?;
+    let schema = requested_schema(&pool, ctx.owner, payload.schema_id, payload.schema.as_ref())
+        .await?;
+    if let Some(ref schema) = schema {
+        check_schema(schema, &payload.data)?;
+    }
 
     // Store variable data
-    let storage_path = storage.store(user_id, &payload.key, &payload.data).await?;
+    let storage_path = storage
+        .store(
+            ctx.owner,
+            scope.environment.id,
+            &payload.key,
+            &payload.data,
+            payload.is_encrypted,
+        )
+        .await?;
     let size_bytes = crate::utils::json_validator::calculate_json_size(&payload.data) as i64;
 
     // Convert tags to JSON
+    let expires_at = payload.expiry(Utc::now());
     let tags_json = payload.tags.map(|tags| serde_json::json!(tags));
 
-    // Create variable metadata in database
-    let variable = var_repo
-        .create(
-            user_id,
-            &payload.key,
-            payload.description.as_deref(),
-            size_bytes,
-            &storage_path,
-            payload.is_encrypted,
-            tags_json,
+    // Create variable metadata in database; an expired variable gives up its key
+    let mut tx = pool.begin().await?;
+    let purged = purge_expired_key_tx(&mut tx, scope.environment.id, &payload.key).await?;
+    let mut variable = VariableRepository::create_tx(
+        &mut tx,
+        ctx.owner,
+        scope.environment.id,
+        ctx.user_id,
+        &payload.key,
+        payload.description.as_deref(),
+        size_bytes,
+        &storage_path,
+        payload.is_encrypted,
+        tags_json,
+    )
+    .await?;
+    if schema.is_some() {
+        variable = VariableRepository::set_schema_tx(
+            &mut tx,
+            variable.id,
+            payload.schema_id,
+            payload.schema.as_ref(),
         )
         .await?;
+    }
+    if expires_at.is_some() {
+        variable = VariableRepository::set_expiry_tx(&mut tx, variable.id, expires_at).await?;
+    }
+
+    record_version(
+        &mut tx,
+        &storage,
+        &variable,
+        &payload.data,
+        Some(ctx.user_id),
+        payload.message.as_deref(),
+    )
+    .await?;
+    tx.commit().await?;
+    remove_purged(&storage, purged).await;
 
     Ok((
         StatusCode::CREATED,
@@ -80,44 +193,99 @@ pub async fn create_variable(
     ))
 }
 
+/// Serve a variable with its `ETag`; answers 304 when `If-None-Match` still matches
 pub async fn get_variable(
     State(pool): State<Pool<Postgres>>,
     State(storage): State<FileStorage>,
-    Extension(claims): Extension<Claims>,
-    Path(id): Path<Uuid>,
-) -> Result<Json<VariableResponse>> {
-    let user_id = claims.user_id()?;
-    let var_repo = VariableRepository::new(pool);
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    Query(params): Query<GetVariableParams>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let var_repo = VariableRepository::new(pool.clone());
 
-    let variable = var_repo
-        .find_by_id(id, user_id)
+    let accessible = var_repo
+        .find_by_id(id, scope.environment.id, scope.shared_with())
         .await?
         .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    scope.authorize_variable(Action::VariableRead, &accessible)?;
+    let variable = accessible.variable;
+
+    // The resolved view changes with the referenced variables, so the variable's ETag
+    // doesn't identify it
+    if params.resolve {
+        let data = storage.retrieve(&variable.storage_path).await?;
+        let data = resolve_variable_data(
+            &pool,
+            &storage,
+            &variable,
+            &data,
+            scope.environment.id,
+            scope.shared_with(),
+            |referenced| scope.authorize_variable(Action::VariableRead, referenced),
+        )
+        .await?;
+
+        return Ok(Json(VariableResponse {
+            variable,
+            data: Some(data),
+        })
+        .into_response());
+    }
+
+    let etag = variable_etag(&variable);
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| etag_matches(v, &etag));
+    if not_modified {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response());
+    }
 
     // Retrieve data from storage
     let data = storage.retrieve(&variable.storage_path).await?;
 
-    Ok(Json(VariableResponse {
-        variable,
-        data: Some(data),
-    }))
+    Ok((
+        [(header::ETAG, etag)],
+        Json(VariableResponse {
+            variable,
+            data: Some(data),
+        }),
+    )
+        .into_response())
 }
 
 pub async fn list_variables(
     State(pool): State<Pool<Postgres>>,
-    Extension(claims): Extension<Claims>,
+    scope: VariableScope,
     Query(params): Query<VariableQueryParams>,
 ) -> Result<Json<VariableListResponse>> {
-    let user_id = claims.user_id()?;
+    scope.authorize(Action::VariableList, scope.resource(None, Vec::new()))?;
+
     let var_repo = VariableRepository::new(pool);
 
     let page = params.page.unwrap_or(1).max(1);
-    let page_size = params.page_size.unwrap_or(20).min(100).max(1);
-
-    let (variables, total) = var_repo
-        .list(user_id, page, page_size, params.search.as_deref())
+    let page_size = params.page_size.unwrap_or(20).clamp(1, 100);
+
+    let (mut variables, total) = var_repo
+        .list(
+            scope.environment.id,
+            scope.shared_with(),
+            page,
+            page_size,
+            &params.filter(),
+        )
         .await?;
 
+    // Highlights quote variable data, so only variables the caller may read keep theirs
+    for variable in &mut variables {
+        if variable.highlight.is_some()
+            && scope.authorize_variable(Action::VariableRead, variable).is_err()
+        {
+            variable.highlight = None;
+        }
+    }
+
     Ok(Json(VariableListResponse {
         variables,
         total,
@@ -126,36 +294,55 @@ pub async fn list_variables(
     }))
 }
 
+/// Update a variable; with `If-Match`, only while it is still at a matching version
 pub async fn update_variable(
     State(pool): State<Pool<Postgres>>,
     State(storage): State<FileStorage>,
-    Extension(claims): Extension<Claims>,
-    Path(id): Path<Uuid>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    headers: HeaderMap,
     Json(payload): Json<UpdateVariableRequest>,
-) -> Result<Json<VariableResponse>> {
+) -> Result<Response> {
     payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
 
-    let user_id = claims.user_id()?;
-    let tier_id = claims.tier_id()?;
+    let ctx = &scope.ctx;
 
     let var_repo = VariableRepository::new(pool.clone());
-    let tier_repo = TierRepository::new(pool);
 
     // Get existing variable
-    let variable = var_repo
-        .find_by_id(id, user_id)
+    let accessible = var_repo
+        .find_by_id(id, scope.environment.id, scope.shared_with())
         .await?
         .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
 
-    // If updating data, validate size and update storage
-    let new_size = if let Some(ref data) = payload.data {
-        let tier = tier_repo
-            .find_by_id(tier_id)
-            .await?
-            .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))?;
+    scope.authorize_variable(Action::VariableWrite, &accessible)?;
+
+    // Retagging must also be allowed for the variable as it will be
+    if let Some(ref tags) = payload.tags {
+        let mut retagged = accessible.clone();
+        retagged.variable.tags = Some(serde_json::json!(tags));
+        scope.authorize_variable(Action::VariableWrite, &retagged)?;
+    }
+
+    let tier = scope.variable_tier(&pool, &accessible).await?;
+    let expected_versions = if_match(&headers, id);
+
+    // Lock the variable before reading its data, so no write in between is undone
+    let mut tx = pool.begin().await?;
+    let variable = VariableRepository::lock_live_tx(&mut tx, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    if expected_versions
+        .as_ref()
+        .is_some_and(|versions| !versions.contains(&variable.version))
+    {
+        return Err(precondition_failed());
+    }
 
+    // If updating data, validate its size and conformance to the variable's schema
+    let new_size = if let Some(ref data) = payload.data {
         validate_json_data(data, tier.max_variable_size_mb)?;
-        storage.update(&variable.storage_path, data).await?;
+        enforce_schema(&pool, &variable, data).await?;
 
         Some(crate::utils::json_validator::calculate_json_size(data) as i64)
     } else {
@@ -163,42 +350,273 @@ pub async fn update_variable(
     };
 
     // Convert tags to JSON
+    let expiry = payload.expiry(Utc::now());
     let tags_json = payload.tags.map(|tags| serde_json::json!(tags));
 
-    // Update variable metadata
-    let updated_variable = var_repo
-        .update(
-            id,
-            user_id,
-            payload.description.as_deref(),
-            new_size,
-            tags_json,
-        )
-        .await?;
+    let current_data = storage.retrieve(&variable.storage_path).await?;
+    preserve_current(&mut tx, &storage, &variable, &current_data).await?;
+
+    let updated_variable = VariableRepository::update_tx(
+        &mut tx,
+        id,
+        variable.environment_id,
+        payload.description.as_deref(),
+        new_size,
+        tags_json,
+        expected_versions.as_deref(),
+    )
+    .await?
+    .ok_or_else(precondition_failed)?;
+    let updated_variable = match expiry {
+        Some(expires_at) => VariableRepository::set_expiry_tx(&mut tx, id, expires_at).await?,
+        None => updated_variable,
+    };
 
-    // Retrieve current data
-    let data = storage.retrieve(&updated_variable.storage_path).await?;
+    // Without new data the file is left alone, as it equals the data just read
+    let data = payload.data.unwrap_or_else(|| current_data.clone());
+    commit_write(
+        tx,
+        &storage,
+        &updated_variable,
+        &current_data,
+        &data,
+        ctx.user_id,
+        payload.message.as_deref(),
+    )
+    .await?;
+
+    prune_versions(&pool, &storage, id, &tier).await;
 
-    Ok(Json(VariableResponse {
-        variable: updated_variable,
-        data: Some(data),
-    }))
+    Ok((
+        [(header::ETAG, variable_etag(&updated_variable))],
+        Json(VariableResponse {
+            variable: updated_variable,
+            data: Some(data),
+        }),
+    )
+        .into_response())
 }
 
-pub async fn delete_variable(
+/// Derive new data from the stored value of a variable with `change` and write it as a
+/// new version, keeping the variable locked throughout; honours `If-Match`. When `change`
+/// returns `None`, nothing is written and the current variable and data are returned.
+async fn change_data(
+    pool: &Pool<Postgres>,
+    storage: &FileStorage,
+    scope: &VariableScope,
+    id: Uuid,
+    headers: &HeaderMap,
+    change: impl FnOnce(&Variable, &Value) -> Result<Option<Value>>,
+) -> Result<(Variable, Value)> {
+    let accessible = VariableRepository::new(pool.clone())
+        .find_by_id(id, scope.environment.id, scope.shared_with())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    scope.authorize_variable(Action::VariableWrite, &accessible)?;
+
+    let tier = scope.variable_tier(pool, &accessible).await?;
+    let expected_versions = if_match(headers, id);
+
+    let mut tx = pool.begin().await?;
+    let variable = VariableRepository::lock_live_tx(&mut tx, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    if expected_versions.is_some_and(|versions| !versions.contains(&variable.version)) {
+        return Err(precondition_failed());
+    }
+
+    let current_data = storage.retrieve(&variable.storage_path).await?;
+    let Some(data) = change(&variable, &current_data)? else {
+        return Ok((variable, current_data));
+    };
+    validate_json_data(&data, tier.max_variable_size_mb)?;
+    enforce_schema(pool, &variable, &data).await?;
+    let size_bytes = crate::utils::json_validator::calculate_json_size(&data) as i64;
+
+    preserve_current(&mut tx, storage, &variable, &current_data).await?;
+    let updated_variable = VariableRepository::update_tx(
+        &mut tx,
+        id,
+        variable.environment_id,
+        None,
+        Some(size_bytes),
+        None,
+        None,
+    )
+    .await?
+    .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+
+    commit_write(
+        tx,
+        storage,
+        &updated_variable,
+        &current_data,
+        &data,
+        scope.ctx.user_id,
+        None,
+    )
+    .await?;
+
+    prune_versions(pool, storage, id, &tier).await;
+
+    Ok((updated_variable, data))
+}
+
+/// A written variable with its new `ETag`
+pub(crate) fn written(variable: Variable, data: Option<Value>) -> Response {
+    (
+        [(header::ETAG, variable_etag(&variable))],
+        Json(VariableResponse { variable, data }),
+    )
+        .into_response()
+}
+
+/// Apply a JSON Merge Patch or JSON Patch, as chosen by `Content-Type`, to the stored
+/// data of a variable; with `If-Match`, only while it is still at a matching version
+pub async fn patch_variable_data(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let patch = DataPatch::parse(content_type, &body)?;
+
+    let (variable, data) = change_data(&pool, &storage, &scope, id, &headers, |_, data| {
+        patch.apply(data).map(Some)
+    })
+    .await?;
+
+    Ok(written(variable, Some(data)))
+}
+
+/// Read the value at a JSON Pointer within a variable's data
+pub async fn get_variable_pointer(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariablePointerPath { id, pointer }): Path<VariablePointerPath>,
+) -> Result<Response> {
+    let accessible = VariableRepository::new(pool)
+        .find_by_id(id, scope.environment.id, scope.shared_with())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+    scope.authorize_variable(Action::VariableRead, &accessible)?;
+    let variable = accessible.variable;
+
+    let pointer = json_pointer(&pointer);
+    let mut data = storage.retrieve(&variable.storage_path).await?;
+    let value = data
+        .pointer_mut(&pointer)
+        .map(Value::take)
+        .ok_or_else(|| no_value_at(&pointer))?;
+
+    Ok(([(header::ETAG, variable_etag(&variable))], Json(value)).into_response())
+}
+
+/// Replace the value at a JSON Pointer, or add it to its parent
+pub async fn put_variable_pointer(
     State(pool): State<Pool<Postgres>>,
     State(storage): State<FileStorage>,
-    Extension(claims): Extension<Claims>,
-    Path(id): Path<Uuid>,
+    scope: VariableScope,
+    Path(VariablePointerPath { id, pointer }): Path<VariablePointerPath>,
+    headers: HeaderMap,
+    Json(value): Json<Value>,
+) -> Result<Response> {
+    let pointer = json_pointer(&pointer);
+
+    let (variable, _) = change_data(&pool, &storage, &scope, id, &headers, |_, data| {
+        DataPatch::put(data, &pointer, value)?.apply(data).map(Some)
+    })
+    .await?;
+
+    Ok(written(variable, None))
+}
+
+/// Remove the value at a JSON Pointer
+pub async fn delete_variable_pointer(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariablePointerPath { id, pointer }): Path<VariablePointerPath>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    let pointer = json_pointer(&pointer);
+
+    let (variable, _) = change_data(&pool, &storage, &scope, id, &headers, |_, data| {
+        if data.pointer(&pointer).is_none() {
+            return Err(no_value_at(&pointer));
+        }
+        DataPatch::remove(&pointer)?.apply(data).map(Some)
+    })
+    .await?;
+
+    Ok(written(variable, None))
+}
+
+/// Apply an atomic operation at a JSON Pointer while the variable is locked, such as
+/// incrementing a counter or a compare-and-swap; with `If-Match`, only while it is still at
+/// a matching version
+pub async fn apply_atomic_operation(
+    State(pool): State<Pool<Postgres>>,
+    State(storage): State<FileStorage>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    headers: HeaderMap,
+    Json(payload): Json<AtomicRequest>,
+) -> Result<Response> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let mut applied = false;
+    let (variable, data) = change_data(&pool, &storage, &scope, id, &headers, |variable, data| {
+        let changed = apply_atomic(&payload, variable.version, data)?;
+        applied = changed.is_some();
+        Ok(changed)
+    })
+    .await?;
+
+    let value = data.pointer(&payload.path).cloned().unwrap_or(Value::Null);
+    Ok((
+        [(header::ETAG, variable_etag(&variable))],
+        Json(AtomicResponse {
+            value,
+            version: variable.version,
+            applied,
+        }),
+    )
+        .into_response())
+}
+
+/// Move a variable to the trash; with `If-Match`, only while it is still at a matching version
+pub async fn delete_variable(
+    State(pool): State<Pool<Postgres>>,
+    scope: VariableScope,
+    Path(VariablePath { id }): Path<VariablePath>,
+    headers: HeaderMap,
 ) -> Result<StatusCode> {
-    let user_id = claims.user_id()?;
-    let var_repo = VariableRepository::new(pool);
+    let var_repo = VariableRepository::new(pool.clone());
 
-    // Delete from database and get the storage path
-    let variable = var_repo.delete(id, user_id).await?;
+    let variable = var_repo
+        .find_by_id(id, scope.environment.id, scope.shared_with())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+
+    scope.authorize_variable(Action::VariableDelete, &variable)?;
 
-    // Delete from storage
-    storage.delete(&variable.storage_path).await?;
+    // The data and versions stay in storage until the variable is purged from the trash
+    let expected_versions = if_match(&headers, id);
+    var_repo
+        .trash(id, scope.environment.id, expected_versions.as_deref(), scope.ctx.user_id)
+        .await?
+        .ok_or_else(|| match expected_versions {
+            Some(_) => precondition_failed(),
+            None => AppError::NotFound("Variable not found".to_string()),
+        })?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::models::{PromotionHistory, PublicUser};
+use crate::models::{KeyRotation, PromotionHistory, PublicUser, UserRole};
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct PromoteUserRequest {
@@ -42,6 +42,16 @@ pub struct UpdateUserRequest {
     pub email_verified: Option<bool>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRoleRequest {
+    pub role: UserRole,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromotionHistoryResponse {
+    pub promotions: Vec<PromotionHistory>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct PlatformAnalytics {
     pub total_users: i64,
@@ -58,3 +68,21 @@ pub struct TierUserCount {
     pub tier_name: String,
     pub user_count: i64,
 }
+
+#[derive(Debug, Serialize)]
+pub struct EncryptionStatusResponse {
+    pub enabled: bool,
+    /// Version of the master key new data keys are wrapped by
+    pub current_key_version: Option<u32>,
+    pub running_rotation: Option<KeyRotation>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyRotationResponse {
+    pub rotation: KeyRotation,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyRotationListResponse {
+    pub rotations: Vec<KeyRotation>,
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use validator::{Validate, ValidationError};
+
+use super::double_option;
+
+/// Body of `POST /variables/{id}/atomic`: one read-modify-write on the value at `path`,
+/// applied while the variable is locked
+#[derive(Debug, Clone, Deserialize, Validate)]
+#[validate(schema(function = "validate_atomic_request"))]
+pub struct AtomicRequest {
+    /// JSON Pointer to the value operated on; empty for the whole data
+    #[serde(default)]
+    pub path: String,
+
+    #[serde(flatten)]
+    pub operation: AtomicOperation,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum AtomicOperation {
+    /// Add `by` to the number at the path; a missing number counts as 0
+    Increment {
+        #[serde(default = "default_increment")]
+        by: Number,
+    },
+    /// Append `value` to the array at the path, creating the array when missing
+    Append { value: Value },
+    /// Remove every element equal to `value` from the array at the path
+    Remove { value: Value },
+    /// Set `value` at the path unless a value is there already
+    SetIfAbsent { value: Value },
+    /// Replace the value at the path with `value`, provided it still equals `expected`
+    /// and the variable is still at `expected_version`, whichever are given
+    CompareAndSwap {
+        #[serde(default, deserialize_with = "double_option")]
+        expected: Option<Option<Value>>,
+        expected_version: Option<i32>,
+        value: Value,
+    },
+}
+
+fn default_increment() -> Number {
+    Number::from(1)
+}
+
+fn validate_atomic_request(request: &AtomicRequest) -> Result<(), ValidationError> {
+    if !request.path.is_empty() && !request.path.starts_with('/') {
+        return Err(ValidationError::new("Path must be a JSON Pointer, such as /counters/visits"));
+    }
+    if let AtomicOperation::CompareAndSwap {
+        expected: None,
+        expected_version: None,
+        ..
+    } = request.operation
+    {
+        return Err(ValidationError::new("Compare-and-swap needs an expected value or version"));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct AtomicResponse {
+    /// The value at the path afterwards, `null` when there is none
+    pub value: Value,
+    pub version: i32,
+    /// False when the operation left the data as it was, such as setting a value that
+    /// is already present
+    pub applied: bool,
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::models::{AccessibleVariable, Variable};
+use crate::utils::validate_variable_key;
+
+/// Most items a single batch request may address
+pub const MAX_BATCH_SIZE: usize = 100;
+
+fn validate_batch_size(len: usize) -> Result<(), ValidationError> {
+    if len == 0 {
+        return Err(ValidationError::new("A batch needs at least one item"));
+    }
+    if len > MAX_BATCH_SIZE {
+        return Err(ValidationError::new("A batch can have at most 100 items"));
+    }
+
+    Ok(())
+}
+
+/// Body of `POST /variables:batchGet`, naming variables by id, by key, or both
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_batch_get"))]
+pub struct BatchGetRequest {
+    #[serde(default)]
+    pub ids: Vec<Uuid>,
+
+    #[serde(default)]
+    pub keys: Vec<String>,
+}
+
+fn validate_batch_get(request: &BatchGetRequest) -> Result<(), ValidationError> {
+    validate_batch_size(request.ids.len() + request.keys.len())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Found,
+    NotFound,
+    Forbidden,
+}
+
+/// One requested variable, in request order: ids first, then keys
+#[derive(Debug, Serialize)]
+pub struct BatchGetItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    pub status: BatchItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable: Option<AccessibleVariable>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchGetResponse {
+    pub items: Vec<BatchGetItem>,
+}
+
+/// One write of a batch, addressed by key
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+    /// Create the variable, or replace the data of an existing one
+    Upsert {
+        key: String,
+        description: Option<String>,
+        data: Value,
+        tags: Option<Vec<String>>,
+        /// Only applies when the variable is created
+        #[serde(default)]
+        is_encrypted: bool,
+    },
+    Delete {
+        key: String,
+    },
+}
+
+impl BatchOperation {
+    pub fn key(&self) -> &str {
+        match self {
+            BatchOperation::Upsert { key, .. } | BatchOperation::Delete { key } => key,
+        }
+    }
+}
+
+/// Body of `POST /variables:batchWrite`; the operations apply all together or not at all
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_batch_write"))]
+pub struct BatchWriteRequest {
+    pub operations: Vec<BatchOperation>,
+
+    /// Recorded with every version the batch creates
+    #[validate(length(max = 500, message = "Message cannot exceed 500 characters"))]
+    pub message: Option<String>,
+}
+
+fn validate_batch_write(request: &BatchWriteRequest) -> Result<(), ValidationError> {
+    validate_batch_size(request.operations.len())?;
+
+    let mut keys = std::collections::HashSet::new();
+    for operation in &request.operations {
+        validate_variable_key(operation.key())?;
+        if !keys.insert(operation.key()) {
+            return Err(ValidationError::new("Each key can appear only once in a batch"));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchWriteOutcome {
+    Created,
+    Updated,
+    Deleted,
+}
+
+/// The result of one operation, in request order
+#[derive(Debug, Serialize)]
+pub struct BatchWriteResult {
+    pub key: String,
+    pub outcome: BatchWriteOutcome,
+    /// The variable as written; absent once deleted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variable: Option<Variable>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchWriteResponse {
+    pub results: Vec<BatchWriteResult>,
+}
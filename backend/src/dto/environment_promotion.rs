@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::models::{EnvironmentPromotion, PromotionAction};
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct PromotionPlanRequest {
+    /// Environment to copy variables from
+    #[validate(length(min = 1, max = 100))]
+    pub source: String,
+
+    /// Environment to copy variables into
+    #[validate(length(min = 1, max = 100))]
+    pub target: String,
+
+    /// Only promote these keys; all keys of the source when absent
+    #[validate(length(min = 1, max = 1000, message = "Between 1 and 1000 keys may be promoted"))]
+    pub keys: Option<Vec<String>>,
+
+    /// Also delete target keys that don't exist in the source
+    #[serde(default)]
+    pub prune: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApplyPromotionRequest {
+    #[serde(flatten)]
+    #[validate(nested)]
+    pub plan: PromotionPlanRequest,
+
+    /// Fingerprint returned by the plan endpoint
+    #[validate(length(min = 1))]
+    pub fingerprint: String,
+}
+
+/// One key that a promotion adds, changes or removes in the target
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PromotionChange {
+    pub key: String,
+    pub action: PromotionAction,
+    pub source_version: Option<i32>,
+    pub target_version: Option<i32>,
+    /// RFC 6902 patch turning the target value into the source value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<json_patch::Patch>,
+    /// Whether description, tags or encryption differ
+    pub metadata_changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromotionPlan {
+    pub source: String,
+    pub target: String,
+    pub prune: bool,
+    pub changes: Vec<PromotionChange>,
+    pub unchanged: usize,
+    /// Identifies the state of both environments; apply refuses to run once it changes
+    pub fingerprint: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromotionListResponse {
+    pub promotions: Vec<EnvironmentPromotion>,
+}
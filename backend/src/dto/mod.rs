@@ -1,11 +1,38 @@
 pub mod admin;
+pub mod atomic;
 pub mod auth;
+pub mod batch;
+pub mod environment_promotion;
+pub mod organization;
+pub mod policy;
+pub mod project;
+pub mod query;
+pub mod schema;
 pub mod tier;
 pub mod user;
 pub mod variable;
 
 pub use admin::*;
+pub use atomic::*;
 pub use auth::*;
+pub use batch::*;
+pub use environment_promotion::*;
+pub use organization::*;
+pub use policy::*;
+pub use project::*;
+pub use query::*;
+pub use schema::*;
 pub use tier::*;
 pub use user::*;
 pub use variable::*;
+
+use serde::{Deserialize, Deserializer};
+
+/// Distinguish an absent field (`None`) from an explicit `null` (`Some(None)`)
+pub(crate) fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
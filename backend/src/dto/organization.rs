@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+
+use crate::models::{
+    MemberOrganization, OrgRole, Organization, OrganizationInvitation, OrganizationMember,
+};
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateOrganizationRequest {
+    #[validate(length(min = 1, max = 255, message = "Name must be between 1 and 255 characters"))]
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateOrganizationRequest {
+    #[validate(length(min = 1, max = 255, message = "Name must be between 1 and 255 characters"))]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrganizationResponse {
+    pub organization: Organization,
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrganizationListResponse {
+    pub organizations: Vec<MemberOrganization>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemberListResponse {
+    pub members: Vec<OrganizationMember>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateMemberRequest {
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateInvitationRequest {
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+
+    pub role: OrgRole,
+
+    #[validate(range(min = 1, max = 30, message = "Invitations expire within 1 to 30 days"))]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct InvitationListResponse {
+    pub invitations: Vec<OrganizationInvitation>,
+}
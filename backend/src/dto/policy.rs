@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::SharePermission;
+use crate::policy::{Action, Decision, Principal, Resource};
+
+/// A hypothetical request to evaluate against the policy for the caller
+#[derive(Debug, Deserialize)]
+pub struct ExplainPolicyRequest {
+    pub action: Action,
+
+    #[serde(default)]
+    pub resource: Resource,
+
+    /// Evaluate as one of the caller's API keys carrying these scopes
+    pub api_key_scopes: Option<Vec<String>>,
+
+    /// Evaluate as the recipient of a share rather than as a member of the owner
+    pub share: Option<SharePermission>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExplainPolicyResponse {
+    pub action: Action,
+    pub principal: Principal,
+    pub resource: Resource,
+    #[serde(flatten)]
+    pub decision: Decision,
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use validator::Validate;
+
+use super::double_option;
+use crate::models::{Environment, MergeMode, Project};
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateProjectRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectResponse {
+    pub project: Project,
+    pub environments: Vec<Environment>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProjectListResponse {
+    pub projects: Vec<Project>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateEnvironmentRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+
+    pub description: Option<String>,
+
+    /// Name of an environment in the same project to inherit from
+    pub parent: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateEnvironmentRequest {
+    pub description: Option<String>,
+
+    /// New parent environment name; `null` stops inheriting
+    #[serde(default, deserialize_with = "double_option")]
+    pub parent: Option<Option<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ResolveQueryParams {
+    #[serde(default)]
+    pub mode: MergeMode,
+    /// Resolve a single key instead of the whole environment
+    pub key: Option<String>,
+}
+
+/// The effective value of a key after applying inheritance
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResolvedVariable {
+    pub key: String,
+    pub value: Value,
+    /// Nearest environment that defines the key
+    pub environment: String,
+    pub environment_id: Uuid,
+    pub variable_id: Uuid,
+    /// Environments whose values were merged into `value`, outermost parent first
+    pub merged_from: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResolveResponse {
+    pub project: String,
+    pub environment: String,
+    pub mode: MergeMode,
+    /// Inheritance chain, nearest environment first
+    pub chain: Vec<String>,
+    pub variables: Vec<ResolvedVariable>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EnvironmentListResponse {
+    pub environments: Vec<Environment>,
+}
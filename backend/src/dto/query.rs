@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::models::TagMatch;
+
+/// Most variables a query across tagged variables reads
+pub const MAX_QUERY_VARIABLES: i32 = 100;
+
+/// Body of `POST /variables/{id}/query`
+#[derive(Debug, Deserialize, Validate)]
+pub struct DataQueryRequest {
+    /// RFC 9535 JSONPath expression, such as `$.services[*].timeout_ms`
+    #[validate(length(min = 1, max = 1000, message = "Path must be between 1 and 1000 characters"))]
+    pub path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataQueryResponse {
+    /// Values the expression selected, in document order
+    pub values: Vec<Value>,
+}
+
+/// Body of `POST /variables:query`, running one expression over every variable carrying
+/// the given tags
+#[derive(Debug, Deserialize, Validate)]
+pub struct TaggedDataQueryRequest {
+    #[validate(length(min = 1, max = 1000, message = "Path must be between 1 and 1000 characters"))]
+    pub path: String,
+
+    #[validate(length(min = 1, message = "At least one tag is required"))]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    pub tag_match: TagMatch,
+}
+
+/// The values selected from one variable
+#[derive(Debug, Serialize)]
+pub struct DataQueryMatch {
+    pub id: Uuid,
+    pub key: String,
+    pub values: Vec<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaggedDataQueryResponse {
+    /// Variables the expression selected anything from, ordered by key
+    pub matches: Vec<DataQueryMatch>,
+    /// Set when more variables carry the tags than a single query reads
+    pub truncated: bool,
+}
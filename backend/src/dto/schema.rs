@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::error::SchemaViolation;
+use crate::models::JsonSchema;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateJsonSchemaRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+
+    pub description: Option<String>,
+
+    /// JSON Schema, draft 2020-12
+    pub schema: Value,
+}
+
+/// Changing a schema doesn't revalidate variables already conforming to it; their data
+/// is checked on its next write
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateJsonSchemaRequest {
+    pub description: Option<String>,
+
+    pub schema: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonSchemaListResponse {
+    pub schemas: Vec<JsonSchema>,
+}
+
+/// Path parameters of routes addressing a named schema
+#[derive(Debug, Deserialize)]
+pub struct JsonSchemaPath {
+    pub id: Uuid,
+}
+
+/// Body of `PUT /variables/{id}/schema`: a named schema or an inline one
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_attach_schema"))]
+pub struct AttachSchemaRequest {
+    pub schema_id: Option<Uuid>,
+    pub schema: Option<Value>,
+}
+
+fn validate_attach_schema(request: &AttachSchemaRequest) -> Result<(), ValidationError> {
+    if request.schema_id.is_some() == request.schema.is_some() {
+        return Err(ValidationError::new("Give either schema_id or schema"));
+    }
+
+    Ok(())
+}
+
+/// Data to check without writing it
+#[derive(Debug, Deserialize)]
+pub struct ValidateDataRequest {
+    pub data: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidateDataResponse {
+    pub valid: bool,
+    pub violations: Vec<SchemaViolation>,
+}
@@ -32,9 +32,25 @@ pub struct CreateTierRequest {
     #[validate(range(min = 1))]
     pub max_api_keys: i32,
 
+    #[serde(default = "default_max_versions")]
+    #[validate(range(min = 1))]
+    pub max_versions: i32,
+
+    #[serde(default = "default_trash_retention_days")]
+    #[validate(range(min = -1))]
+    pub trash_retention_days: i32,
+
     pub price_monthly: i32, // in cents
 }
 
+fn default_max_versions() -> i32 {
+    10
+}
+
+fn default_trash_retention_days() -> i32 {
+    30
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct UpdateTierRequest {
     pub name: Option<String>,
@@ -43,6 +59,9 @@ pub struct UpdateTierRequest {
     pub max_variable_size_mb: Option<i32>,
     pub max_requests_per_day: Option<i32>,
     pub max_api_keys: Option<i32>,
+    pub max_versions: Option<i32>,
+    #[validate(range(min = -1))]
+    pub trash_retention_days: Option<i32>,
     pub price_monthly: Option<i32>,
     pub is_active: Option<bool>,
 }
@@ -4,6 +4,7 @@ use uuid::Uuid;
 use validator::Validate;
 
 use crate::models::{ApiKey, PublicUser};
+use crate::utils::validate_api_key_scopes;
 
 #[derive(Debug, Serialize)]
 pub struct UserProfileResponse {
@@ -34,7 +35,12 @@ pub struct CreateApiKeyRequest {
 
     pub expires_in_days: Option<i32>,
 
+    /// Scopes granted to the key, as an array of strings such as `["pii:read"]`
+    #[validate(custom(function = "validate_api_key_scopes"))]
     pub permissions: Option<serde_json::Value>,
+
+    /// Restrict the key to a single environment of the owner
+    pub environment_id: Option<Uuid>,
 }
 
 #[derive(Debug, Serialize)]
@@ -1,10 +1,24 @@
+use chrono::{DateTime, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use validator::Validate;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
 
-use crate::models::Variable;
+use super::double_option;
+use crate::models::{
+    AccessibleVariable, ScheduledChange, SharePermission, TagCount, TagMatch, TrashedVariable,
+    Variable, VariableFilter, VariableShare, VariableShareLink, VariableVersion,
+};
+
+/// Path parameters of single-variable routes; project and environment segments, when
+/// present, are consumed by `VariableScope`
+#[derive(Debug, Deserialize)]
+pub struct VariablePath {
+    pub id: Uuid,
+}
 
 #[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_create_variable"))]
 pub struct CreateVariableRequest {
     #[validate(length(min = 1, max = 255, message = "Key must be between 1 and 255 characters"))]
     pub key: String,
@@ -17,15 +31,151 @@ pub struct CreateVariableRequest {
 
     #[serde(default)]
     pub is_encrypted: bool,
+
+    /// Recorded with the first version
+    #[validate(length(max = 500, message = "Message cannot exceed 500 characters"))]
+    pub message: Option<String>,
+
+    /// Named schema the data must conform to
+    pub schema_id: Option<Uuid>,
+
+    /// Schema of this variable alone the data must conform to
+    pub schema: Option<Value>,
+
+    /// When the variable expires
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Seconds until the variable expires, instead of `expires_at`
+    #[validate(range(min = 1, max = 315_360_000, message = "TTL must be 1 second to 10 years"))]
+    pub ttl_seconds: Option<i64>,
+}
+
+impl CreateVariableRequest {
+    /// When the created variable expires, if ever
+    pub fn expiry(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        expiry(self.expires_at, self.ttl_seconds, now)
+    }
+}
+
+/// `expires_at`, or `ttl_seconds` after `now`
+fn expiry(
+    expires_at: Option<DateTime<Utc>>,
+    ttl_seconds: Option<i64>,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    match ttl_seconds {
+        Some(ttl) => TimeDelta::try_seconds(ttl).and_then(|ttl| now.checked_add_signed(ttl)),
+        None => expires_at,
+    }
+}
+
+fn validate_expiry(
+    expires_at: Option<DateTime<Utc>>,
+    ttl_seconds: Option<i64>,
+) -> Result<(), ValidationError> {
+    if expires_at.is_some() && ttl_seconds.is_some() {
+        return Err(ValidationError::new("Give at most one of expires_at and ttl_seconds"));
+    }
+    if expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        return Err(ValidationError::new("expires_at must be in the future"));
+    }
+
+    Ok(())
+}
+
+fn validate_create_variable(request: &CreateVariableRequest) -> Result<(), ValidationError> {
+    if request.schema_id.is_some() && request.schema.is_some() {
+        return Err(ValidationError::new("Give at most one of schema_id and schema"));
+    }
+
+    validate_expiry(request.expires_at, request.ttl_seconds)
 }
 
 #[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_update_variable"))]
 pub struct UpdateVariableRequest {
     pub description: Option<String>,
 
     pub data: Option<Value>,
 
     pub tags: Option<Vec<String>>,
+
+    /// Recorded with the version this update creates
+    #[validate(length(max = 500, message = "Message cannot exceed 500 characters"))]
+    pub message: Option<String>,
+
+    /// New expiry; `null` makes the variable permanent
+    #[serde(default, deserialize_with = "double_option")]
+    pub expires_at: Option<Option<DateTime<Utc>>>,
+
+    /// Seconds from now until the variable expires, instead of `expires_at`
+    #[validate(range(min = 1, max = 315_360_000, message = "TTL must be 1 second to 10 years"))]
+    pub ttl_seconds: Option<i64>,
+}
+
+impl UpdateVariableRequest {
+    /// The variable's new expiry, `Some(None)` to make it permanent, or `None` to keep it
+    pub fn expiry(&self, now: DateTime<Utc>) -> Option<Option<DateTime<Utc>>> {
+        match (self.expires_at, self.ttl_seconds) {
+            (None, None) => None,
+            (expires_at, ttl_seconds) => Some(expiry(expires_at.flatten(), ttl_seconds, now)),
+        }
+    }
+}
+
+fn validate_update_variable(request: &UpdateVariableRequest) -> Result<(), ValidationError> {
+    if request.expires_at.is_some() && request.ttl_seconds.is_some() {
+        return Err(ValidationError::new("Give at most one of expires_at and ttl_seconds"));
+    }
+
+    validate_expiry(request.expires_at.flatten(), request.ttl_seconds)
+}
+
+/// Body of `PUT /by-key/{key}`, which creates the variable or replaces its data
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_upsert_variable"))]
+pub struct UpsertVariableRequest {
+    pub description: Option<String>,
+
+    pub data: Value,
+
+    pub tags: Option<Vec<String>>,
+
+    /// Must match an existing variable's
+    #[serde(default)]
+    pub is_encrypted: bool,
+
+    #[validate(length(max = 500, message = "Message cannot exceed 500 characters"))]
+    pub message: Option<String>,
+
+    /// Must match an existing variable's; `PUT /{id}/schema` changes it
+    pub schema_id: Option<Uuid>,
+
+    /// Must match an existing variable's; `PUT /{id}/schema` changes it
+    pub schema: Option<Value>,
+
+    /// When the variable expires; without this or `ttl_seconds` it never does
+    pub expires_at: Option<DateTime<Utc>>,
+
+    #[validate(range(min = 1, max = 315_360_000, message = "TTL must be 1 second to 10 years"))]
+    pub ttl_seconds: Option<i64>,
+}
+
+fn validate_upsert_variable(request: &UpsertVariableRequest) -> Result<(), ValidationError> {
+    validate_expiry(request.expires_at, request.ttl_seconds)
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GetVariableParams {
+    /// Replace `{"$ref": key}` objects and `${key}` placeholders with the referenced data
+    #[serde(default)]
+    pub resolve: bool,
+}
+
+/// Path parameters of routes addressing a variable by key
+#[derive(Debug, Deserialize)]
+pub struct VariableKeyPath {
+    pub key: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -37,16 +187,255 @@ pub struct VariableResponse {
 
 #[derive(Debug, Serialize)]
 pub struct VariableListResponse {
-    pub variables: Vec<Variable>,
+    pub variables: Vec<AccessibleVariable>,
     pub total: i64,
     pub page: i32,
     pub page_size: i32,
 }
 
+/// Path parameters of routes addressing a value within a variable's data by JSON Pointer
+#[derive(Debug, Deserialize)]
+pub struct VariablePointerPath {
+    pub id: Uuid,
+    /// The pointer without its leading `/`
+    pub pointer: String,
+}
+
+/// Path parameters of routes addressing one version of a variable
+#[derive(Debug, Deserialize)]
+pub struct VariableVersionPath {
+    pub id: Uuid,
+    pub version: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VariableVersionResponse {
+    #[serde(flatten)]
+    pub version: VariableVersion,
+    pub data: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VariableVersionListResponse {
+    pub versions: Vec<VariableVersion>,
+}
+
+/// A variable whose data references a key, directly or through `depth - 1` others
+#[derive(Debug, Serialize)]
+pub struct Referrer {
+    #[serde(flatten)]
+    pub variable: Variable,
+    pub depth: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReferrersResponse {
+    pub key: String,
+    pub referrers: Vec<Referrer>,
+    /// Referrers the caller may not read
+    pub hidden: usize,
+}
+
+/// Data to replace a variable's data with at a later time
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "validate_schedule_change"))]
+pub struct ScheduleChangeRequest {
+    pub data: Value,
+
+    pub apply_at: DateTime<Utc>,
+
+    /// Recorded with the version the change creates
+    #[validate(length(max = 500, message = "Message cannot exceed 500 characters"))]
+    pub message: Option<String>,
+}
+
+fn validate_schedule_change(request: &ScheduleChangeRequest) -> Result<(), ValidationError> {
+    if request.apply_at <= Utc::now() {
+        return Err(ValidationError::new("apply_at must be in the future"));
+    }
+
+    Ok(())
+}
+
+/// Path parameters of routes addressing one scheduled change of a variable
+#[derive(Debug, Deserialize)]
+pub struct ScheduledChangePath {
+    pub id: Uuid,
+    pub change_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScheduledChangeListResponse {
+    pub changes: Vec<ScheduledChange>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct VariableQueryParams {
     pub page: Option<i32>,
     pub page_size: Option<i32>,
     pub search: Option<String>,
+    /// Full-text query; results are ordered by relevance and highlighted
+    pub q: Option<String>,
     pub tags: Option<String>, // Comma-separated tags
+    /// Whether variables must carry all of `tags` (the default) or any of them
+    pub tag_match: Option<TagMatch>,
+    /// Only variables expiring within this many seconds, soonest first
+    pub expiring_within: Option<i64>,
+}
+
+impl VariableQueryParams {
+    pub fn filter(&self) -> VariableFilter {
+        let tags = self
+            .tags
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        VariableFilter {
+            search: self.search.clone().filter(|s| !s.is_empty()),
+            query: self.q.clone().filter(|q| !q.trim().is_empty()),
+            tags,
+            tag_match: self.tag_match.unwrap_or_default(),
+            expiring_within: self.expiring_within.map(|seconds| seconds.max(0)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TagListResponse {
+    pub tags: Vec<TagCount>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RenameTagRequest {
+    #[validate(length(min = 1, max = 100, message = "Tags must be between 1 and 100 characters"))]
+    pub from: String,
+
+    #[validate(length(min = 1, max = 100, message = "Tags must be between 1 and 100 characters"))]
+    pub to: String,
+}
+
+/// Path parameters of routes addressing a tag
+#[derive(Debug, Deserialize)]
+pub struct TagPath {
+    pub tag: String,
+}
+
+/// Variables changed by a bulk tag operation
+#[derive(Debug, Serialize)]
+pub struct TagChangeResponse {
+    pub updated: Vec<Variable>,
+}
+
+/// A variable in the trash, with when the retention sweeper purges it
+#[derive(Debug, Serialize)]
+pub struct TrashItem {
+    #[serde(flatten)]
+    pub variable: TrashedVariable,
+    /// `None` when the owner's tier keeps the trash until it is emptied
+    pub purge_at: Option<DateTime<Utc>>,
+}
+
+impl TrashItem {
+    /// `retention_days` as given by `Tier::trash_retention`
+    pub fn new(variable: TrashedVariable, retention_days: Option<i64>) -> Self {
+        let purge_at = variable
+            .variable
+            .deleted_at
+            .zip(retention_days)
+            .map(|(deleted_at, days)| deleted_at + TimeDelta::days(days));
+
+        Self { variable, purge_at }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashListResponse {
+    pub variables: Vec<TrashItem>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EmptyTrashResponse {
+    pub purged: usize,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateShareRequest {
+    /// Email of the user to share with
+    #[validate(email(message = "Invalid email format"))]
+    pub email: String,
+
+    #[serde(default = "default_share_permission")]
+    pub permission: SharePermission,
+}
+
+fn default_share_permission() -> SharePermission {
+    SharePermission::Read
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShareListResponse {
+    pub shares: Vec<VariableShare>,
+}
+
+/// Path parameters of a single share of a variable
+#[derive(Debug, Deserialize)]
+pub struct SharePath {
+    pub id: Uuid,
+    pub share_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetVisibilityRequest {
+    pub is_public: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateShareLinkRequest {
+    /// Lifetime of the link, up to 30 days
+    #[serde(default = "default_link_lifetime")]
+    #[validate(range(min = 1, max = 43200, message = "Links can last between 1 minute and 30 days"))]
+    pub expires_in_minutes: i64,
+}
+
+fn default_link_lifetime() -> i64 {
+    60
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShareLinkResponse {
+    #[serde(flatten)]
+    pub link: VariableShareLink,
+    /// Signed path granting read access until the link expires
+    pub url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShareLinkListResponse {
+    pub links: Vec<VariableShareLink>,
+}
+
+/// Path parameters of a single share link of a variable
+#[derive(Debug, Deserialize)]
+pub struct ShareLinkPath {
+    pub id: Uuid,
+    pub link_id: Uuid,
+}
+
+/// Path of a public variable: the owning user's or organization's id and the key
+#[derive(Debug, Deserialize)]
+pub struct PublicVariablePath {
+    pub owner: Uuid,
+    pub key: String,
+}
+
+/// Signature of a share link URL
+#[derive(Debug, Deserialize)]
+pub struct ShareLinkQuery {
+    pub expires: i64,
+    pub signature: String,
 }
@@ -3,9 +3,20 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use serde::Serialize;
 use serde_json::json;
 use thiserror::Error;
 
+/// Where and why data failed to conform to its JSON Schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SchemaViolation {
+    /// JSON Pointer to the offending value
+    pub instance_path: String,
+    /// JSON Pointer to the schema keyword it failed
+    pub schema_path: String,
+    pub message: String,
+}
+
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
@@ -50,6 +61,15 @@ pub enum AppError {
     #[error("Conflict: {0}")]
     Conflict(String),
 
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+
+    #[error("Data does not conform to its schema")]
+    SchemaViolation(Vec<SchemaViolation>),
+
     #[error("Password hash error")]
     PasswordHash,
 }
@@ -91,18 +111,31 @@ impl IntoResponse for AppError {
                 (StatusCode::BAD_REQUEST, "Invalid JSON data")
             }
             AppError::Conflict(ref msg) => (StatusCode::CONFLICT, msg.as_str()),
+            AppError::PreconditionFailed(ref msg) => {
+                (StatusCode::PRECONDITION_FAILED, msg.as_str())
+            }
+            AppError::UnsupportedMediaType(ref msg) => {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, msg.as_str())
+            }
+            AppError::SchemaViolation(_) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Data does not conform to its schema",
+            ),
             AppError::PasswordHash => {
                 tracing::error!("Password hash error");
                 (StatusCode::INTERNAL_SERVER_ERROR, "Authentication error")
             }
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "error": error_message,
             "status": status.as_u16(),
-        }));
+        });
+        if let AppError::SchemaViolation(ref violations) = self {
+            body["violations"] = json!(violations);
+        }
 
-        (status, body).into_response()
+        (status, Json(body)).into_response()
     }
 }
 
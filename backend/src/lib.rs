@@ -4,7 +4,9 @@ pub mod dto;
 pub mod error;
 pub mod middleware;
 pub mod models;
+pub mod policy;
 pub mod repositories;
+pub mod services;
 pub mod storage;
 pub mod utils;
 
@@ -1,4 +1,5 @@
 use axum::{
+    http::header,
     middleware,
     routing::{delete, get, patch, post, put},
     Router,
@@ -6,33 +7,85 @@ use axum::{
 use cloud_variables::{
     api::{
         admin::{
-            create_tier, delete_tier, delete_user, list_tiers as admin_list_tiers,
-            list_users, promote_user, update_tier, update_user,
+            create_key_rotation, create_tier, delete_tier, delete_user, get_encryption_status,
+            get_key_rotation, get_tier, list_key_rotations, list_tiers as admin_list_tiers,
+            list_user_promotions, list_users, promote_user, resume_key_rotation, update_tier,
+            update_user, update_user_role,
         },
         auth::{login, register},
+        environment_promotions::{
+            apply_environment_promotion, list_environment_promotions, plan_environment_promotion,
+        },
         health::health_check,
+        policy::explain_policy,
+        public::{get_linked_variable, get_public_variable},
+        organizations::{
+            accept_invitation, create_invitation, create_organization, decline_invitation,
+            delete_organization, get_organization, list_invitations, list_members,
+            list_my_invitations, list_organizations, remove_member, revoke_invitation,
+            update_member, update_organization,
+        },
+        projects::{
+            create_environment, create_project, delete_environment, delete_project,
+            get_project, list_environments, list_projects, resolve_variables, update_environment,
+        },
+        schemas::{
+            create_schema, delete_schema, get_schema, list_schemas, update_schema,
+            validate_against_schema,
+        },
+        tags::{delete_tag, list_tags, rename_tag},
+        trash::{empty_trash, list_trash, purge_trashed_variable, restore_trashed_variable},
         users::{
             change_password, create_api_key, delete_api_key, get_profile, list_api_keys,
             revoke_api_key,
         },
+        variable_batch::{batch_get_variables, batch_write_variables},
+        variable_keys::{
+            delete_variable_by_key, get_variable_by_key, put_variable_by_key,
+            update_variable_by_key, validate_variable_data_by_key,
+        },
+        variable_query::{query_tagged_variables, query_variable},
+        variable_references::list_referrers,
+        variable_schedules::{
+            cancel_scheduled_change, create_scheduled_change, list_scheduled_changes,
+        },
+        variable_schemas::{delete_variable_schema, put_variable_schema, validate_variable_data},
+        variable_shares::{
+            create_share, create_share_link, list_share_links, list_shares, revoke_share,
+            revoke_share_link, set_visibility,
+        },
+        variable_versions::{get_version, list_versions, rollback_variable},
         variables::{
-            create_variable, delete_variable, get_variable, list_variables, update_variable,
+            apply_atomic_operation, create_variable, delete_variable, delete_variable_pointer,
+            get_variable, get_variable_pointer, list_variables, patch_variable_data,
+            put_variable_pointer, update_variable,
         },
     },
     db::{create_pool_from_env, DbConfig},
-    middleware::{admin_middleware, auth_middleware, request_logger_middleware},
+    middleware::{
+        auth_middleware, permission_middleware, reject_api_key_middleware,
+        request_logger_middleware,
+    },
+    models::Permission,
+    policy::PolicyEngine,
+    services::{
+        spawn_content_backfill, spawn_expiry_sweeper, spawn_reference_backfill,
+        spawn_rotation_watchdog, spawn_scheduler, spawn_trash_sweeper,
+    },
     storage::FileStorage,
+    utils::Keyring,
 };
 use sqlx::{migrate::MigrateDatabase, Postgres, Pool};
-use std::env;
+use std::{env, sync::Arc};
 use tower_http::cors::{Any, CorsLayer};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[derive(Clone)]
 struct AppState {
     pool: Pool<Postgres>,
     storage: FileStorage,
+    policy: Arc<PolicyEngine>,
 }
 
 impl axum::extract::FromRef<AppState> for Pool<Postgres> {
@@ -47,6 +100,12 @@ impl axum::extract::FromRef<AppState> for FileStorage {
     }
 }
 
+impl axum::extract::FromRef<AppState> for Arc<PolicyEngine> {
+    fn from_ref(state: &AppState) -> Self {
+        state.policy.clone()
+    }
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Initialize tracing
@@ -91,49 +150,221 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize file storage
     let storage_path = env::var("STORAGE_PATH").unwrap_or_else(|_| "./data/variables".to_string());
-    let storage = FileStorage::new(&storage_path);
+    let mut storage = FileStorage::new(&storage_path);
+    match Keyring::from_env()? {
+        Some(keyring) => {
+            info!(
+                "Server-side encryption enabled with master key v{}",
+                keyring.current().version()
+            );
+            storage = storage.with_keyring(keyring);
+        }
+        None => warn!("No encryption master key configured; encrypted variables are rejected"),
+    }
     storage.init().await?;
     info!("Storage initialized at: {}", storage_path);
 
+    // Resume key rotations interrupted by a restart or left behind by another server
+    spawn_rotation_watchdog(pool.clone(), storage.clone());
+
+    // Index the contents of variables stored before contents were searchable
+    spawn_content_backfill(pool.clone(), storage.clone());
+
+    // Record the references of variables stored before references were recorded
+    spawn_reference_backfill(pool.clone(), storage.clone());
+
+    // Delete expired variables in the background
+    spawn_expiry_sweeper(pool.clone(), storage.clone());
+
+    // Purge trashed variables past their tier's retention in the background
+    spawn_trash_sweeper(pool.clone(), storage.clone());
+
+    // Apply scheduled changes once they are due
+    spawn_scheduler(pool.clone(), storage.clone());
+
+    // Load the authorization policy
+    let policy = Arc::new(PolicyEngine::from_env().await?);
+    info!("Authorization policy loaded with {} rules", policy.rules().len());
+
     // Create shared app state
     let state = AppState {
         pool: pool.clone(),
         storage,
+        policy: policy.clone(),
     };
 
     // Build public routes (no authentication required)
     let public_routes = Router::new()
         .route("/health", get(health_check))
         .route("/auth/register", post(register))
-        .route("/auth/login", post(login));
+        .route("/auth/login", post(login))
+        .route("/public/links/{link_id}", get(get_linked_variable))
+        .route("/public/{owner}/{key}", get(get_public_variable));
+
+    // Variable routes, served both for the default environment and per project environment
+    let variable_routes = Router::new()
+        .route("/", post(create_variable))
+        .route("/", get(list_variables))
+        .route("/by-key/{key}", get(get_variable_by_key))
+        .route("/by-key/{key}", put(put_variable_by_key))
+        .route("/by-key/{key}", patch(update_variable_by_key))
+        .route("/by-key/{key}", delete(delete_variable_by_key))
+        .route("/by-key/{key}/validate", post(validate_variable_data_by_key))
+        .route("/by-key/{key}/referrers", get(list_referrers))
+        .route("/{id}", get(get_variable))
+        .route("/{id}", patch(update_variable))
+        .route("/{id}", delete(delete_variable))
+        .route("/{id}/data", patch(patch_variable_data))
+        .route("/{id}/data/{*pointer}", get(get_variable_pointer))
+        .route("/{id}/data/{*pointer}", put(put_variable_pointer))
+        .route("/{id}/data/{*pointer}", delete(delete_variable_pointer))
+        .route("/{id}/atomic", post(apply_atomic_operation))
+        .route("/{id}/query", post(query_variable))
+        .route("/{id}/schema", put(put_variable_schema))
+        .route("/{id}/schema", delete(delete_variable_schema))
+        .route("/{id}/validate", post(validate_variable_data))
+        .route("/{id}/shares", post(create_share))
+        .route("/{id}/shares", get(list_shares))
+        .route("/{id}/shares/{share_id}", delete(revoke_share))
+        .route("/{id}/visibility", put(set_visibility))
+        .route("/{id}/links", post(create_share_link))
+        .route("/{id}/links", get(list_share_links))
+        .route("/{id}/links/{link_id}", delete(revoke_share_link))
+        .route("/{id}/versions", get(list_versions))
+        .route("/{id}/versions/{version}", get(get_version))
+        .route("/{id}/rollback/{version}", post(rollback_variable))
+        .route("/{id}/scheduled", post(create_scheduled_change))
+        .route("/{id}/scheduled", get(list_scheduled_changes))
+        .route("/{id}/scheduled/{change_id}", delete(cancel_scheduled_change));
+
+    // Tag routes, scoped to an environment like the variable routes
+    let tag_routes = Router::new()
+        .route("/", get(list_tags))
+        .route("/rename", post(rename_tag))
+        .route("/{tag}", delete(delete_tag));
+
+    // Trash routes, scoped to an environment like the variable routes
+    let trash_routes = Router::new()
+        .route("/", get(list_trash))
+        .route("/", delete(empty_trash))
+        .route("/{id}", delete(purge_trashed_variable))
+        .route("/{id}/restore", post(restore_trashed_variable));
+
+    // Organization and invitation routes, which API keys may not use
+    let organization_routes = Router::new()
+        .route("/api/orgs", post(create_organization))
+        .route("/api/orgs", get(list_organizations))
+        .route("/api/orgs/{id}", get(get_organization))
+        .route("/api/orgs/{id}", patch(update_organization))
+        .route("/api/orgs/{id}", delete(delete_organization))
+        .route("/api/orgs/{id}/members", get(list_members))
+        .route("/api/orgs/{id}/members/{user_id}", patch(update_member))
+        .route("/api/orgs/{id}/members/{user_id}", delete(remove_member))
+        .route("/api/orgs/{id}/invitations", post(create_invitation))
+        .route("/api/orgs/{id}/invitations", get(list_invitations))
+        .route("/api/orgs/{id}/invitations/{invitation_id}", delete(revoke_invitation))
+        .route("/api/invitations", get(list_my_invitations))
+        .route("/api/invitations/{id}/accept", post(accept_invitation))
+        .route("/api/invitations/{id}/decline", post(decline_invitation))
+        .route_layer(middleware::from_fn(reject_api_key_middleware));
 
     // Build protected user routes (requires authentication)
     let protected_routes = Router::new()
         .route("/api/profile", get(get_profile))
         .route("/api/profile/password", put(change_password))
-        .route("/api/variables", post(create_variable))
-        .route("/api/variables", get(list_variables))
-        .route("/api/variables/{id}", get(get_variable))
-        .route("/api/variables/{id}", patch(update_variable))
-        .route("/api/variables/{id}", delete(delete_variable))
+        .nest("/api/variables", variable_routes.clone())
+        .nest("/api/projects/{project}/envs/{env}/variables", variable_routes)
+        .route("/api/variables:batchGet", post(batch_get_variables))
+        .route("/api/variables:batchWrite", post(batch_write_variables))
+        .route("/api/variables:query", post(query_tagged_variables))
+        .route(
+            "/api/projects/{project}/envs/{env}/variables:batchGet",
+            post(batch_get_variables),
+        )
+        .route(
+            "/api/projects/{project}/envs/{env}/variables:batchWrite",
+            post(batch_write_variables),
+        )
+        .route(
+            "/api/projects/{project}/envs/{env}/variables:query",
+            post(query_tagged_variables),
+        )
+        .nest("/api/tags", tag_routes.clone())
+        .nest("/api/projects/{project}/envs/{env}/tags", tag_routes)
+        .nest("/api/trash", trash_routes.clone())
+        .nest("/api/projects/{project}/envs/{env}/trash", trash_routes)
+        .route("/api/schemas", post(create_schema))
+        .route("/api/schemas", get(list_schemas))
+        .route("/api/schemas/{id}", get(get_schema))
+        .route("/api/schemas/{id}", patch(update_schema))
+        .route("/api/schemas/{id}", delete(delete_schema))
+        .route("/api/schemas/{id}/validate", post(validate_against_schema))
+        .route("/api/projects", post(create_project))
+        .route("/api/projects", get(list_projects))
+        .route("/api/projects/{project}", get(get_project))
+        .route("/api/projects/{project}", delete(delete_project))
+        .route("/api/projects/{project}/envs", post(create_environment))
+        .route("/api/projects/{project}/envs", get(list_environments))
+        .route("/api/projects/{project}/envs/{env}", patch(update_environment))
+        .route("/api/projects/{project}/envs/{env}", delete(delete_environment))
+        .route("/api/projects/{project}/envs/{env}/resolve", get(resolve_variables))
+        .route("/api/projects/{project}/promotions", get(list_environment_promotions))
+        .route("/api/projects/{project}/promotions/plan", post(plan_environment_promotion))
+        .route("/api/projects/{project}/promotions/apply", post(apply_environment_promotion))
+        .route("/api/policy/explain", post(explain_policy))
         .route("/api/api-keys", post(create_api_key))
         .route("/api/api-keys", get(list_api_keys))
         .route("/api/api-keys/{id}/revoke", post(revoke_api_key))
         .route("/api/api-keys/{id}", delete(delete_api_key))
-        .layer(middleware::from_fn(auth_middleware));
+        .merge(organization_routes)
+        .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
 
-    // Build admin routes (requires admin role)
+    // Build admin routes, each guarded by the permission its handler requires
+    let require = |permission| {
+        middleware::from_fn_with_state((policy.clone(), permission), permission_middleware)
+    };
     let admin_routes = Router::new()
-        .route("/admin/users", get(list_users))
-        .route("/admin/users/{id}", patch(update_user))
-        .route("/admin/users/{id}", delete(delete_user))
-        .route("/admin/users/{id}/promote", post(promote_user))
-        .route("/admin/tiers", post(create_tier))
-        .route("/admin/tiers", get(admin_list_tiers))
-        .route("/admin/tiers/{id}", patch(update_tier))
-        .route("/admin/tiers/{id}", delete(delete_tier))
-        .layer(middleware::from_fn(admin_middleware))
-        .layer(middleware::from_fn(auth_middleware));
+        .route("/admin/users", get(list_users).route_layer(require(Permission::UsersRead)))
+        .route("/admin/users/{id}", patch(update_user).route_layer(require(Permission::UsersWrite)))
+        .route("/admin/users/{id}", delete(delete_user).route_layer(require(Permission::UsersDelete)))
+        .route(
+            "/admin/users/{id}/role",
+            put(update_user_role).route_layer(require(Permission::UsersManageRoles)),
+        )
+        .route(
+            "/admin/users/{id}/promote",
+            post(promote_user).route_layer(require(Permission::BillingWrite)),
+        )
+        .route(
+            "/admin/users/{id}/promotions",
+            get(list_user_promotions).route_layer(require(Permission::BillingRead)),
+        )
+        .route("/admin/tiers", post(create_tier).route_layer(require(Permission::TiersWrite)))
+        .route("/admin/tiers", get(admin_list_tiers).route_layer(require(Permission::TiersRead)))
+        .route("/admin/tiers/{id}", get(get_tier).route_layer(require(Permission::TiersRead)))
+        .route("/admin/tiers/{id}", patch(update_tier).route_layer(require(Permission::TiersWrite)))
+        .route("/admin/tiers/{id}", delete(delete_tier).route_layer(require(Permission::TiersWrite)))
+        .route(
+            "/admin/encryption",
+            get(get_encryption_status).route_layer(require(Permission::EncryptionRead)),
+        )
+        .route(
+            "/admin/encryption/rotations",
+            post(create_key_rotation).route_layer(require(Permission::EncryptionWrite)),
+        )
+        .route(
+            "/admin/encryption/rotations",
+            get(list_key_rotations).route_layer(require(Permission::EncryptionRead)),
+        )
+        .route(
+            "/admin/encryption/rotations/{id}",
+            get(get_key_rotation).route_layer(require(Permission::EncryptionRead)),
+        )
+        .route(
+            "/admin/encryption/rotations/{id}/resume",
+            post(resume_key_rotation).route_layer(require(Permission::EncryptionWrite)),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), auth_middleware));
 
     // Combine all routes
     let app = Router::new()
@@ -145,7 +376,8 @@ async fn main() -> anyhow::Result<()> {
             CorsLayer::new()
                 .allow_origin(Any)
                 .allow_methods(Any)
-                .allow_headers(Any),
+                .allow_headers(Any)
+                .expose_headers([header::ETAG]),
         )
         .with_state(state);
 
@@ -1,17 +1,26 @@
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     http::header::AUTHORIZATION,
     middleware::Next,
     response::Response,
 };
+use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
 use crate::models::UserRole;
-use crate::utils::{Claims, JwtConfig};
+use crate::repositories::{ApiKeyRepository, UserRepository};
+use crate::utils::{extract_key_prefix, verify_password, Claims, JwtConfig};
 
-/// Extract and validate JWT token from Authorization header
-pub async fn auth_middleware(mut req: Request, next: Next) -> Result<Response> {
+/// Prefix shared by every generated API key secret
+const API_KEY_PREFIX: &str = "cv_";
+
+/// Extract and validate a JWT or API key from the Authorization header
+pub async fn auth_middleware(
+    State(pool): State<Pool<Postgres>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response> {
     let auth_header = req
         .headers()
         .get(AUTHORIZATION)
@@ -22,8 +31,12 @@ pub async fn auth_middleware(mut req: Request, next: Next) -> Result<Response> {
         .strip_prefix("Bearer ")
         .ok_or_else(|| AppError::Authentication("Invalid authorization format".to_string()))?;
 
-    let jwt_config = JwtConfig::from_env();
-    let claims = jwt_config.verify_token(token)?;
+    let claims = if token.starts_with(API_KEY_PREFIX) {
+        authenticate_api_key(&pool, token).await?
+    } else {
+        let jwt_config = JwtConfig::from_env();
+        jwt_config.verify_token(token)?
+    };
 
     // Store claims in request extensions for later use
     req.extensions_mut().insert(claims);
@@ -31,6 +44,52 @@ pub async fn auth_middleware(mut req: Request, next: Next) -> Result<Response> {
     Ok(next.run(req).await)
 }
 
+/// Route-level guard: rejects callers authenticated with an API key. Keys act on the
+/// variables they are scoped to; managing organizations and invitations needs a signed-in
+/// user.
+pub async fn reject_api_key_middleware(req: Request, next: Next) -> Result<Response> {
+    let claims = req
+        .extensions()
+        .get::<Claims>()
+        .ok_or_else(|| AppError::Authentication("User not authenticated".to_string()))?;
+
+    if claims.is_api_key() {
+        return Err(AppError::Authorization(
+            "API keys cannot manage organizations or invitations".to_string(),
+        ));
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Resolve an API key secret to claims for the user that created it
+async fn authenticate_api_key(pool: &Pool<Postgres>, secret: &str) -> Result<Claims> {
+    let key_repo = ApiKeyRepository::new(pool.clone());
+    let user_repo = UserRepository::new(pool.clone());
+
+    let invalid = || AppError::Authentication("Invalid API key".to_string());
+
+    let api_key = key_repo
+        .find_by_prefix(&extract_key_prefix(secret))
+        .await?
+        .filter(|key| key.is_valid())
+        .ok_or_else(invalid)?;
+
+    if !verify_password(secret, &api_key.key_hash)? {
+        return Err(invalid());
+    }
+
+    let user = user_repo
+        .find_by_id(api_key.user_id)
+        .await?
+        .filter(|user| user.is_active)
+        .ok_or_else(invalid)?;
+
+    key_repo.update_last_used(api_key.id).await?;
+
+    Ok(Claims::for_api_key(&user, &api_key))
+}
+
 /// Extension trait to easily extract authenticated user info from request
 pub trait AuthenticatedUser {
     fn user_id(&self) -> Result<Uuid>;
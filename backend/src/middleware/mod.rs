@@ -1,7 +1,7 @@
 pub mod auth;
-pub mod admin_auth;
+pub mod permission;
 pub mod request_logger;
 
 pub use auth::*;
-pub use admin_auth::*;
+pub use permission::*;
 pub use request_logger::*;
@@ -0,0 +1,35 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::{AppError, Result};
+use crate::models::Permission;
+use crate::policy::{Action, PolicyEngine, PolicyRequest, Principal, Resource};
+use crate::utils::Claims;
+
+/// Route-level guard: rejects callers the policy doesn't allow `admin:<permission>`.
+///
+/// Attach per route with `route_layer(middleware::from_fn_with_state(
+/// (policy, Permission::UsersRead), permission_middleware))`.
+pub async fn permission_middleware(
+    State((policy, permission)): State<(Arc<PolicyEngine>, Permission)>,
+    req: Request,
+    next: Next,
+) -> Result<Response> {
+    let claims = req
+        .extensions()
+        .get::<Claims>()
+        .ok_or_else(|| AppError::Authorization("User not authenticated".to_string()))?;
+
+    policy.authorize(&PolicyRequest {
+        principal: Principal::from_claims(claims)?,
+        action: Action::Admin(permission),
+        resource: Resource::default(),
+    })?;
+
+    Ok(next.run(req).await)
+}
@@ -3,10 +3,14 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use super::organization::Owner;
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct ApiKey {
     pub id: Uuid,
     pub user_id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub environment_id: Option<Uuid>,
     pub name: String,
     #[serde(skip_serializing)]
     pub key_hash: String,
@@ -19,6 +23,13 @@ pub struct ApiKey {
 }
 
 impl ApiKey {
+    pub fn owner(&self) -> Owner {
+        match self.organization_id {
+            Some(org_id) => Owner::Organization(org_id),
+            None => Owner::User(self.user_id),
+        }
+    }
+
     pub fn is_expired(&self) -> bool {
         if let Some(expires_at) = self.expires_at {
             expires_at < Utc::now()
@@ -30,6 +41,20 @@ impl ApiKey {
     pub fn is_valid(&self) -> bool {
         self.is_active && !self.is_expired()
     }
+
+    /// Scopes granted to the key, stored as a JSON array of strings in `permissions`
+    pub fn scopes(&self) -> Vec<String> {
+        self.permissions
+            .as_ref()
+            .and_then(|p| p.as_array())
+            .map(|scopes| {
+                scopes
+                    .iter()
+                    .filter_map(|s| s.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// A completed promotion of variables from one environment into another
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct EnvironmentPromotion {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub source_environment_id: Option<Uuid>,
+    pub target_environment_id: Option<Uuid>,
+    pub promoted_by: Option<Uuid>,
+    pub added: i32,
+    pub changed: i32,
+    pub removed: i32,
+    /// Per-key summary of what was applied
+    pub changes: sqlx::types::JsonValue,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a promotion does to one key of the target environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromotionAction {
+    Add,
+    Change,
+    Remove,
+}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::organization::Owner;
+
+/// A named JSON Schema (draft 2020-12) that variables of its owner can conform to
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct JsonSchema {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub name: String,
+    pub description: Option<String>,
+    pub schema: sqlx::types::JsonValue,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl JsonSchema {
+    pub fn owner(&self) -> Owner {
+        match self.organization_id {
+            Some(org_id) => Owner::Organization(org_id),
+            None => Owner::User(self.user_id),
+        }
+    }
+}
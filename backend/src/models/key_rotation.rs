@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "key_rotation_status", rename_all = "lowercase")]
+pub enum KeyRotationStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// A job re-wrapping the data keys of encrypted variables under master key `key_version`
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct KeyRotation {
+    pub id: Uuid,
+    pub key_version: i32,
+    pub status: KeyRotationStatus,
+    /// Encrypted variables when the job started
+    pub total_variables: i64,
+    pub processed_variables: i64,
+    /// Data keys that were wrapped by an older master key
+    pub rewrapped_keys: i64,
+    /// Variables marked encrypted whose data was still stored in plain
+    pub encrypted_variables: i64,
+    /// The job resumes after this variable
+    pub last_variable_id: Option<Uuid>,
+    /// Variables decrypted to verify the rotation once every key was re-wrapped
+    pub verified_variables: Option<i32>,
+    pub error: Option<String>,
+    pub started_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
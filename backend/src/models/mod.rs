@@ -1,15 +1,31 @@
 pub mod api_key;
+pub mod environment_promotion;
+pub mod json_schema;
+pub mod key_rotation;
+pub mod organization;
+pub mod project;
 pub mod promotion;
 pub mod role;
+pub mod scheduled_change;
 pub mod tier;
 pub mod usage_stats;
 pub mod user;
 pub mod variable;
+pub mod variable_share;
+pub mod variable_version;
 
 pub use api_key::*;
+pub use environment_promotion::*;
+pub use json_schema::*;
+pub use key_rotation::*;
+pub use organization::*;
+pub use project::*;
 pub use promotion::*;
 pub use role::*;
+pub use scheduled_change::*;
 pub use tier::*;
 pub use usage_stats::*;
 pub use user::*;
 pub use variable::*;
+pub use variable_share::*;
+pub use variable_version::*;
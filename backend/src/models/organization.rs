@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Organization {
+    pub id: Uuid,
+    pub name: String,
+    pub tier_id: Uuid,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// An organization as seen by one of its members
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct MemberOrganization {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub organization: Organization,
+    pub role: OrgRole,
+}
+
+/// Role of a member within an organization, ordered from least to most privileged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "org_role", rename_all = "lowercase")]
+pub enum OrgRole {
+    Viewer,
+    Editor,
+    Admin,
+    Owner,
+}
+
+impl OrgRole {
+    pub fn can_read(&self) -> bool {
+        *self >= OrgRole::Viewer
+    }
+
+    pub fn can_write(&self) -> bool {
+        *self >= OrgRole::Editor
+    }
+
+    pub fn can_manage_members(&self) -> bool {
+        *self >= OrgRole::Admin
+    }
+
+    pub fn is_owner(&self) -> bool {
+        matches!(self, OrgRole::Owner)
+    }
+}
+
+impl std::fmt::Display for OrgRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrgRole::Viewer => write!(f, "viewer"),
+            OrgRole::Editor => write!(f, "editor"),
+            OrgRole::Admin => write!(f, "admin"),
+            OrgRole::Owner => write!(f, "owner"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrganizationMember {
+    pub organization_id: Uuid,
+    pub user_id: Uuid,
+    pub role: OrgRole,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrganizationInvitation {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub email: String,
+    pub role: OrgRole,
+    pub invited_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub accepted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OrganizationInvitation {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at < Utc::now()
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.accepted_at.is_none() && !self.is_expired()
+    }
+}
+
+/// The principal that owns variables and API keys: a single user or an organization
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "lowercase")]
+pub enum Owner {
+    User(Uuid),
+    Organization(Uuid),
+}
+
+impl Owner {
+    pub fn id(&self) -> Uuid {
+        match self {
+            Owner::User(id) | Owner::Organization(id) => *id,
+        }
+    }
+
+    pub fn organization_id(&self) -> Option<Uuid> {
+        match self {
+            Owner::User(_) => None,
+            Owner::Organization(id) => Some(*id),
+        }
+    }
+
+    /// Directory (relative to the storage root) holding this owner's variable files
+    pub fn storage_prefix(&self) -> String {
+        match self {
+            Owner::User(id) => id.to_string(),
+            Owner::Organization(id) => format!("orgs/{}", id),
+        }
+    }
+}
+
+impl std::fmt::Display for Owner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Owner::User(id) => write!(f, "user:{}", id),
+            Owner::Organization(id) => write!(f, "org:{}", id),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::organization::Owner;
+
+/// Name of the project and environment that back the flat `/api/variables` routes
+pub const DEFAULT_PROJECT: &str = "default";
+pub const DEFAULT_ENVIRONMENT: &str = "default";
+
+/// Longest parent chain an environment may have, itself included
+pub const MAX_ENVIRONMENT_DEPTH: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Project {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Project {
+    pub fn owner(&self) -> Owner {
+        match self.organization_id {
+            Some(org_id) => Owner::Organization(org_id),
+            None => Owner::User(self.user_id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Environment {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    /// Environment this one inherits variables from
+    pub parent_id: Option<Uuid>,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How values from parent environments combine with those of their children
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMode {
+    /// The nearest environment defining a key supplies the whole value
+    #[default]
+    Override,
+    /// JSON objects are merged recursively, nearer environments winning per field
+    Merge,
+}
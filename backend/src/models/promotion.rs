@@ -7,9 +7,9 @@ use uuid::Uuid;
 pub struct PromotionHistory {
     pub id: Uuid,
     pub user_id: Uuid,
-    pub from_tier_id: Uuid,
+    pub from_tier_id: Option<Uuid>,
     pub to_tier_id: Uuid,
-    pub promoted_by: Uuid, // Admin user ID
+    pub promoted_by: Option<Uuid>, // Staff user ID
     pub reason: Option<String>,
     pub created_at: DateTime<Utc>,
 }
@@ -1,24 +1,85 @@
 use serde::{Deserialize, Serialize};
 use sqlx::Type;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
 #[sqlx(type_name = "user_role", rename_all = "lowercase")]
 pub enum UserRole {
+    #[default]
     #[sqlx(rename = "user")]
     User,
     #[sqlx(rename = "admin")]
     Admin,
+    /// Customer support: inspects and (de)activates accounts
+    #[sqlx(rename = "support")]
+    Support,
+    /// Finance: tier assignments and promotion history
+    #[sqlx(rename = "billing")]
+    Billing,
+    /// Read-only access to everything staff can see
+    #[sqlx(rename = "auditor")]
+    Auditor,
+}
+
+/// A capability on the admin API, granted to staff roles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Permission {
+    #[serde(rename = "users:read")]
+    UsersRead,
+    #[serde(rename = "users:write")]
+    UsersWrite,
+    #[serde(rename = "users:delete")]
+    UsersDelete,
+    #[serde(rename = "users:manage_roles")]
+    UsersManageRoles,
+    #[serde(rename = "tiers:read")]
+    TiersRead,
+    #[serde(rename = "tiers:write")]
+    TiersWrite,
+    #[serde(rename = "billing:read")]
+    BillingRead,
+    #[serde(rename = "billing:write")]
+    BillingWrite,
+    #[serde(rename = "encryption:read")]
+    EncryptionRead,
+    #[serde(rename = "encryption:write")]
+    EncryptionWrite,
 }
 
 impl UserRole {
     pub fn is_admin(&self) -> bool {
         matches!(self, UserRole::Admin)
     }
-}
 
-impl Default for UserRole {
-    fn default() -> Self {
-        UserRole::User
+    /// Whether the role grants any access to the admin API
+    pub fn is_staff(&self) -> bool {
+        !self.permissions().is_empty()
+    }
+
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+
+        match self {
+            UserRole::User => &[],
+            UserRole::Admin => &[
+                UsersRead,
+                UsersWrite,
+                UsersDelete,
+                UsersManageRoles,
+                TiersRead,
+                TiersWrite,
+                BillingRead,
+                BillingWrite,
+                EncryptionRead,
+                EncryptionWrite,
+            ],
+            UserRole::Support => &[UsersRead, UsersWrite, TiersRead],
+            UserRole::Billing => &[UsersRead, TiersRead, BillingRead, BillingWrite],
+            UserRole::Auditor => &[UsersRead, TiersRead, BillingRead, EncryptionRead],
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
     }
 }
 
@@ -27,6 +88,26 @@ impl std::fmt::Display for UserRole {
         match self {
             UserRole::User => write!(f, "user"),
             UserRole::Admin => write!(f, "admin"),
+            UserRole::Support => write!(f, "support"),
+            UserRole::Billing => write!(f, "billing"),
+            UserRole::Auditor => write!(f, "auditor"),
+        }
+    }
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Permission::UsersRead => write!(f, "users:read"),
+            Permission::UsersWrite => write!(f, "users:write"),
+            Permission::UsersDelete => write!(f, "users:delete"),
+            Permission::UsersManageRoles => write!(f, "users:manage_roles"),
+            Permission::TiersRead => write!(f, "tiers:read"),
+            Permission::TiersWrite => write!(f, "tiers:write"),
+            Permission::BillingRead => write!(f, "billing:read"),
+            Permission::BillingWrite => write!(f, "billing:write"),
+            Permission::EncryptionRead => write!(f, "encryption:read"),
+            Permission::EncryptionWrite => write!(f, "encryption:write"),
         }
     }
 }
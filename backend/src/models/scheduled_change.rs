@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "scheduled_change_status", rename_all = "lowercase")]
+pub enum ScheduledChangeStatus {
+    Pending,
+    Applied,
+    Cancelled,
+    Failed,
+}
+
+/// Data staged to replace a variable's data once `apply_at` has passed
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ScheduledChange {
+    pub id: Uuid,
+    pub variable_id: Uuid,
+    pub storage_path: String,
+    pub apply_at: DateTime<Utc>,
+    /// Recorded on the version the change writes
+    pub message: Option<String>,
+    pub status: ScheduledChangeStatus,
+    /// Version of the variable the change wrote
+    pub applied_version: Option<i32>,
+    /// Why the change could not be applied, or why the last attempt failed
+    pub error: Option<String>,
+    /// Attempts that failed with an error that may go away
+    pub attempts: i32,
+    /// When the change is retried after a failed attempt
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub applied_at: Option<DateTime<Utc>>,
+}
@@ -12,6 +12,10 @@ pub struct Tier {
     pub max_variable_size_mb: i32,
     pub max_requests_per_day: i32,
     pub max_api_keys: i32,
+    /// Versions kept per variable; -1 keeps every version
+    pub max_versions: i32,
+    /// Days deleted variables stay in the trash; -1 keeps them until purged
+    pub trash_retention_days: i32,
     pub price_monthly: i32, // in cents
     pub is_active: bool,
     pub created_at: DateTime<Utc>,
@@ -34,4 +38,14 @@ impl Tier {
     pub fn is_within_rate_limit(&self, requests_today: i32) -> bool {
         requests_today < self.max_requests_per_day
     }
+
+    /// Number of versions to keep per variable, or `None` to keep them all
+    pub fn version_retention(&self) -> Option<i64> {
+        (self.max_versions >= 0).then(|| i64::from(self.max_versions.max(1)))
+    }
+
+    /// Days a deleted variable stays restorable, or `None` to keep it until purged
+    pub fn trash_retention(&self) -> Option<i64> {
+        (self.trash_retention_days >= 0).then(|| i64::from(self.trash_retention_days))
+    }
 }
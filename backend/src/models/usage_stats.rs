@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
@@ -6,8 +6,11 @@ use uuid::Uuid;
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct UsageStats {
     pub id: Uuid,
-    pub user_id: Uuid,
-    pub date: DateTime<Utc>,
+    /// Set for a user's own usage
+    pub user_id: Option<Uuid>,
+    /// Set for an organization's usage
+    pub organization_id: Option<Uuid>,
+    pub date: NaiveDate,
     pub requests_count: i32,
     pub variables_created: i32,
     pub variables_updated: i32,
@@ -3,10 +3,15 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use super::organization::Owner;
+use super::variable_share::SharePermission;
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Variable {
     pub id: Uuid,
     pub user_id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub environment_id: Uuid,
     pub key: String,
     pub description: Option<String>,
     pub size_bytes: i64,
@@ -14,14 +19,110 @@ pub struct Variable {
     pub storage_path: String,
     pub is_encrypted: bool,
     pub tags: Option<sqlx::types::JsonValue>,
+    /// Served without authentication under `/public/{owner}/{key}`
+    pub is_public: bool,
+    /// Named schema the data must conform to
+    pub schema_id: Option<Uuid>,
+    /// Schema of this variable alone the data must conform to
+    pub inline_schema: Option<sqlx::types::JsonValue>,
+    /// Once passed, the variable is treated as deleted
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Set while the variable is in the trash
+    pub deleted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 impl Variable {
+    pub fn owner(&self) -> Owner {
+        match self.organization_id {
+            Some(org_id) => Owner::Organization(org_id),
+            None => Owner::User(self.user_id),
+        }
+    }
+
     pub fn size_in_mb(&self) -> i32 {
         (self.size_bytes / (1024 * 1024)) as i32
     }
+
+    /// Tags stored as a JSON array of strings
+    pub fn tag_list(&self) -> Vec<String> {
+        self.tags
+            .as_ref()
+            .and_then(|t| t.as_array())
+            .map(|tags| {
+                tags.iter()
+                    .filter_map(|t| t.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// A variable in the trash, restorable until it is purged
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TrashedVariable {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub variable: Variable,
+    pub deleted_by: Option<Uuid>,
+}
+
+/// How a tag filter combines its tags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TagMatch {
+    /// Variables carrying every tag
+    #[default]
+    All,
+    /// Variables carrying at least one of the tags
+    Any,
+}
+
+/// Filters narrowing a listing of variables
+#[derive(Debug, Clone, Default)]
+pub struct VariableFilter {
+    /// Substring of the key, matched case-insensitively
+    pub search: Option<String>,
+    /// Full-text query over keys, descriptions, tags and indexed contents
+    pub query: Option<String>,
+    pub tags: Vec<String>,
+    pub tag_match: TagMatch,
+    /// Only variables expiring within this many seconds, soonest first
+    pub expiring_within: Option<i64>,
+}
+
+/// A tag and the number of variables carrying it
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// A variable as seen by a caller who either owns it or received a share
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AccessibleVariable {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub variable: Variable,
+    /// Set when the variable belongs to someone else and was shared with the caller
+    pub share_permission: Option<SharePermission>,
+    /// Organization name or user email of the owner of a shared variable
+    pub shared_by: Option<String>,
+    /// Relevance to a full-text search, set only on search results
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank: Option<f32>,
+    /// Excerpt with the search terms marked by `<b>` tags, set only on search results
+    #[sqlx(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub highlight: Option<String>,
+}
+
+impl AccessibleVariable {
+    pub fn is_shared(&self) -> bool {
+        self.share_permission.is_some()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, Type};
+use uuid::Uuid;
+
+/// Access a share grants to its recipient
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "share_permission", rename_all = "lowercase")]
+pub enum SharePermission {
+    Read,
+    Write,
+}
+
+impl SharePermission {
+    pub fn can_write(&self) -> bool {
+        matches!(self, SharePermission::Write)
+    }
+}
+
+impl std::fmt::Display for SharePermission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SharePermission::Read => write!(f, "read"),
+            SharePermission::Write => write!(f, "write"),
+        }
+    }
+}
+
+/// A grant giving another user access to a single variable
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct VariableShare {
+    pub id: Uuid,
+    pub variable_id: Uuid,
+    pub user_id: Uuid,
+    /// Email of the recipient
+    pub email: String,
+    pub permission: SharePermission,
+    pub granted_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A time-limited link to a single variable, valid only with its signature
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct VariableShareLink {
+    pub id: Uuid,
+    pub variable_id: Uuid,
+    pub created_by: Option<Uuid>,
+    pub expires_at: DateTime<Utc>,
+    pub use_count: i32,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl VariableShareLink {
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+/// An immutable snapshot of a variable as it stood at one version
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct VariableVersion {
+    pub id: Uuid,
+    pub variable_id: Uuid,
+    pub version: i32,
+    pub storage_path: String,
+    pub size_bytes: i64,
+    pub description: Option<String>,
+    pub tags: Option<sqlx::types::JsonValue>,
+    pub is_encrypted: bool,
+    /// User who wrote this version; unknown for versions written before history was kept
+    pub author_id: Option<Uuid>,
+    pub message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
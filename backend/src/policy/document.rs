@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, Result};
+use crate::models::{OrgRole, Permission, SharePermission};
+
+use super::engine::{PolicyRequest, PrincipalKind};
+
+/// Rules shipped with the server; they reproduce the built-in role and share checks
+pub const DEFAULT_POLICY: &str = include_str!("../../policies/default.json");
+
+/// Match `value` against a glob where `*` matches any run of characters and `?` any single one
+pub fn glob_match(pattern: &str, value: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let value: Vec<char> = value.chars().collect();
+
+    let (mut p, mut v) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, v));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character and retry
+                Some((star, matched)) => {
+                    p = star + 1;
+                    v = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Effect {
+    Permit,
+    Forbid,
+}
+
+/// Requirements on the principal and resource of a request. Every clause that
+/// is set must hold; an empty condition always holds.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Condition {
+    /// Whether the caller authenticated with a JWT (`user`) or an API key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principal: Option<PrincipalKind>,
+    /// The caller is an API key carrying this scope
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_scope: Option<String>,
+    /// The caller holds at least this role for the variable's owner
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_org_role: Option<OrgRole>,
+    /// The variable was shared with the caller with at least this permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share: Option<SharePermission>,
+    /// The caller's staff role grants this admin permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub staff_permission: Option<Permission>,
+    /// Glob over the variable key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// The variable carries at least one of these tags
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags_any: Option<Vec<String>>,
+    /// Glob over the project name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    /// Glob over the environment name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub environment: Option<String>,
+}
+
+impl Condition {
+    /// Check every clause, returning a description of the first one that fails
+    pub fn check(&self, request: &PolicyRequest) -> std::result::Result<(), String> {
+        let principal = &request.principal;
+        let resource = &request.resource;
+
+        if let Some(kind) = self.principal
+            && principal.kind() != kind
+        {
+            return Err(format!("principal is not {}", kind));
+        }
+
+        if let Some(ref scope) = self.api_key_scope
+            && !(principal.api_key && principal.scopes.contains(scope))
+        {
+            return Err(format!("principal is not an API key with scope '{}'", scope));
+        }
+
+        if let Some(role) = self.min_org_role
+            && principal.org_role.is_none_or(|r| r < role)
+        {
+            return Err(format!("principal lacks organization role '{}'", role));
+        }
+
+        if let Some(permission) = self.share
+            && principal.share.is_none_or(|p| p < permission)
+        {
+            return Err(format!("variable is not shared with '{}' permission", permission));
+        }
+
+        if let Some(permission) = self.staff_permission
+            && !principal.user_role.has_permission(permission)
+        {
+            return Err(format!("role lacks permission '{}'", permission));
+        }
+
+        if let Some(ref pattern) = self.key
+            && !resource.key.as_deref().is_some_and(|key| glob_match(pattern, key))
+        {
+            return Err(format!("key does not match '{}'", pattern));
+        }
+
+        if let Some(ref tags) = self.tags_any
+            && !tags.iter().any(|tag| resource.tags.contains(tag))
+        {
+            return Err(format!("variable has none of the tags {:?}", tags));
+        }
+
+        if let Some(ref pattern) = self.project
+            && !resource.project.as_deref().is_some_and(|p| glob_match(pattern, p))
+        {
+            return Err(format!("project does not match '{}'", pattern));
+        }
+
+        if let Some(ref pattern) = self.environment
+            && !resource.environment.as_deref().is_some_and(|e| glob_match(pattern, e))
+        {
+            return Err(format!("environment does not match '{}'", pattern));
+        }
+
+        Ok(())
+    }
+}
+
+/// A single permit or forbid statement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub id: String,
+    pub effect: Effect,
+    /// Globs over action names such as `variable:read` or `admin:*`
+    pub actions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Condition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unless: Option<Condition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// The contents of a policy file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyDocument {
+    /// Evaluate the built-in rules alongside the file's own
+    #[serde(default = "default_extends_default")]
+    pub extends_default: bool,
+    pub rules: Vec<Rule>,
+}
+
+fn default_extends_default() -> bool {
+    true
+}
+
+impl PolicyDocument {
+    pub fn parse(source: &str) -> Result<Self> {
+        let document: Self = serde_json::from_str(source)?;
+
+        for (index, rule) in document.rules.iter().enumerate() {
+            if rule.id.is_empty() || rule.actions.is_empty() {
+                return Err(AppError::Validation(format!(
+                    "Policy rule {} needs an id and at least one action",
+                    index + 1
+                )));
+            }
+            if document.rules[..index].iter().any(|r| r.id == rule.id) {
+                return Err(AppError::Validation(format!(
+                    "Duplicate policy rule id '{}'",
+                    rule.id
+                )));
+            }
+        }
+
+        Ok(document)
+    }
+}
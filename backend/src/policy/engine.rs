@@ -0,0 +1,457 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{OrgRole, Permission, SharePermission, UserRole, Variable};
+use crate::utils::Claims;
+
+use super::document::{glob_match, Effect, PolicyDocument, Rule, DEFAULT_POLICY};
+
+/// Something a caller can attempt; policies refer to it by its string form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Action {
+    VariableRead,
+    VariableList,
+    VariableWrite,
+    VariableDelete,
+    VariableShare,
+    VariablePublish,
+    Admin(Permission),
+}
+
+impl std::fmt::Display for Action {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Action::VariableRead => write!(f, "variable:read"),
+            Action::VariableList => write!(f, "variable:list"),
+            Action::VariableWrite => write!(f, "variable:write"),
+            Action::VariableDelete => write!(f, "variable:delete"),
+            Action::VariableShare => write!(f, "variable:share"),
+            Action::VariablePublish => write!(f, "variable:publish"),
+            Action::Admin(permission) => write!(f, "admin:{}", permission),
+        }
+    }
+}
+
+impl TryFrom<String> for Action {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, Self::Error> {
+        match value.as_str() {
+            "variable:read" => Ok(Action::VariableRead),
+            "variable:list" => Ok(Action::VariableList),
+            "variable:write" => Ok(Action::VariableWrite),
+            "variable:delete" => Ok(Action::VariableDelete),
+            "variable:share" => Ok(Action::VariableShare),
+            "variable:publish" => Ok(Action::VariablePublish),
+            other => other
+                .strip_prefix("admin:")
+                .and_then(|permission| {
+                    serde_json::from_value(serde_json::Value::String(permission.to_string())).ok()
+                })
+                .map(Action::Admin)
+                .ok_or_else(|| format!("Unknown action '{}'", value)),
+        }
+    }
+}
+
+impl From<Action> for String {
+    fn from(action: Action) -> Self {
+        action.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrincipalKind {
+    User,
+    ApiKey,
+}
+
+impl std::fmt::Display for PrincipalKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrincipalKind::User => write!(f, "a user"),
+            PrincipalKind::ApiKey => write!(f, "an API key"),
+        }
+    }
+}
+
+/// The caller of a request, as seen by policies
+#[derive(Debug, Clone, Serialize)]
+pub struct Principal {
+    pub user_id: Uuid,
+    pub user_role: UserRole,
+    pub api_key: bool,
+    /// Scopes of the API key, empty for users
+    pub scopes: Vec<String>,
+    /// Role for the owner of the resource; absent when acting through a share
+    pub org_role: Option<OrgRole>,
+    /// Permission of the share the resource is reached through, if any
+    pub share: Option<SharePermission>,
+}
+
+impl Principal {
+    pub fn from_claims(claims: &Claims) -> Result<Self> {
+        Ok(Self {
+            user_id: claims.user_id()?,
+            user_role: claims.role,
+            api_key: claims.is_api_key(),
+            scopes: claims.scopes.clone(),
+            org_role: None,
+            share: None,
+        })
+    }
+
+    pub fn kind(&self) -> PrincipalKind {
+        if self.api_key {
+            PrincipalKind::ApiKey
+        } else {
+            PrincipalKind::User
+        }
+    }
+}
+
+/// What a request acts on. Fields that don't apply are left empty.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Resource {
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub environment: Option<String>,
+}
+
+impl Resource {
+    pub fn for_variable(
+        variable: &Variable,
+        project: Option<&str>,
+        environment: Option<&str>,
+    ) -> Self {
+        Self {
+            key: Some(variable.key.clone()),
+            tags: variable.tag_list(),
+            project: project.map(str::to_string),
+            environment: environment.map(str::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyRequest {
+    pub principal: Principal,
+    pub action: Action,
+    pub resource: Resource,
+}
+
+/// How a single rule related to a request
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleEvaluation {
+    pub rule: String,
+    pub effect: Effect,
+    pub applies: bool,
+    pub detail: String,
+}
+
+/// The outcome of evaluating a request, with the reasoning behind it
+#[derive(Debug, Clone, Serialize)]
+pub struct Decision {
+    pub allowed: bool,
+    pub reason: String,
+    /// Rules that decided the outcome
+    pub determining_rules: Vec<String>,
+    pub evaluations: Vec<RuleEvaluation>,
+}
+
+impl Rule {
+    fn evaluate(&self, request: &PolicyRequest) -> RuleEvaluation {
+        let action = request.action.to_string();
+
+        let outcome = if !self.actions.iter().any(|pattern| glob_match(pattern, &action)) {
+            Err(format!("action '{}' is not covered", action))
+        } else if let Some(Err(unmet)) = self.when.as_ref().map(|c| c.check(request)) {
+            Err(format!("condition not met: {}", unmet))
+        } else if let Some(Ok(())) = self.unless.as_ref().map(|c| c.check(request)) {
+            Err("exception applies".to_string())
+        } else {
+            Ok(())
+        };
+
+        RuleEvaluation {
+            rule: self.id.clone(),
+            effect: self.effect,
+            applies: outcome.is_ok(),
+            detail: outcome.err().unwrap_or_else(|| "applies".to_string()),
+        }
+    }
+}
+
+/// Evaluates requests against permit and forbid rules.
+///
+/// Any applicable `forbid` denies the request; otherwise an applicable `permit`
+/// allows it; requests no rule permits are denied.
+#[derive(Debug, Clone)]
+pub struct PolicyEngine {
+    rules: Vec<Rule>,
+}
+
+impl PolicyEngine {
+    pub fn new(document: PolicyDocument) -> Result<Self> {
+        let mut rules = if document.extends_default {
+            Self::default_policy().rules
+        } else {
+            Vec::new()
+        };
+
+        for rule in document.rules {
+            if rules.iter().any(|r| r.id == rule.id) {
+                return Err(AppError::Validation(format!(
+                    "Policy rule id '{}' is already used by the default policy",
+                    rule.id
+                )));
+            }
+            rules.push(rule);
+        }
+
+        Ok(Self { rules })
+    }
+
+    /// The built-in rules only
+    pub fn default_policy() -> Self {
+        let document = PolicyDocument::parse(DEFAULT_POLICY).expect("default policy is valid");
+        Self {
+            rules: document.rules,
+        }
+    }
+
+    pub async fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let source = tokio::fs::read_to_string(path).await?;
+        Self::new(PolicyDocument::parse(&source)?)
+    }
+
+    /// Load the file named by `POLICY_FILE`, or the built-in rules when unset
+    pub async fn from_env() -> Result<Self> {
+        match std::env::var("POLICY_FILE") {
+            Ok(path) => Self::from_file(path).await,
+            Err(_) => Ok(Self::default_policy()),
+        }
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn evaluate(&self, request: &PolicyRequest) -> Decision {
+        let evaluations: Vec<RuleEvaluation> =
+            self.rules.iter().map(|rule| rule.evaluate(request)).collect();
+
+        let applying = |effect| -> Vec<String> {
+            evaluations
+                .iter()
+                .filter(|e| e.applies && e.effect == effect)
+                .map(|e| e.rule.clone())
+                .collect()
+        };
+        let forbids = applying(Effect::Forbid);
+        let permits = applying(Effect::Permit);
+
+        let (allowed, reason, determining_rules) = if let Some(first) = forbids.first() {
+            let description = self
+                .rules
+                .iter()
+                .find(|r| &r.id == first)
+                .and_then(|r| r.description.clone());
+            let reason = match description {
+                Some(description) => format!("Forbidden by policy '{}': {}", first, description),
+                None => format!("Forbidden by policy '{}'", first),
+            };
+            (false, reason, forbids)
+        } else if let Some(first) = permits.first() {
+            (true, format!("Permitted by policy '{}'", first), permits)
+        } else {
+            let reason = format!("No policy permits '{}'", request.action);
+            (false, reason, Vec::new())
+        };
+
+        Decision {
+            allowed,
+            reason,
+            determining_rules,
+            evaluations,
+        }
+    }
+
+    /// Fail with an authorization error unless the request is allowed
+    pub fn authorize(&self, request: &PolicyRequest) -> Result<()> {
+        let decision = self.evaluate(request);
+        if !decision.allowed {
+            return Err(AppError::Authorization(decision.reason));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(rules: &str) -> PolicyEngine {
+        PolicyEngine::new(PolicyDocument::parse(rules).unwrap()).unwrap()
+    }
+
+    fn request(principal: Principal, action: Action, key: &str, tags: &[&str]) -> PolicyRequest {
+        PolicyRequest {
+            principal,
+            action,
+            resource: Resource {
+                key: Some(key.to_string()),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                project: None,
+                environment: None,
+            },
+        }
+    }
+
+    fn member(role: OrgRole) -> Principal {
+        Principal {
+            user_id: Uuid::new_v4(),
+            user_role: UserRole::User,
+            api_key: false,
+            scopes: Vec::new(),
+            org_role: Some(role),
+            share: None,
+        }
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("prod.*", "prod.db"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXbYbc"));
+        assert!(glob_match("v?", "v1"));
+        assert!(!glob_match("prod.*", "staging.db"));
+        assert!(!glob_match("admin:*", "variable:read"));
+    }
+
+    #[test]
+    fn test_action_round_trip() {
+        for action in ["variable:read", "variable:share", "admin:users:manage_roles"] {
+            let parsed = Action::try_from(action.to_string()).unwrap();
+            assert_eq!(parsed.to_string(), action);
+        }
+        assert!(Action::try_from("admin:nope".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_default_policy_requires_roles() {
+        let engine = PolicyEngine::default_policy();
+
+        let read = request(member(OrgRole::Viewer), Action::VariableRead, "db", &[]);
+        assert!(engine.evaluate(&read).allowed);
+
+        let write = request(member(OrgRole::Viewer), Action::VariableWrite, "db", &[]);
+        let decision = engine.evaluate(&write);
+        assert!(!decision.allowed);
+        assert!(decision.determining_rules.is_empty());
+    }
+
+    #[test]
+    fn test_default_policy_shares() {
+        let engine = PolicyEngine::default_policy();
+        let mut principal = member(OrgRole::Owner);
+        principal.org_role = None;
+        principal.share = Some(SharePermission::Read);
+
+        let read = request(principal.clone(), Action::VariableRead, "db", &[]);
+        assert!(engine.evaluate(&read).allowed);
+
+        let write = request(principal, Action::VariableWrite, "db", &[]);
+        assert!(!engine.evaluate(&write).allowed);
+    }
+
+    #[test]
+    fn test_forbid_overrides_permit() {
+        let engine = engine(
+            r#"{"rules": [{
+                "id": "pii-needs-scope",
+                "effect": "forbid",
+                "actions": ["variable:read"],
+                "when": {"tags_any": ["pii"]},
+                "unless": {"api_key_scope": "pii:read"},
+                "description": "PII is only readable by scoped API keys"
+            }]}"#,
+        );
+
+        let owner = request(member(OrgRole::Owner), Action::VariableRead, "ssn", &["pii"]);
+        let decision = engine.evaluate(&owner);
+        assert!(!decision.allowed);
+        assert_eq!(decision.determining_rules, vec!["pii-needs-scope"]);
+        assert!(decision.reason.contains("PII is only readable"));
+
+        let mut key = member(OrgRole::Editor);
+        key.api_key = true;
+        key.scopes = vec!["pii:read".to_string()];
+        let scoped = request(key, Action::VariableRead, "ssn", &["pii"]);
+        assert!(engine.evaluate(&scoped).allowed);
+
+        let untagged = request(member(OrgRole::Owner), Action::VariableRead, "db", &[]);
+        assert!(engine.evaluate(&untagged).allowed);
+    }
+
+    #[test]
+    fn test_key_glob_rule() {
+        let engine = engine(
+            r#"{"extends_default": false, "rules": [
+                {"id": "prod-editors", "effect": "permit", "actions": ["variable:write"],
+                 "when": {"key": "prod.*", "min_org_role": "editor"}},
+                {"id": "others", "effect": "permit", "actions": ["variable:*"],
+                 "unless": {"key": "prod.*"}}
+            ]}"#,
+        );
+
+        let prod = request(member(OrgRole::Viewer), Action::VariableWrite, "prod.db", &[]);
+        assert!(!engine.evaluate(&prod).allowed);
+
+        let prod = request(member(OrgRole::Editor), Action::VariableWrite, "prod.db", &[]);
+        assert!(engine.evaluate(&prod).allowed);
+
+        let dev = request(member(OrgRole::Viewer), Action::VariableWrite, "dev.db", &[]);
+        assert!(engine.evaluate(&dev).allowed);
+    }
+
+    #[test]
+    fn test_admin_actions_reject_api_keys() {
+        let engine = PolicyEngine::default_policy();
+        let mut admin = member(OrgRole::Owner);
+        admin.user_role = UserRole::Admin;
+
+        let action = Action::Admin(Permission::UsersRead);
+        let allowed = request(admin.clone(), action, "", &[]);
+        assert!(engine.evaluate(&allowed).allowed);
+
+        admin.api_key = true;
+        let denied = request(admin, action, "", &[]);
+        assert!(!engine.evaluate(&denied).allowed);
+    }
+
+    #[test]
+    fn test_rule_ids_must_be_unique() {
+        let document = PolicyDocument::parse(
+            r#"{"rules": [{"id": "members-read", "effect": "permit", "actions": ["*"]}]}"#,
+        )
+        .unwrap();
+        assert!(PolicyEngine::new(document).is_err());
+
+        assert!(PolicyDocument::parse(r#"{"rules": [{"id": "x", "effect": "permit",
+            "actions": ["*"], "when": {"role": "admin"}}]}"#)
+        .is_err());
+    }
+}
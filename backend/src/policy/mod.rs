@@ -0,0 +1,5 @@
+pub mod document;
+pub mod engine;
+
+pub use document::*;
+pub use engine::*;
@@ -2,8 +2,9 @@ use chrono::{Duration, Utc};
 use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
+use super::owner_filter;
 use crate::error::Result;
-use crate::models::ApiKey;
+use crate::models::{ApiKey, Owner};
 
 pub struct ApiKeyRepository {
     pool: Pool<Postgres>,
@@ -14,28 +15,35 @@ impl ApiKeyRepository {
         Self { pool }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
         &self,
-        user_id: Uuid,
+        owner: Owner,
+        created_by: Uuid,
+        environment_id: Option<Uuid>,
         name: &str,
         key_hash: &str,
         prefix: &str,
         expires_in_days: Option<i32>,
+        permissions: Option<&serde_json::Value>,
     ) -> Result<ApiKey> {
         let expires_at = expires_in_days.map(|days| Utc::now() + Duration::days(days as i64));
 
         let api_key = sqlx::query_as::<_, ApiKey>(
             r#"
-            INSERT INTO api_keys (user_id, name, key_hash, prefix, expires_at, is_active)
-            VALUES ($1, $2, $3, $4, $5, true)
+            INSERT INTO api_keys (user_id, organization_id, environment_id, name, key_hash, prefix, expires_at, permissions, is_active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, true)
             RETURNING *
             "#,
         )
-        .bind(user_id)
+        .bind(created_by)
+        .bind(owner.organization_id())
+        .bind(environment_id)
         .bind(name)
         .bind(key_hash)
         .bind(prefix)
         .bind(expires_at)
+        .bind(permissions)
         .fetch_one(&self.pool)
         .await?;
 
@@ -55,26 +63,30 @@ impl ApiKeyRepository {
         Ok(api_key)
     }
 
-    pub async fn list_by_user(&self, user_id: Uuid) -> Result<Vec<ApiKey>> {
-        let api_keys = sqlx::query_as::<_, ApiKey>(
-            r#"
-            SELECT * FROM api_keys WHERE user_id = $1 ORDER BY created_at DESC
-            "#,
-        )
-        .bind(user_id)
-        .fetch_all(&self.pool)
-        .await?;
+    pub async fn list_by_owner(&self, owner: Owner) -> Result<Vec<ApiKey>> {
+        let query = format!(
+            "SELECT * FROM api_keys WHERE {} ORDER BY created_at DESC",
+            owner_filter(&owner, 1)
+        );
+
+        let api_keys = sqlx::query_as::<_, ApiKey>(&query)
+            .bind(owner.id())
+            .fetch_all(&self.pool)
+            .await?;
 
         Ok(api_keys)
     }
 
-    pub async fn count_by_user(&self, user_id: Uuid) -> Result<i32> {
-        let count: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM api_keys WHERE user_id = $1 AND is_active = true"
-        )
-        .bind(user_id)
-        .fetch_one(&self.pool)
-        .await?;
+    pub async fn count_by_owner(&self, owner: Owner) -> Result<i32> {
+        let query = format!(
+            "SELECT COUNT(*) FROM api_keys WHERE {} AND is_active = true",
+            owner_filter(&owner, 1)
+        );
+
+        let count: (i64,) = sqlx::query_as(&query)
+            .bind(owner.id())
+            .fetch_one(&self.pool)
+            .await?;
 
         Ok(count.0 as i32)
     }
@@ -92,30 +104,32 @@ impl ApiKeyRepository {
         Ok(())
     }
 
-    pub async fn revoke(&self, id: Uuid, user_id: Uuid) -> Result<()> {
-        sqlx::query(
-            r#"
-            UPDATE api_keys SET is_active = false WHERE id = $1 AND user_id = $2
-            "#,
-        )
-        .bind(id)
-        .bind(user_id)
-        .execute(&self.pool)
-        .await?;
+    pub async fn revoke(&self, id: Uuid, owner: Owner) -> Result<()> {
+        let query = format!(
+            "UPDATE api_keys SET is_active = false WHERE id = $1 AND {}",
+            owner_filter(&owner, 2)
+        );
+
+        sqlx::query(&query)
+            .bind(id)
+            .bind(owner.id())
+            .execute(&self.pool)
+            .await?;
 
         Ok(())
     }
 
-    pub async fn delete(&self, id: Uuid, user_id: Uuid) -> Result<()> {
-        sqlx::query(
-            r#"
-            DELETE FROM api_keys WHERE id = $1 AND user_id = $2
-            "#,
-        )
-        .bind(id)
-        .bind(user_id)
-        .execute(&self.pool)
-        .await?;
+    pub async fn delete(&self, id: Uuid, owner: Owner) -> Result<()> {
+        let query = format!(
+            "DELETE FROM api_keys WHERE id = $1 AND {}",
+            owner_filter(&owner, 2)
+        );
+
+        sqlx::query(&query)
+            .bind(id)
+            .bind(owner.id())
+            .execute(&self.pool)
+            .await?;
 
         Ok(())
     }
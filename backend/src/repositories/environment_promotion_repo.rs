@@ -0,0 +1,63 @@
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::EnvironmentPromotion;
+
+pub struct EnvironmentPromotionRepository {
+    pool: Pool<Postgres>,
+}
+
+impl EnvironmentPromotionRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Record a promotion as part of the transaction that applies it
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_tx(
+        conn: &mut PgConnection,
+        project_id: Uuid,
+        source_environment_id: Uuid,
+        target_environment_id: Uuid,
+        promoted_by: Uuid,
+        added: i32,
+        changed: i32,
+        removed: i32,
+        changes: serde_json::Value,
+    ) -> Result<EnvironmentPromotion> {
+        let promotion = sqlx::query_as::<_, EnvironmentPromotion>(
+            r#"
+            INSERT INTO environment_promotions (project_id, source_environment_id, target_environment_id,
+                                                promoted_by, added, changed, removed, changes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#,
+        )
+        .bind(project_id)
+        .bind(source_environment_id)
+        .bind(target_environment_id)
+        .bind(promoted_by)
+        .bind(added)
+        .bind(changed)
+        .bind(removed)
+        .bind(changes)
+        .fetch_one(conn)
+        .await?;
+
+        Ok(promotion)
+    }
+
+    pub async fn list_by_project(&self, project_id: Uuid) -> Result<Vec<EnvironmentPromotion>> {
+        let promotions = sqlx::query_as::<_, EnvironmentPromotion>(
+            r#"
+            SELECT * FROM environment_promotions WHERE project_id = $1 ORDER BY created_at DESC
+            "#,
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(promotions)
+    }
+}
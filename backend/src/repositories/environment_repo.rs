@@ -0,0 +1,216 @@
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{
+    Environment, Owner, Project, DEFAULT_ENVIRONMENT, DEFAULT_PROJECT, MAX_ENVIRONMENT_DEPTH,
+};
+use crate::repositories::ProjectRepository;
+
+pub struct EnvironmentRepository {
+    pool: Pool<Postgres>,
+}
+
+impl EnvironmentRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        project_id: Uuid,
+        parent_id: Option<Uuid>,
+        name: &str,
+        description: Option<&str>,
+    ) -> Result<Environment> {
+        let environment = sqlx::query_as::<_, Environment>(
+            r#"
+            INSERT INTO environments (project_id, parent_id, name, description)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(project_id)
+        .bind(parent_id)
+        .bind(name)
+        .bind(description)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict("Environment name already exists".to_string());
+            }
+            AppError::Database(e)
+        })?;
+
+        Ok(environment)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<Environment>> {
+        let environment = sqlx::query_as::<_, Environment>(
+            r#"
+            SELECT * FROM environments WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(environment)
+    }
+
+    pub async fn find_by_name(&self, project_id: Uuid, name: &str) -> Result<Option<Environment>> {
+        let environment = sqlx::query_as::<_, Environment>(
+            r#"
+            SELECT * FROM environments WHERE project_id = $1 AND name = $2
+            "#,
+        )
+        .bind(project_id)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(environment)
+    }
+
+    pub async fn list_by_project(&self, project_id: Uuid) -> Result<Vec<Environment>> {
+        let environments = sqlx::query_as::<_, Environment>(
+            r#"
+            SELECT * FROM environments WHERE project_id = $1 ORDER BY name ASC
+            "#,
+        )
+        .bind(project_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(environments)
+    }
+
+    /// The environment followed by its parents, nearest first
+    pub async fn ancestry(&self, id: Uuid) -> Result<Vec<Environment>> {
+        let environments = sqlx::query_as::<_, Environment>(
+            r#"
+            WITH RECURSIVE chain AS (
+                SELECT e.*, 1 AS depth FROM environments e WHERE e.id = $1
+                UNION ALL
+                SELECT p.*, c.depth + 1 FROM environments p
+                JOIN chain c ON p.id = c.parent_id
+                WHERE c.depth < $2
+            )
+            SELECT id, project_id, parent_id, name, description, created_at, updated_at
+            FROM chain
+            ORDER BY depth ASC
+            "#,
+        )
+        .bind(id)
+        .bind(MAX_ENVIRONMENT_DEPTH as i32)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(environments)
+    }
+
+    /// Number of levels of environments inheriting from `id`, itself included
+    pub async fn subtree_height(&self, id: Uuid) -> Result<usize> {
+        let height: (Option<i32>,) = sqlx::query_as(
+            r#"
+            WITH RECURSIVE tree AS (
+                SELECT id, 1 AS depth FROM environments WHERE id = $1
+                UNION ALL
+                SELECT e.id, t.depth + 1 FROM environments e
+                JOIN tree t ON e.parent_id = t.id
+                WHERE t.depth <= $2
+            )
+            SELECT MAX(depth) FROM tree
+            "#,
+        )
+        .bind(id)
+        .bind(MAX_ENVIRONMENT_DEPTH as i32)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(height.0.unwrap_or(0) as usize)
+    }
+
+    pub async fn update(
+        &self,
+        id: Uuid,
+        parent_id: Option<Uuid>,
+        description: Option<&str>,
+    ) -> Result<Environment> {
+        let environment = sqlx::query_as::<_, Environment>(
+            r#"
+            UPDATE environments
+            SET parent_id = $2, description = $3
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(parent_id)
+        .bind(description)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Environment not found".to_string()))?;
+
+        Ok(environment)
+    }
+
+    /// Fetch the owner's default environment, creating the default project and
+    /// environment on first use
+    pub async fn get_or_create_default(
+        &self,
+        owner: Owner,
+        created_by: Uuid,
+    ) -> Result<(Project, Environment)> {
+        let project_repo = ProjectRepository::new(self.pool.clone());
+
+        let project = match project_repo.find_by_name(owner, DEFAULT_PROJECT).await? {
+            Some(project) => project,
+            None => match project_repo
+                .create(owner, created_by, DEFAULT_PROJECT, Some("Default project"))
+                .await
+            {
+                Ok(project) => project,
+                // Lost a race with a concurrent request creating the same project
+                Err(AppError::Conflict(_)) => project_repo
+                    .find_by_name(owner, DEFAULT_PROJECT)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?,
+                Err(e) => return Err(e),
+            },
+        };
+
+        let environment = match self.find_by_name(project.id, DEFAULT_ENVIRONMENT).await? {
+            Some(environment) => environment,
+            None => match self
+                .create(project.id, None, DEFAULT_ENVIRONMENT, Some("Default environment"))
+                .await
+            {
+                Ok(environment) => environment,
+                Err(AppError::Conflict(_)) => self
+                    .find_by_name(project.id, DEFAULT_ENVIRONMENT)
+                    .await?
+                    .ok_or_else(|| AppError::NotFound("Environment not found".to_string()))?,
+                Err(e) => return Err(e),
+            },
+        };
+
+        Ok((project, environment))
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM environments WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use serde_json::Value;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use super::owner_filter;
+use crate::error::{AppError, Result};
+use crate::models::{JsonSchema, Owner};
+
+pub struct JsonSchemaRepository {
+    pool: Pool<Postgres>,
+}
+
+impl JsonSchemaRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        owner: Owner,
+        created_by: Uuid,
+        name: &str,
+        description: Option<&str>,
+        schema: &Value,
+    ) -> Result<JsonSchema> {
+        let json_schema = sqlx::query_as::<_, JsonSchema>(
+            r#"
+            INSERT INTO json_schemas (user_id, organization_id, name, description, schema)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(created_by)
+        .bind(owner.organization_id())
+        .bind(name)
+        .bind(description)
+        .bind(schema)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict("Schema name already exists".to_string());
+            }
+            AppError::Database(e)
+        })?;
+
+        Ok(json_schema)
+    }
+
+    /// Find one of the owner's schemas
+    pub async fn find_by_id(&self, owner: Owner, id: Uuid) -> Result<Option<JsonSchema>> {
+        let query = format!(
+            "SELECT * FROM json_schemas WHERE id = $1 AND {}",
+            owner_filter(&owner, 2)
+        );
+
+        let json_schema = sqlx::query_as::<_, JsonSchema>(&query)
+            .bind(id)
+            .bind(owner.id())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(json_schema)
+    }
+
+    pub async fn list_by_owner(&self, owner: Owner) -> Result<Vec<JsonSchema>> {
+        let query = format!(
+            "SELECT * FROM json_schemas WHERE {} ORDER BY name ASC",
+            owner_filter(&owner, 1)
+        );
+
+        let json_schemas = sqlx::query_as::<_, JsonSchema>(&query)
+            .bind(owner.id())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(json_schemas)
+    }
+
+    pub async fn update(
+        &self,
+        owner: Owner,
+        id: Uuid,
+        description: Option<&str>,
+        schema: Option<&Value>,
+    ) -> Result<Option<JsonSchema>> {
+        let query = format!(
+            r#"
+            UPDATE json_schemas
+            SET description = COALESCE($2, description),
+                schema = COALESCE($3, schema)
+            WHERE id = $1 AND {}
+            RETURNING *
+            "#,
+            owner_filter(&owner, 4)
+        );
+
+        let json_schema = sqlx::query_as::<_, JsonSchema>(&query)
+            .bind(id)
+            .bind(description)
+            .bind(schema)
+            .bind(owner.id())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(json_schema)
+    }
+
+    /// Delete one of the owner's schemas, unless variables still conform to it
+    pub async fn delete(&self, owner: Owner, id: Uuid) -> Result<bool> {
+        let query = format!(
+            "DELETE FROM json_schemas WHERE id = $1 AND {}",
+            owner_filter(&owner, 2)
+        );
+
+        let result = sqlx::query(&query)
+            .bind(id)
+            .bind(owner.id())
+            .execute(&self.pool)
+            .await
+            .map_err(|e| {
+                if let sqlx::Error::Database(db_err) = &e
+                    && db_err.is_foreign_key_violation()
+                {
+                    return AppError::Conflict("Schema is used by variables".to_string());
+                }
+                AppError::Database(e)
+            })?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
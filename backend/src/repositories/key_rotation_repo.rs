@@ -0,0 +1,181 @@
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::KeyRotation;
+
+/// How long a running rotation may go without progress before another server resumes it
+const STALE_AFTER_SECONDS: i64 = 120;
+
+pub struct KeyRotationRepository {
+    pool: Pool<Postgres>,
+}
+
+impl KeyRotationRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        key_version: i32,
+        total_variables: i64,
+        started_by: Uuid,
+    ) -> Result<KeyRotation> {
+        let rotation = sqlx::query_as::<_, KeyRotation>(
+            r#"
+            INSERT INTO key_rotations (key_version, total_variables, started_by)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(key_version)
+        .bind(total_variables)
+        .bind(started_by)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict("A key rotation is already running".to_string());
+            }
+            AppError::Database(e)
+        })?;
+
+        Ok(rotation)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<KeyRotation>> {
+        let rotation = sqlx::query_as::<_, KeyRotation>("SELECT * FROM key_rotations WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(rotation)
+    }
+
+    pub async fn list(&self) -> Result<Vec<KeyRotation>> {
+        let rotations = sqlx::query_as::<_, KeyRotation>(
+            "SELECT * FROM key_rotations ORDER BY created_at DESC",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rotations)
+    }
+
+    pub async fn find_running(&self) -> Result<Option<KeyRotation>> {
+        let rotation = sqlx::query_as::<_, KeyRotation>(
+            "SELECT * FROM key_rotations WHERE status = 'running'",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(rotation)
+    }
+
+    /// Claim the running rotation if the server running it stopped making progress, unless
+    /// it targets a newer master key than `key_version`
+    pub async fn claim_stale(&self, key_version: i32) -> Result<Option<KeyRotation>> {
+        let rotation = sqlx::query_as::<_, KeyRotation>(
+            r#"
+            UPDATE key_rotations
+            SET key_version = $1
+            WHERE status = 'running'
+              AND key_version <= $1
+              AND updated_at < NOW() - make_interval(secs => $2)
+            RETURNING *
+            "#,
+        )
+        .bind(key_version)
+        .bind(STALE_AFTER_SECONDS as f64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(rotation)
+    }
+
+    /// Restart a failed rotation from where it stopped, towards master key `key_version`
+    pub async fn resume(&self, id: Uuid, key_version: i32) -> Result<Option<KeyRotation>> {
+        let rotation = sqlx::query_as::<_, KeyRotation>(
+            r#"
+            UPDATE key_rotations
+            SET status = 'running', key_version = $2, error = NULL
+            WHERE id = $1 AND status = 'failed'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(key_version)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict("A key rotation is already running".to_string());
+            }
+            AppError::Database(e)
+        })?;
+
+        Ok(rotation)
+    }
+
+    /// Record a processed batch ending at `last_variable_id`
+    pub async fn record_progress(
+        &self,
+        id: Uuid,
+        last_variable_id: Uuid,
+        processed: i64,
+        rewrapped: i64,
+        encrypted: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE key_rotations
+            SET last_variable_id = $2,
+                processed_variables = processed_variables + $3,
+                rewrapped_keys = rewrapped_keys + $4,
+                encrypted_variables = encrypted_variables + $5
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(last_variable_id)
+        .bind(processed)
+        .bind(rewrapped)
+        .bind(encrypted)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn complete(&self, id: Uuid, verified_variables: i32) -> Result<KeyRotation> {
+        let rotation = sqlx::query_as::<_, KeyRotation>(
+            r#"
+            UPDATE key_rotations
+            SET status = 'completed', verified_variables = $2, completed_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(verified_variables)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(rotation)
+    }
+
+    pub async fn fail(&self, id: Uuid, error: &str) -> Result<()> {
+        sqlx::query("UPDATE key_rotations SET status = 'failed', error = $2 WHERE id = $1")
+            .bind(id)
+            .bind(error)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
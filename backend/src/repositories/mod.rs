@@ -1,13 +1,44 @@
 pub mod api_key_repo;
+pub mod environment_promotion_repo;
+pub mod environment_repo;
+pub mod json_schema_repo;
+pub mod key_rotation_repo;
+pub mod organization_repo;
+pub mod project_repo;
 pub mod promotion_repo;
+pub mod scheduled_change_repo;
 pub mod tier_repo;
 pub mod usage_repo;
 pub mod user_repo;
 pub mod variable_repo;
+pub mod variable_share_link_repo;
+pub mod variable_share_repo;
+pub mod variable_version_repo;
 
 pub use api_key_repo::*;
+pub use environment_promotion_repo::*;
+pub use environment_repo::*;
+pub use json_schema_repo::*;
+pub use key_rotation_repo::*;
+pub use organization_repo::*;
+pub use project_repo::*;
 pub use promotion_repo::*;
+pub use scheduled_change_repo::*;
 pub use tier_repo::*;
 pub use usage_repo::*;
 pub use user_repo::*;
 pub use variable_repo::*;
+pub use variable_share_link_repo::*;
+pub use variable_share_repo::*;
+pub use variable_version_repo::*;
+
+use crate::models::Owner;
+
+/// SQL predicate restricting rows with `user_id`/`organization_id` columns to `owner`,
+/// whose id is bound at `$param`
+pub(crate) fn owner_filter(owner: &Owner, param: usize) -> String {
+    match owner {
+        Owner::User(_) => format!("user_id = ${} AND organization_id IS NULL", param),
+        Owner::Organization(_) => format!("organization_id = ${}", param),
+    }
+}
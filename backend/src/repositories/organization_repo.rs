@@ -0,0 +1,323 @@
+use chrono::{Duration, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{
+    MemberOrganization, OrgRole, Organization, OrganizationInvitation, OrganizationMember,
+};
+
+pub struct OrganizationRepository {
+    pool: Pool<Postgres>,
+}
+
+impl OrganizationRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Create an organization and register the creator as its owner
+    pub async fn create(&self, name: &str, tier_id: Uuid, created_by: Uuid) -> Result<Organization> {
+        let mut tx = self.pool.begin().await?;
+
+        let organization = sqlx::query_as::<_, Organization>(
+            r#"
+            INSERT INTO organizations (name, tier_id, created_by)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(tier_id)
+        .bind(created_by)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO organization_members (organization_id, user_id, role)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(organization.id)
+        .bind(created_by)
+        .bind(OrgRole::Owner)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(organization)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<Organization>> {
+        let organization = sqlx::query_as::<_, Organization>(
+            r#"
+            SELECT * FROM organizations WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(organization)
+    }
+
+    pub async fn list_by_member(&self, user_id: Uuid) -> Result<Vec<MemberOrganization>> {
+        let organizations = sqlx::query_as::<_, MemberOrganization>(
+            r#"
+            SELECT o.*, m.role FROM organizations o
+            JOIN organization_members m ON m.organization_id = o.id
+            WHERE m.user_id = $1
+            ORDER BY o.created_at ASC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(organizations)
+    }
+
+    pub async fn update_name(&self, id: Uuid, name: &str) -> Result<Organization> {
+        let organization = sqlx::query_as::<_, Organization>(
+            r#"
+            UPDATE organizations
+            SET name = $1, updated_at = NOW()
+            WHERE id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(organization)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM organizations WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_member(&self, org_id: Uuid, user_id: Uuid) -> Result<Option<OrganizationMember>> {
+        let member = sqlx::query_as::<_, OrganizationMember>(
+            r#"
+            SELECT * FROM organization_members WHERE organization_id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(org_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(member)
+    }
+
+    pub async fn list_members(&self, org_id: Uuid) -> Result<Vec<OrganizationMember>> {
+        let members = sqlx::query_as::<_, OrganizationMember>(
+            r#"
+            SELECT * FROM organization_members WHERE organization_id = $1 ORDER BY created_at ASC
+            "#,
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(members)
+    }
+
+    pub async fn count_owners(&self, org_id: Uuid) -> Result<i64> {
+        let count: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM organization_members WHERE organization_id = $1 AND role = 'owner'"
+        )
+        .bind(org_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count.0)
+    }
+
+    pub async fn update_member_role(
+        &self,
+        org_id: Uuid,
+        user_id: Uuid,
+        role: OrgRole,
+    ) -> Result<OrganizationMember> {
+        let member = sqlx::query_as::<_, OrganizationMember>(
+            r#"
+            UPDATE organization_members
+            SET role = $1, updated_at = NOW()
+            WHERE organization_id = $2 AND user_id = $3
+            RETURNING *
+            "#,
+        )
+        .bind(role)
+        .bind(org_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+
+        Ok(member)
+    }
+
+    pub async fn remove_member(&self, org_id: Uuid, user_id: Uuid) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM organization_members WHERE organization_id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(org_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Member not found".to_string()));
+        }
+
+        Ok(())
+    }
+
+    pub async fn create_invitation(
+        &self,
+        org_id: Uuid,
+        email: &str,
+        role: OrgRole,
+        invited_by: Uuid,
+        expires_in_days: i64,
+    ) -> Result<OrganizationInvitation> {
+        let expires_at = Utc::now() + Duration::days(expires_in_days);
+
+        let invitation = sqlx::query_as::<_, OrganizationInvitation>(
+            r#"
+            INSERT INTO organization_invitations (organization_id, email, role, invited_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(org_id)
+        .bind(email)
+        .bind(role)
+        .bind(invited_by)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict("An invitation for this email is already open".to_string());
+            }
+            AppError::Database(e)
+        })?;
+
+        Ok(invitation)
+    }
+
+    pub async fn find_invitation(&self, id: Uuid) -> Result<Option<OrganizationInvitation>> {
+        let invitation = sqlx::query_as::<_, OrganizationInvitation>(
+            r#"
+            SELECT * FROM organization_invitations WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(invitation)
+    }
+
+    pub async fn list_invitations(&self, org_id: Uuid) -> Result<Vec<OrganizationInvitation>> {
+        let invitations = sqlx::query_as::<_, OrganizationInvitation>(
+            r#"
+            SELECT * FROM organization_invitations
+            WHERE organization_id = $1 AND accepted_at IS NULL
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(invitations)
+    }
+
+    pub async fn list_pending_invitations_for_email(
+        &self,
+        email: &str,
+    ) -> Result<Vec<OrganizationInvitation>> {
+        let invitations = sqlx::query_as::<_, OrganizationInvitation>(
+            r#"
+            SELECT * FROM organization_invitations
+            WHERE LOWER(email) = LOWER($1) AND accepted_at IS NULL AND expires_at > NOW()
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(email)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(invitations)
+    }
+
+    /// Mark an invitation accepted and add the user as a member in one transaction
+    pub async fn accept_invitation(
+        &self,
+        invitation: &OrganizationInvitation,
+        user_id: Uuid,
+    ) -> Result<OrganizationMember> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE organization_invitations SET accepted_at = NOW() WHERE id = $1
+            "#,
+        )
+        .bind(invitation.id)
+        .execute(&mut *tx)
+        .await?;
+
+        let member = sqlx::query_as::<_, OrganizationMember>(
+            r#"
+            INSERT INTO organization_members (organization_id, user_id, role)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (organization_id, user_id) DO UPDATE SET role = EXCLUDED.role, updated_at = NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(invitation.organization_id)
+        .bind(user_id)
+        .bind(invitation.role)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(member)
+    }
+
+    pub async fn delete_invitation(&self, id: Uuid, org_id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM organization_invitations WHERE id = $1 AND organization_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(org_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
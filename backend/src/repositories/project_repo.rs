@@ -0,0 +1,103 @@
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use super::owner_filter;
+use crate::error::{AppError, Result};
+use crate::models::{Owner, Project};
+
+pub struct ProjectRepository {
+    pool: Pool<Postgres>,
+}
+
+impl ProjectRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        owner: Owner,
+        created_by: Uuid,
+        name: &str,
+        description: Option<&str>,
+    ) -> Result<Project> {
+        let project = sqlx::query_as::<_, Project>(
+            r#"
+            INSERT INTO projects (user_id, organization_id, name, description)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *
+            "#,
+        )
+        .bind(created_by)
+        .bind(owner.organization_id())
+        .bind(name)
+        .bind(description)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict("Project name already exists".to_string());
+            }
+            AppError::Database(e)
+        })?;
+
+        Ok(project)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<Project>> {
+        let project = sqlx::query_as::<_, Project>(
+            r#"
+            SELECT * FROM projects WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(project)
+    }
+
+    pub async fn find_by_name(&self, owner: Owner, name: &str) -> Result<Option<Project>> {
+        let query = format!(
+            "SELECT * FROM projects WHERE name = $1 AND {}",
+            owner_filter(&owner, 2)
+        );
+
+        let project = sqlx::query_as::<_, Project>(&query)
+            .bind(name)
+            .bind(owner.id())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(project)
+    }
+
+    pub async fn list_by_owner(&self, owner: Owner) -> Result<Vec<Project>> {
+        let query = format!(
+            "SELECT * FROM projects WHERE {} ORDER BY name ASC",
+            owner_filter(&owner, 1)
+        );
+
+        let projects = sqlx::query_as::<_, Project>(&query)
+            .bind(owner.id())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(projects)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM projects WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
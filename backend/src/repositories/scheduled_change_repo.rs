@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::ScheduledChange;
+
+pub struct ScheduledChangeRepository {
+    pool: Pool<Postgres>,
+}
+
+impl ScheduledChangeRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        id: Uuid,
+        variable_id: Uuid,
+        storage_path: &str,
+        apply_at: DateTime<Utc>,
+        message: Option<&str>,
+        created_by: Uuid,
+    ) -> Result<ScheduledChange> {
+        let change = sqlx::query_as::<_, ScheduledChange>(
+            r#"
+            INSERT INTO scheduled_changes (id, variable_id, storage_path, apply_at, message,
+                                           created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(variable_id)
+        .bind(storage_path)
+        .bind(apply_at)
+        .bind(message)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(change)
+    }
+
+    /// Changes of a variable, soonest first
+    pub async fn list(&self, variable_id: Uuid) -> Result<Vec<ScheduledChange>> {
+        let changes = sqlx::query_as::<_, ScheduledChange>(
+            r#"
+            SELECT * FROM scheduled_changes
+            WHERE variable_id = $1
+            ORDER BY apply_at ASC, created_at ASC
+            "#,
+        )
+        .bind(variable_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(changes)
+    }
+
+    pub async fn find(&self, id: Uuid, variable_id: Uuid) -> Result<Option<ScheduledChange>> {
+        let change = sqlx::query_as::<_, ScheduledChange>(
+            "SELECT * FROM scheduled_changes WHERE id = $1 AND variable_id = $2",
+        )
+        .bind(id)
+        .bind(variable_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(change)
+    }
+
+    /// Cancel a pending change; waits for the scheduler if it is applying the change, and
+    /// returns `None` unless the change is still pending
+    pub async fn cancel(&self, id: Uuid, variable_id: Uuid) -> Result<Option<ScheduledChange>> {
+        let change = sqlx::query_as::<_, ScheduledChange>(
+            r#"
+            UPDATE scheduled_changes SET status = 'cancelled'
+            WHERE id = $1 AND variable_id = $2 AND status = 'pending'
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(variable_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(change)
+    }
+
+    /// Lock the pending change due soonest that isn't waiting to be retried, skipping those
+    /// another transaction holds
+    pub async fn lock_due_tx(conn: &mut PgConnection) -> Result<Option<ScheduledChange>> {
+        let change = sqlx::query_as::<_, ScheduledChange>(
+            r#"
+            SELECT * FROM scheduled_changes
+            WHERE status = 'pending' AND apply_at <= NOW()
+                AND (next_attempt_at IS NULL OR next_attempt_at <= NOW())
+            ORDER BY apply_at ASC
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(change)
+    }
+
+    pub async fn mark_applied_tx(
+        conn: &mut PgConnection,
+        id: Uuid,
+        applied_version: i32,
+    ) -> Result<ScheduledChange> {
+        let change = sqlx::query_as::<_, ScheduledChange>(
+            r#"
+            UPDATE scheduled_changes
+            SET status = 'applied', applied_version = $2, applied_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(applied_version)
+        .fetch_one(conn)
+        .await?;
+
+        Ok(change)
+    }
+
+    pub async fn mark_failed_tx(
+        conn: &mut PgConnection,
+        id: Uuid,
+        error: &str,
+    ) -> Result<ScheduledChange> {
+        let change = sqlx::query_as::<_, ScheduledChange>(
+            r#"
+            UPDATE scheduled_changes SET status = 'failed', error = $2, next_attempt_at = NULL
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .fetch_one(conn)
+        .await?;
+
+        Ok(change)
+    }
+
+    /// Record a failed attempt at a change still pending and retry it `delay` from now
+    pub async fn retry_tx(
+        conn: &mut PgConnection,
+        id: Uuid,
+        error: &str,
+        delay: Duration,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE scheduled_changes
+            SET attempts = attempts + 1, error = $2,
+                next_attempt_at = NOW() + make_interval(secs => $3)
+            WHERE id = $1 AND status = 'pending'
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .bind(delay.as_secs_f64())
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Storage paths of the data staged by a variable's pending changes
+    pub async fn list_pending_paths_tx(
+        conn: &mut PgConnection,
+        variable_id: Uuid,
+    ) -> Result<Vec<String>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT storage_path FROM scheduled_changes
+            WHERE variable_id = $1 AND status = 'pending'
+            "#,
+        )
+        .bind(variable_id)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.0).collect())
+    }
+}
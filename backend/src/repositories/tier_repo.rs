@@ -2,7 +2,7 @@ use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
 use crate::error::Result;
-use crate::models::Tier;
+use crate::models::{Owner, Tier};
 
 pub struct TierRepository {
     pool: Pool<Postgres>,
@@ -26,6 +26,25 @@ impl TierRepository {
         Ok(tier)
     }
 
+    /// The tier whose limits apply to variables of `owner`
+    pub async fn find_for_owner(&self, owner: Owner) -> Result<Option<Tier>> {
+        let query = match owner {
+            Owner::User(_) => {
+                "SELECT t.* FROM tiers t JOIN users u ON u.tier_id = t.id WHERE u.id = $1"
+            }
+            Owner::Organization(_) => {
+                "SELECT t.* FROM tiers t JOIN organizations o ON o.tier_id = t.id WHERE o.id = $1"
+            }
+        };
+
+        let tier = sqlx::query_as::<_, Tier>(query)
+            .bind(owner.id())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(tier)
+    }
+
     pub async fn find_by_name(&self, name: &str) -> Result<Option<Tier>> {
         let tier = sqlx::query_as::<_, Tier>(
             r#"
@@ -63,6 +82,7 @@ impl TierRepository {
         Ok(tiers)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
         &self,
         name: &str,
@@ -71,13 +91,16 @@ impl TierRepository {
         max_variable_size_mb: i32,
         max_requests_per_day: i32,
         max_api_keys: i32,
+        max_versions: i32,
+        trash_retention_days: i32,
         price_monthly: i32,
     ) -> Result<Tier> {
         let tier = sqlx::query_as::<_, Tier>(
             r#"
             INSERT INTO tiers (name, description, max_variables, max_variable_size_mb,
-                             max_requests_per_day, max_api_keys, price_monthly, is_active)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, true)
+                             max_requests_per_day, max_api_keys, max_versions,
+                             trash_retention_days, price_monthly, is_active)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, true)
             RETURNING *
             "#,
         )
@@ -87,6 +110,8 @@ impl TierRepository {
         .bind(max_variable_size_mb)
         .bind(max_requests_per_day)
         .bind(max_api_keys)
+        .bind(max_versions)
+        .bind(trash_retention_days)
         .bind(price_monthly)
         .fetch_one(&self.pool)
         .await?;
@@ -94,6 +119,7 @@ impl TierRepository {
         Ok(tier)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn update(
         &self,
         id: Uuid,
@@ -103,6 +129,8 @@ impl TierRepository {
         max_variable_size_mb: Option<i32>,
         max_requests_per_day: Option<i32>,
         max_api_keys: Option<i32>,
+        max_versions: Option<i32>,
+        trash_retention_days: Option<i32>,
         price_monthly: Option<i32>,
         is_active: Option<bool>,
     ) -> Result<Tier> {
@@ -133,6 +161,14 @@ impl TierRepository {
             param_count += 1;
             query.push_str(&format!(", max_api_keys = ${}", param_count));
         }
+        if max_versions.is_some() {
+            param_count += 1;
+            query.push_str(&format!(", max_versions = ${}", param_count));
+        }
+        if trash_retention_days.is_some() {
+            param_count += 1;
+            query.push_str(&format!(", trash_retention_days = ${}", param_count));
+        }
         if price_monthly.is_some() {
             param_count += 1;
             query.push_str(&format!(", price_monthly = ${}", param_count));
@@ -164,6 +200,12 @@ impl TierRepository {
         if let Some(ma) = max_api_keys {
             query_builder = query_builder.bind(ma);
         }
+        if let Some(mv) = max_versions {
+            query_builder = query_builder.bind(mv);
+        }
+        if let Some(tr) = trash_retention_days {
+            query_builder = query_builder.bind(tr);
+        }
         if let Some(pm) = price_monthly {
             query_builder = query_builder.bind(pm);
         }
@@ -3,7 +3,7 @@ use sqlx::{Pool, Postgres};
 use uuid::Uuid;
 
 use crate::error::Result;
-use crate::models::UsageStats;
+use crate::models::{Owner, UsageStats};
 
 pub struct UsageRepository {
     pool: Pool<Postgres>,
@@ -23,7 +23,8 @@ impl UsageRepository {
                                    variables_updated, variables_deleted, variables_read,
                                    total_bytes_stored, total_bytes_transferred)
             VALUES ($1, $2, 0, 0, 0, 0, 0, 0, 0)
-            ON CONFLICT (user_id, date) DO UPDATE SET user_id = EXCLUDED.user_id
+            ON CONFLICT (user_id, date) WHERE organization_id IS NULL
+            DO UPDATE SET user_id = EXCLUDED.user_id
             RETURNING *
             "#,
         )
@@ -44,7 +45,8 @@ impl UsageRepository {
                                    variables_updated, variables_deleted, variables_read,
                                    total_bytes_stored, total_bytes_transferred)
             VALUES ($1, $2, 1, 0, 0, 0, 0, 0, 0)
-            ON CONFLICT (user_id, date) DO UPDATE SET requests_count = usage_stats.requests_count + 1
+            ON CONFLICT (user_id, date) WHERE organization_id IS NULL
+            DO UPDATE SET requests_count = usage_stats.requests_count + 1
             "#,
         )
         .bind(user_id)
@@ -55,6 +57,42 @@ impl UsageRepository {
         Ok(())
     }
 
+    /// Count a read of `bytes` served on behalf of `owner`, e.g. a public or linked variable
+    pub async fn record_read(&self, owner: Owner, bytes: i64) -> Result<()> {
+        let today = Utc::now().date_naive();
+        let conflict = match owner {
+            Owner::User(_) => "(user_id, date) WHERE organization_id IS NULL",
+            Owner::Organization(_) => "(organization_id, date) WHERE organization_id IS NOT NULL",
+        };
+        let user_id = match owner {
+            Owner::User(id) => Some(id),
+            Owner::Organization(_) => None,
+        };
+
+        let query = format!(
+            r#"
+            INSERT INTO usage_stats (user_id, organization_id, date, requests_count,
+                                   variables_read, total_bytes_transferred)
+            VALUES ($1, $2, $3, 1, 1, $4)
+            ON CONFLICT {}
+            DO UPDATE SET requests_count = usage_stats.requests_count + 1,
+                          variables_read = usage_stats.variables_read + 1,
+                          total_bytes_transferred = usage_stats.total_bytes_transferred + $4
+            "#,
+            conflict
+        );
+
+        sqlx::query(&query)
+            .bind(user_id)
+            .bind(owner.organization_id())
+            .bind(today)
+            .bind(bytes)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
     pub async fn get_requests_today(&self, user_id: Uuid) -> Result<i32> {
         let today = Utc::now().date_naive();
 
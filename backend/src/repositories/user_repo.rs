@@ -35,10 +35,10 @@ impl UserRepository {
         .fetch_one(&self.pool)
         .await
         .map_err(|e| {
-            if let sqlx::Error::Database(db_err) = &e {
-                if db_err.is_unique_violation() {
-                    return AppError::Conflict("Email already exists".to_string());
-                }
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict("Email already exists".to_string());
             }
             AppError::Database(e)
         })?;
@@ -140,6 +140,24 @@ impl UserRepository {
         Ok(user)
     }
 
+    pub async fn update_role(&self, id: Uuid, role: UserRole) -> Result<User> {
+        let user = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET role = $1, updated_at = NOW()
+            WHERE id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(role)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        Ok(user)
+    }
+
     pub async fn list(
         &self,
         page: i32,
@@ -1,8 +1,67 @@
-use sqlx::{Pool, Postgres};
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgArguments;
+use sqlx::query::QueryAs;
+use sqlx::{PgConnection, Pool, Postgres};
 use uuid::Uuid;
 
-use crate::error::Result;
-use crate::models::Variable;
+use super::owner_filter;
+use crate::error::{AppError, Result};
+use crate::models::{
+    AccessibleVariable, Owner, TagCount, TagMatch, TrashedVariable, Variable, VariableFilter,
+};
+
+/// Condition excluding trashed variables and expired ones, which are treated as deleted
+/// until swept
+const LIVE: &str = "(is_active AND (expires_at IS NULL OR expires_at > NOW()))";
+
+/// `LIVE` for queries aliasing `variables` as `v`
+const LIVE_V: &str = "(v.is_active AND (v.expires_at IS NULL OR v.expires_at > NOW()))";
+
+/// Condition selecting trashed variables that have not expired meanwhile
+const TRASHED: &str = "(NOT is_active AND (expires_at IS NULL OR expires_at > NOW()))";
+
+/// Select variables along with the share the user bound at `$param` holds on them
+fn accessible_select(param: usize) -> String {
+    accessible_select_with(param, "")
+}
+
+/// `accessible_select` with `columns` selected alongside each variable
+fn accessible_select_with(param: usize, columns: &str) -> String {
+    format!(
+        r#"
+        SELECT v.*, s.permission AS share_permission, COALESCE(o.name, u.email) AS shared_by
+               {columns}
+        FROM variables v
+        LEFT JOIN variable_shares s ON s.variable_id = v.id AND s.user_id = ${param}
+        LEFT JOIN users u ON s.id IS NOT NULL AND u.id = v.user_id
+        LEFT JOIN organizations o ON s.id IS NOT NULL AND o.id = v.organization_id
+        "#
+    )
+}
+
+/// Bind the parameters of the conditions `list` derives from `filter`, in order
+fn bind_filter<'q, O>(
+    query: QueryAs<'q, Postgres, O, PgArguments>,
+    filter: &'q VariableFilter,
+) -> QueryAs<'q, Postgres, O, PgArguments> {
+    let mut query = query;
+    if let Some(search) = &filter.search {
+        query = query.bind(search.as_str());
+    }
+    if let Some(text) = &filter.query {
+        query = query.bind(text.as_str());
+    }
+    if !filter.tags.is_empty() {
+        query = match filter.tag_match {
+            TagMatch::All => query.bind(serde_json::json!(filter.tags)),
+            TagMatch::Any => query.bind(filter.tags.as_slice()),
+        };
+    }
+    if let Some(seconds) = filter.expiring_within {
+        query = query.bind(seconds as f64);
+    }
+    query
+}
 
 pub struct VariableRepository {
     pool: Pool<Postgres>,
@@ -13,9 +72,43 @@ impl VariableRepository {
         Self { pool }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn create(
         &self,
-        user_id: Uuid,
+        owner: Owner,
+        environment_id: Uuid,
+        created_by: Uuid,
+        key: &str,
+        description: Option<&str>,
+        size_bytes: i64,
+        storage_path: &str,
+        is_encrypted: bool,
+        tags: Option<serde_json::Value>,
+    ) -> Result<Variable> {
+        let mut conn = self.pool.acquire().await?;
+
+        Self::create_tx(
+            &mut conn,
+            owner,
+            environment_id,
+            created_by,
+            key,
+            description,
+            size_bytes,
+            storage_path,
+            is_encrypted,
+            tags,
+        )
+        .await
+    }
+
+    /// Insert a variable on `conn`, typically inside a transaction
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_tx(
+        conn: &mut PgConnection,
+        owner: Owner,
+        environment_id: Uuid,
+        created_by: Uuid,
         key: &str,
         description: Option<&str>,
         size_bytes: i64,
@@ -25,115 +118,561 @@ impl VariableRepository {
     ) -> Result<Variable> {
         let variable = sqlx::query_as::<_, Variable>(
             r#"
-            INSERT INTO variables (user_id, key, description, size_bytes, storage_path,
-                                 is_encrypted, tags, version)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, 1)
+            INSERT INTO variables (user_id, organization_id, environment_id, key, description,
+                                 size_bytes, storage_path, is_encrypted, tags, version)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, 1)
             RETURNING *
             "#,
         )
-        .bind(user_id)
+        .bind(created_by)
+        .bind(owner.organization_id())
+        .bind(environment_id)
         .bind(key)
         .bind(description)
         .bind(size_bytes)
         .bind(storage_path)
         .bind(is_encrypted)
         .bind(tags)
-        .fetch_one(&self.pool)
-        .await?;
+        .fetch_one(conn)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict("Variable key already exists".to_string());
+            }
+            AppError::Database(e)
+        })?;
 
         Ok(variable)
     }
 
-    pub async fn find_by_id(&self, id: Uuid, user_id: Uuid) -> Result<Option<Variable>> {
+    /// Lock every live variable of an environment until the transaction on `conn` ends
+    pub async fn lock_environment_tx(
+        conn: &mut PgConnection,
+        environment_id: Uuid,
+    ) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables WHERE environment_id = $1 AND {LIVE}
+            ORDER BY key ASC FOR UPDATE
+            "#,
+        ))
+        .bind(environment_id)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// Lock a variable until the transaction on `conn` ends
+    pub async fn lock_tx(conn: &mut PgConnection, id: Uuid) -> Result<Option<Variable>> {
         let variable = sqlx::query_as::<_, Variable>(
             r#"
-            SELECT * FROM variables WHERE id = $1 AND user_id = $2
+            SELECT * FROM variables WHERE id = $1 FOR UPDATE
             "#,
         )
         .bind(id)
-        .bind(user_id)
-        .fetch_optional(&self.pool)
+        .fetch_optional(conn)
         .await?;
 
         Ok(variable)
     }
 
-    pub async fn find_by_key(&self, key: &str, user_id: Uuid) -> Result<Option<Variable>> {
+    /// Lock a variable unless it is trashed or expired
+    pub async fn lock_live_tx(conn: &mut PgConnection, id: Uuid) -> Result<Option<Variable>> {
+        let variable = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables WHERE id = $1 AND {LIVE} FOR UPDATE
+            "#,
+        ))
+        .bind(id)
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(variable)
+    }
+
+    /// Replace the metadata of a live variable of the environment wholesale and bump its
+    /// version
+    pub async fn overwrite_tx(
+        conn: &mut PgConnection,
+        id: Uuid,
+        environment_id: Uuid,
+        description: Option<&str>,
+        size_bytes: i64,
+        is_encrypted: bool,
+        tags: Option<serde_json::Value>,
+    ) -> Result<Variable> {
         let variable = sqlx::query_as::<_, Variable>(
             r#"
-            SELECT * FROM variables WHERE key = $1 AND user_id = $2
+            UPDATE variables
+            SET description = $2,
+                size_bytes = $3,
+                is_encrypted = $4,
+                tags = $5,
+                version = version + 1,
+                updated_at = NOW()
+            WHERE id = $1 AND environment_id = $6 AND is_active
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(description)
+        .bind(size_bytes)
+        .bind(is_encrypted)
+        .bind(tags)
+        .bind(environment_id)
+        .fetch_optional(conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+
+        Ok(variable)
+    }
+
+    pub async fn delete_tx(conn: &mut PgConnection, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM variables WHERE id = $1
             "#,
         )
+        .bind(id)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Find a variable of the environment, or one shared with `shared_with`
+    pub async fn find_by_id(
+        &self,
+        id: Uuid,
+        environment_id: Uuid,
+        shared_with: Option<Uuid>,
+    ) -> Result<Option<AccessibleVariable>> {
+        let query = format!(
+            "{} WHERE v.id = $1 AND (v.environment_id = $2 OR s.id IS NOT NULL) AND {}",
+            accessible_select(3),
+            LIVE_V
+        );
+
+        let variable = sqlx::query_as::<_, AccessibleVariable>(&query)
+            .bind(id)
+            .bind(environment_id)
+            .bind(shared_with)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(variable)
+    }
+
+    /// Find the variables with the given ids, in the environment or shared with
+    /// `shared_with`, and those of the environment with the given keys
+    pub async fn find_many(
+        &self,
+        environment_id: Uuid,
+        shared_with: Option<Uuid>,
+        ids: &[Uuid],
+        keys: &[String],
+    ) -> Result<Vec<AccessibleVariable>> {
+        let query = format!(
+            r#"
+            {}
+            WHERE ((v.id = ANY($1) AND (v.environment_id = $2 OR s.id IS NOT NULL))
+                   OR (v.key = ANY($4) AND v.environment_id = $2))
+              AND {}
+            "#,
+            accessible_select(3),
+            LIVE_V
+        );
+
+        let variables = sqlx::query_as::<_, AccessibleVariable>(&query)
+            .bind(ids)
+            .bind(environment_id)
+            .bind(shared_with)
+            .bind(keys)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(variables)
+    }
+
+    /// Find a variable by id alone, for callers that authorize access themselves
+    pub async fn find(&self, id: Uuid) -> Result<Option<Variable>> {
+        let variable = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables WHERE id = $1 AND {LIVE}
+            "#,
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(variable)
+    }
+
+    /// Find the public variable `key` of the user or organization `owner_id`
+    pub async fn find_public(&self, owner_id: Uuid, key: &str) -> Result<Option<Variable>> {
+        let variable = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables
+            WHERE is_public AND key = $2 AND COALESCE(organization_id, user_id) = $1 AND {LIVE}
+            "#,
+        ))
+        .bind(owner_id)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(variable)
+    }
+
+    pub async fn find_by_key(&self, key: &str, environment_id: Uuid) -> Result<Option<Variable>> {
+        let variable = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables WHERE key = $1 AND environment_id = $2 AND {LIVE}
+            "#,
+        ))
         .bind(key)
-        .bind(user_id)
+        .bind(environment_id)
         .fetch_optional(&self.pool)
         .await?;
 
         Ok(variable)
     }
 
+    pub async fn find_by_keys(
+        &self,
+        keys: &[String],
+        environment_id: Uuid,
+    ) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables WHERE key = ANY($1) AND environment_id = $2 AND {LIVE}
+            "#,
+        ))
+        .bind(keys)
+        .bind(environment_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// List the variables of an environment whose data references any of `keys`
+    pub async fn list_referrers(
+        &self,
+        environment_id: Uuid,
+        keys: &[String],
+    ) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables
+            WHERE environment_id = $1 AND referenced_keys && $2 AND {LIVE}
+            ORDER BY key
+            "#,
+        ))
+        .bind(environment_id)
+        .bind(keys)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// List the variables of an environment, plus those shared with `shared_with`,
+    /// narrowed by `filter`. The total counts every variable the filter matches.
     pub async fn list(
         &self,
-        user_id: Uuid,
+        environment_id: Uuid,
+        shared_with: Option<Uuid>,
         page: i32,
         page_size: i32,
-        search: Option<&str>,
-    ) -> Result<(Vec<Variable>, i64)> {
+        filter: &VariableFilter,
+    ) -> Result<(Vec<AccessibleVariable>, i64)> {
         let offset = (page - 1) * page_size;
 
-        let mut query = String::from(
+        // $1 and $2 bind the environment and the user whose shares are visible
+        let mut conditions = vec![
+            "(v.environment_id = $1 OR s.id IS NOT NULL)".to_string(),
+            LIVE_V.to_string(),
+        ];
+        let mut param = 3;
+        if filter.search.is_some() {
+            conditions.push(format!("v.key ILIKE '%' || ${} || '%'", param));
+            param += 1;
+        }
+        let text_param = filter.query.is_some().then_some(param);
+        if let Some(text) = text_param {
+            conditions.push(format!(
+                "v.search_vector @@ websearch_to_tsquery('english', ${})",
+                text
+            ));
+            param += 1;
+        }
+        if !filter.tags.is_empty() {
+            conditions.push(match filter.tag_match {
+                TagMatch::All => format!("v.tags @> ${}", param),
+                TagMatch::Any => format!("v.tags ?| ${}", param),
+            });
+            param += 1;
+        }
+        if filter.expiring_within.is_some() {
+            conditions.push(format!(
+                "v.expires_at <= NOW() + make_interval(secs => ${})",
+                param
+            ));
+            param += 1;
+        }
+        let conditions = conditions.join(" AND ");
+
+        // Text searches rank the matches and highlight where they matched
+        let (select, order) = match text_param {
+            Some(text) => {
+                let tsquery = format!("websearch_to_tsquery('english', ${})", text);
+                let columns = format!(
+                    r#", ts_rank(v.search_vector, {tsquery}) AS rank,
+                    ts_headline('english', concat_ws(' ', v.key, v.description, v.content_text),
+                                {tsquery}, 'MaxFragments=2, MaxWords=20, MinWords=5') AS highlight"#
+                );
+                (accessible_select_with(2, &columns), "rank DESC, v.created_at DESC")
+            }
+            None if filter.expiring_within.is_some() => {
+                (accessible_select(2), "v.expires_at ASC, v.created_at DESC")
+            }
+            None => (accessible_select(2), "v.created_at DESC"),
+        };
+
+        let query = format!(
+            "{} WHERE {} ORDER BY {} LIMIT ${} OFFSET ${}",
+            select,
+            conditions,
+            order,
+            param,
+            param + 1
+        );
+        let variables = bind_filter(
+            sqlx::query_as::<_, AccessibleVariable>(&query)
+                .bind(environment_id)
+                .bind(shared_with),
+            filter,
+        )
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let count_query = format!(
             r#"
-            SELECT * FROM variables
-            WHERE user_id = $1
+            SELECT COUNT(*) FROM variables v
+            LEFT JOIN variable_shares s ON s.variable_id = v.id AND s.user_id = $2
+            WHERE {}
             "#,
+            conditions
         );
+        let total: (i64,) = bind_filter(
+            sqlx::query_as(&count_query)
+                .bind(environment_id)
+                .bind(shared_with),
+            filter,
+        )
+        .fetch_one(&self.pool)
+        .await?;
 
-        if search.is_some() {
-            query.push_str(" AND key ILIKE '%' || $4 || '%'");
-        }
+        Ok((variables, total.0))
+    }
 
-        query.push_str(" ORDER BY created_at DESC LIMIT $2 OFFSET $3");
+    /// Number of variables of the environment carrying each tag
+    pub async fn tag_counts(&self, environment_id: Uuid) -> Result<Vec<TagCount>> {
+        let counts = sqlx::query_as::<_, TagCount>(&format!(
+            r#"
+            SELECT t.tag, COUNT(*) AS count
+            FROM variables v
+            CROSS JOIN LATERAL jsonb_array_elements_text(v.tags) AS t(tag)
+            WHERE v.environment_id = $1 AND jsonb_typeof(v.tags) = 'array' AND {LIVE_V}
+            GROUP BY t.tag
+            ORDER BY t.tag ASC
+            "#,
+        ))
+        .bind(environment_id)
+        .fetch_all(&self.pool)
+        .await?;
 
-        let mut query_builder = sqlx::query_as::<_, Variable>(&query)
-            .bind(user_id)
-            .bind(page_size)
-            .bind(offset);
+        Ok(counts)
+    }
 
-        if let Some(s) = search {
-            query_builder = query_builder.bind(s);
-        }
+    /// Every variable of an environment, ordered by key
+    pub async fn list_all(&self, environment_id: Uuid) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables WHERE environment_id = $1 AND {LIVE} ORDER BY key ASC
+            "#,
+        ))
+        .bind(environment_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// Count live variables across every environment of `owner`; tier limits apply per owner
+    pub async fn count_by_owner(&self, owner: Owner) -> Result<i32> {
+        let query = format!(
+            "SELECT COUNT(*) FROM variables WHERE {} AND {}",
+            owner_filter(&owner, 1),
+            LIVE
+        );
+
+        let count: (i64,) = sqlx::query_as(&query)
+            .bind(owner.id())
+            .fetch_one(&self.pool)
+            .await?;
 
-        let variables = query_builder.fetch_all(&self.pool).await?;
+        Ok(count.0 as i32)
+    }
+
+    /// Storage paths of every variable held by `owner`, of their versions and of their
+    /// staged changes, used to clean up files on removal
+    pub async fn list_storage_paths(&self, owner: Owner) -> Result<Vec<String>> {
+        let filter = owner_filter(&owner, 1);
+        let query = format!(
+            r#"
+            SELECT storage_path FROM variables WHERE {filter}
+            UNION ALL
+            SELECT vv.storage_path FROM variable_versions vv
+            JOIN variables v ON v.id = vv.variable_id
+            WHERE {filter}
+            UNION ALL
+            SELECT sc.storage_path FROM scheduled_changes sc
+            JOIN variables v ON v.id = sc.variable_id
+            WHERE {filter} AND sc.status = 'pending'
+            "#
+        );
+
+        let rows: Vec<(String,)> = sqlx::query_as(&query)
+            .bind(owner.id())
+            .fetch_all(&self.pool)
+            .await?;
 
-        let total: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM variables WHERE user_id = $1"
+        Ok(rows.into_iter().map(|r| r.0).collect())
+    }
+
+    /// Storage paths of every variable in the given environments, of their versions and of
+    /// their staged changes
+    pub async fn list_storage_paths_in(&self, environment_ids: &[Uuid]) -> Result<Vec<String>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"
+            SELECT storage_path FROM variables WHERE environment_id = ANY($1)
+            UNION ALL
+            SELECT vv.storage_path FROM variable_versions vv
+            JOIN variables v ON v.id = vv.variable_id
+            WHERE v.environment_id = ANY($1)
+            UNION ALL
+            SELECT sc.storage_path FROM scheduled_changes sc
+            JOIN variables v ON v.id = sc.variable_id
+            WHERE v.environment_id = ANY($1) AND sc.status = 'pending'
+            "#,
         )
-        .bind(user_id)
-        .fetch_one(&self.pool)
+        .bind(environment_ids)
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok((variables, total.0))
+        Ok(rows.into_iter().map(|r| r.0).collect())
     }
 
-    pub async fn count_by_user(&self, user_id: Uuid) -> Result<i32> {
-        let count: (i64,) = sqlx::query_as(
-            "SELECT COUNT(*) FROM variables WHERE user_id = $1"
+    /// Lock up to `limit` expired variables, skipping those another transaction holds
+    pub async fn lock_expired_tx(conn: &mut PgConnection, limit: i64) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(
+            r#"
+            SELECT * FROM variables
+            WHERE expires_at <= NOW()
+            ORDER BY expires_at ASC
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+            "#,
         )
-        .bind(user_id)
-        .fetch_one(&self.pool)
+        .bind(limit)
+        .fetch_all(conn)
         .await?;
 
-        Ok(count.0 as i32)
+        Ok(variables)
     }
 
-    pub async fn update(
+    /// Lock the expired variable holding `key` in an environment, if there is one
+    pub async fn lock_expired_key_tx(
+        conn: &mut PgConnection,
+        environment_id: Uuid,
+        key: &str,
+    ) -> Result<Option<Variable>> {
+        let variable = sqlx::query_as::<_, Variable>(
+            r#"
+            SELECT * FROM variables
+            WHERE environment_id = $1 AND key = $2 AND is_active AND expires_at <= NOW()
+            FOR UPDATE
+            "#,
+        )
+        .bind(environment_id)
+        .bind(key)
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(variable)
+    }
+
+    pub async fn count_encrypted(&self) -> Result<i64> {
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM variables WHERE is_encrypted")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count.0)
+    }
+
+    /// Up to `limit` encrypted variables, across all owners, with ids after `after`
+    pub async fn list_encrypted_after(
         &self,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(
+            r#"
+            SELECT * FROM variables
+            WHERE is_encrypted AND ($1::uuid IS NULL OR id > $1)
+            ORDER BY id ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// Up to `limit` encrypted variables picked at random
+    pub async fn sample_encrypted(&self, limit: i64) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(
+            "SELECT * FROM variables WHERE is_encrypted ORDER BY random() LIMIT $1",
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// Update a variable and bump its version. With `expected_versions`, only updates
+    /// while its version is one of them; returns `None` when it isn't.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update_tx(
+        conn: &mut PgConnection,
         id: Uuid,
-        user_id: Uuid,
+        environment_id: Uuid,
         description: Option<&str>,
         size_bytes: Option<i64>,
         tags: Option<serde_json::Value>,
-    ) -> Result<Variable> {
+        expected_versions: Option<&[i32]>,
+    ) -> Result<Option<Variable>> {
         let variable = sqlx::query_as::<_, Variable>(
             r#"
             UPDATE variables
@@ -142,33 +681,380 @@ impl VariableRepository {
                 tags = COALESCE($5, tags),
                 version = version + 1,
                 updated_at = NOW()
-            WHERE id = $1 AND user_id = $2
+            WHERE id = $1 AND environment_id = $2 AND is_active
+              AND ($6::INTEGER[] IS NULL OR version = ANY($6))
             RETURNING *
             "#,
         )
         .bind(id)
-        .bind(user_id)
+        .bind(environment_id)
         .bind(description)
         .bind(size_bytes)
         .bind(tags)
-        .fetch_one(&self.pool)
+        .bind(expected_versions)
+        .fetch_optional(conn)
         .await?;
 
         Ok(variable)
     }
 
-    pub async fn delete(&self, id: Uuid, user_id: Uuid) -> Result<Variable> {
+    /// Replace the indexed contents of a variable; `None` leaves its contents unsearchable
+    pub async fn set_content_text_tx(
+        conn: &mut PgConnection,
+        id: Uuid,
+        content_text: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query("UPDATE variables SET content_text = $2 WHERE id = $1")
+            .bind(id)
+            .bind(content_text)
+            .execute(conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Unencrypted variables, trashed ones included, whose contents were never indexed, in
+    /// order of id after `after`
+    pub async fn list_unindexed(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(
+            r#"
+            SELECT * FROM variables
+            WHERE content_text IS NULL AND NOT is_encrypted AND ($1::UUID IS NULL OR id > $1)
+            ORDER BY id
+            LIMIT $2
+            "#,
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// Index the contents of a variable still at `version` that has none indexed; a write in
+    /// the meantime indexes its own contents
+    pub async fn backfill_content_text(
+        &self,
+        id: Uuid,
+        version: i32,
+        content_text: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE variables SET content_text = $3
+            WHERE id = $1 AND version = $2 AND content_text IS NULL AND NOT is_encrypted
+            "#,
+        )
+        .bind(id)
+        .bind(version)
+        .bind(content_text)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Unencrypted variables, trashed ones included, whose references were never recorded,
+    /// in order of id after `after`
+    pub async fn list_unindexed_references(
+        &self,
+        after: Option<Uuid>,
+        limit: i64,
+    ) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(
+            r#"
+            SELECT * FROM variables
+            WHERE referenced_keys IS NULL AND NOT is_encrypted
+                AND ($1::UUID IS NULL OR id > $1)
+            ORDER BY id
+            LIMIT $2
+            "#,
+        )
+        .bind(after)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// Record the references of a variable still at `version` that has none recorded; a
+    /// write in the meantime records its own
+    pub async fn backfill_referenced_keys(
+        &self,
+        id: Uuid,
+        version: i32,
+        keys: &[String],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE variables SET referenced_keys = $3
+            WHERE id = $1 AND version = $2 AND referenced_keys IS NULL AND NOT is_encrypted
+            "#,
+        )
+        .bind(id)
+        .bind(version)
+        .bind(keys)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace the keys a variable's data references
+    pub async fn set_referenced_keys_tx(
+        conn: &mut PgConnection,
+        id: Uuid,
+        keys: &[String],
+    ) -> Result<()> {
+        sqlx::query("UPDATE variables SET referenced_keys = $2 WHERE id = $1")
+            .bind(id)
+            .bind(keys)
+            .execute(conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Set when the variable expires; `None` makes it permanent. Callers set it as part of
+    /// a write that bumps the version.
+    pub async fn set_expiry_tx(
+        conn: &mut PgConnection,
+        id: Uuid,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Variable> {
         let variable = sqlx::query_as::<_, Variable>(
             r#"
-            DELETE FROM variables WHERE id = $1 AND user_id = $2
+            UPDATE variables SET expires_at = $2 WHERE id = $1 RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(expires_at)
+        .fetch_one(conn)
+        .await?;
+
+        Ok(variable)
+    }
+
+    /// Bump a variable's version after a change outside its history, so its `ETag` changes
+    pub async fn bump_version_tx(conn: &mut PgConnection, id: Uuid) -> Result<Variable> {
+        let variable = sqlx::query_as::<_, Variable>(
+            r#"
+            UPDATE variables SET version = version + 1, updated_at = NOW()
+            WHERE id = $1
             RETURNING *
             "#,
         )
         .bind(id)
-        .bind(user_id)
-        .fetch_one(&self.pool)
+        .fetch_one(conn)
         .await?;
 
         Ok(variable)
     }
+
+    /// Attach a named schema or a schema of the variable's own, or neither to detach it
+    pub async fn set_schema_tx(
+        conn: &mut PgConnection,
+        id: Uuid,
+        schema_id: Option<Uuid>,
+        inline_schema: Option<&serde_json::Value>,
+    ) -> Result<Variable> {
+        let variable = sqlx::query_as::<_, Variable>(
+            r#"
+            UPDATE variables SET schema_id = $2, inline_schema = $3, updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(schema_id)
+        .bind(inline_schema)
+        .fetch_one(conn)
+        .await?;
+
+        Ok(variable)
+    }
+
+    /// Make a variable public or private, bumping its version so its `ETag` changes
+    pub async fn set_public(
+        &self,
+        id: Uuid,
+        environment_id: Uuid,
+        is_public: bool,
+    ) -> Result<Variable> {
+        let variable = sqlx::query_as::<_, Variable>(
+            r#"
+            UPDATE variables SET is_public = $3, version = version + 1, updated_at = NOW()
+            WHERE id = $1 AND environment_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(environment_id)
+        .bind(is_public)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict(
+                    "Another public variable already uses this key".to_string(),
+                );
+            }
+            AppError::Database(e)
+        })?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+
+        Ok(variable)
+    }
+
+    /// Move a variable to the trash; with `expected_versions`, only while its version is
+    /// one of them
+    pub async fn trash(
+        &self,
+        id: Uuid,
+        environment_id: Uuid,
+        expected_versions: Option<&[i32]>,
+        deleted_by: Uuid,
+    ) -> Result<Option<Variable>> {
+        let variable = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            UPDATE variables SET is_active = false, deleted_at = NOW(), deleted_by = $4
+            WHERE id = $1 AND environment_id = $2 AND {LIVE}
+              AND ($3::INTEGER[] IS NULL OR version = ANY($3))
+            RETURNING *
+            "#,
+        ))
+        .bind(id)
+        .bind(environment_id)
+        .bind(expected_versions)
+        .bind(deleted_by)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(variable)
+    }
+
+    /// Move a locked variable to the trash
+    pub async fn trash_tx(conn: &mut PgConnection, id: Uuid, deleted_by: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE variables SET is_active = false, deleted_at = NOW(), deleted_by = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(deleted_by)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Variables in the trash of an environment, most recently deleted first
+    pub async fn list_trashed(&self, environment_id: Uuid) -> Result<Vec<TrashedVariable>> {
+        let variables = sqlx::query_as::<_, TrashedVariable>(&format!(
+            r#"
+            SELECT * FROM variables WHERE environment_id = $1 AND {TRASHED}
+            ORDER BY deleted_at DESC
+            "#,
+        ))
+        .bind(environment_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// Lock a variable in the trash of an environment
+    pub async fn lock_trashed_tx(
+        conn: &mut PgConnection,
+        id: Uuid,
+        environment_id: Uuid,
+    ) -> Result<Option<Variable>> {
+        let variable = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables WHERE id = $1 AND environment_id = $2 AND {TRASHED}
+            FOR UPDATE
+            "#,
+        ))
+        .bind(id)
+        .bind(environment_id)
+        .fetch_optional(conn)
+        .await?;
+
+        Ok(variable)
+    }
+
+    /// Lock every variable in the trash of an environment
+    pub async fn lock_trash_tx(
+        conn: &mut PgConnection,
+        environment_id: Uuid,
+    ) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(&format!(
+            r#"
+            SELECT * FROM variables WHERE environment_id = $1 AND {TRASHED}
+            FOR UPDATE
+            "#,
+        ))
+        .bind(environment_id)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// Lock up to `limit` trashed variables kept longer than their owner's tier retains
+    /// them, skipping those another transaction holds
+    pub async fn lock_purgeable_tx(conn: &mut PgConnection, limit: i64) -> Result<Vec<Variable>> {
+        let variables = sqlx::query_as::<_, Variable>(
+            r#"
+            SELECT v.* FROM variables v
+            LEFT JOIN users u ON u.id = v.user_id
+            LEFT JOIN organizations o ON o.id = v.organization_id
+            JOIN tiers t ON t.id = COALESCE(o.tier_id, u.tier_id)
+            WHERE NOT v.is_active AND t.trash_retention_days >= 0
+              AND v.deleted_at <= NOW() - make_interval(days => t.trash_retention_days)
+            ORDER BY v.deleted_at ASC
+            LIMIT $1
+            FOR UPDATE OF v SKIP LOCKED
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(variables)
+    }
+
+    /// Take a locked variable out of the trash. Fails with a conflict when a live variable
+    /// holds its key meanwhile.
+    pub async fn restore_tx(conn: &mut PgConnection, id: Uuid) -> Result<Variable> {
+        let variable = sqlx::query_as::<_, Variable>(
+            r#"
+            UPDATE variables SET is_active = true, deleted_at = NULL, deleted_by = NULL
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| {
+            if let sqlx::Error::Database(db_err) = &e
+                && db_err.is_unique_violation()
+            {
+                return AppError::Conflict("Another variable already uses this key".to_string());
+            }
+            AppError::Database(e)
+        })?;
+
+        Ok(variable)
+    }
 }
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::VariableShareLink;
+
+pub struct VariableShareLinkRepository {
+    pool: Pool<Postgres>,
+}
+
+impl VariableShareLinkRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        variable_id: Uuid,
+        created_by: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<VariableShareLink> {
+        let link = sqlx::query_as::<_, VariableShareLink>(
+            r#"
+            INSERT INTO variable_share_links (variable_id, created_by, expires_at)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(variable_id)
+        .bind(created_by)
+        .bind(expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(link)
+    }
+
+    pub async fn find_by_id(&self, id: Uuid) -> Result<Option<VariableShareLink>> {
+        let link = sqlx::query_as::<_, VariableShareLink>(
+            r#"
+            SELECT * FROM variable_share_links WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(link)
+    }
+
+    pub async fn list_by_variable(&self, variable_id: Uuid) -> Result<Vec<VariableShareLink>> {
+        let links = sqlx::query_as::<_, VariableShareLink>(
+            r#"
+            SELECT * FROM variable_share_links
+            WHERE variable_id = $1
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(variable_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(links)
+    }
+
+    pub async fn record_use(&self, id: Uuid) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE variable_share_links
+            SET use_count = use_count + 1, last_used_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: Uuid, variable_id: Uuid) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM variable_share_links WHERE id = $1 AND variable_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(variable_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Share link not found".to_string()));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{SharePermission, VariableShare};
+
+pub struct VariableShareRepository {
+    pool: Pool<Postgres>,
+}
+
+impl VariableShareRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Share a variable with a user, replacing the permission of an existing grant
+    pub async fn upsert(
+        &self,
+        variable_id: Uuid,
+        user_id: Uuid,
+        permission: SharePermission,
+        granted_by: Uuid,
+    ) -> Result<VariableShare> {
+        let share = sqlx::query_as::<_, VariableShare>(
+            r#"
+            WITH share AS (
+                INSERT INTO variable_shares (variable_id, user_id, permission, granted_by)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (variable_id, user_id)
+                DO UPDATE SET permission = EXCLUDED.permission, granted_by = EXCLUDED.granted_by
+                RETURNING *
+            )
+            SELECT share.*, u.email FROM share JOIN users u ON u.id = share.user_id
+            "#,
+        )
+        .bind(variable_id)
+        .bind(user_id)
+        .bind(permission)
+        .bind(granted_by)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(share)
+    }
+
+    pub async fn list_by_variable(&self, variable_id: Uuid) -> Result<Vec<VariableShare>> {
+        let shares = sqlx::query_as::<_, VariableShare>(
+            r#"
+            SELECT s.*, u.email FROM variable_shares s
+            JOIN users u ON u.id = s.user_id
+            WHERE s.variable_id = $1
+            ORDER BY s.created_at ASC
+            "#,
+        )
+        .bind(variable_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(shares)
+    }
+
+    pub async fn delete(&self, id: Uuid, variable_id: Uuid) -> Result<()> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM variable_shares WHERE id = $1 AND variable_id = $2
+            "#,
+        )
+        .bind(id)
+        .bind(variable_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Share not found".to_string()));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::{Variable, VariableVersion};
+
+pub struct VariableVersionRepository {
+    pool: Pool<Postgres>,
+}
+
+impl VariableVersionRepository {
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+
+    /// Record `variable` as it now stands, with its data snapshot at `storage_path`
+    pub async fn create_tx(
+        conn: &mut PgConnection,
+        variable: &Variable,
+        storage_path: &str,
+        author_id: Option<Uuid>,
+        message: Option<&str>,
+    ) -> Result<VariableVersion> {
+        let version = sqlx::query_as::<_, VariableVersion>(
+            r#"
+            INSERT INTO variable_versions (variable_id, version, storage_path, size_bytes,
+                                         description, tags, is_encrypted, author_id, message)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING *
+            "#,
+        )
+        .bind(variable.id)
+        .bind(variable.version)
+        .bind(storage_path)
+        .bind(variable.size_bytes)
+        .bind(variable.description.as_deref())
+        .bind(variable.tags.clone())
+        .bind(variable.is_encrypted)
+        .bind(author_id)
+        .bind(message)
+        .fetch_one(conn)
+        .await?;
+
+        Ok(version)
+    }
+
+    pub async fn exists_tx(
+        conn: &mut PgConnection,
+        variable_id: Uuid,
+        version: i32,
+    ) -> Result<bool> {
+        let exists: (bool,) = sqlx::query_as(
+            r#"
+            SELECT EXISTS(SELECT 1 FROM variable_versions WHERE variable_id = $1 AND version = $2)
+            "#,
+        )
+        .bind(variable_id)
+        .bind(version)
+        .fetch_one(conn)
+        .await?;
+
+        Ok(exists.0)
+    }
+
+    /// Newest first
+    pub async fn list(&self, variable_id: Uuid) -> Result<Vec<VariableVersion>> {
+        let versions = sqlx::query_as::<_, VariableVersion>(
+            r#"
+            SELECT * FROM variable_versions
+            WHERE variable_id = $1
+            ORDER BY version DESC
+            "#,
+        )
+        .bind(variable_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(versions)
+    }
+
+    pub async fn find(&self, variable_id: Uuid, version: i32) -> Result<Option<VariableVersion>> {
+        let version = sqlx::query_as::<_, VariableVersion>(
+            r#"
+            SELECT * FROM variable_versions WHERE variable_id = $1 AND version = $2
+            "#,
+        )
+        .bind(variable_id)
+        .bind(version)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(version)
+    }
+
+    /// Storage paths of every version of a variable, used to clean up files on removal
+    pub async fn list_storage_paths(&self, variable_id: Uuid) -> Result<Vec<String>> {
+        let mut conn = self.pool.acquire().await?;
+
+        Self::list_storage_paths_tx(&mut conn, variable_id).await
+    }
+
+    pub async fn list_storage_paths_tx(
+        conn: &mut PgConnection,
+        variable_id: Uuid,
+    ) -> Result<Vec<String>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            "SELECT storage_path FROM variable_versions WHERE variable_id = $1"
+        )
+        .bind(variable_id)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.0).collect())
+    }
+
+    /// Delete all but the newest `keep` versions and return the storage paths of the
+    /// deleted ones
+    pub async fn prune_tx(
+        conn: &mut PgConnection,
+        variable_id: Uuid,
+        keep: i64,
+    ) -> Result<Vec<String>> {
+        let rows: Vec<(String,)> = sqlx::query_as(
+            r#"
+            DELETE FROM variable_versions
+            WHERE variable_id = $1
+              AND version NOT IN (
+                  SELECT version FROM variable_versions
+                  WHERE variable_id = $1
+                  ORDER BY version DESC
+                  LIMIT $2
+              )
+            RETURNING storage_path
+            "#,
+        )
+        .bind(variable_id)
+        .bind(keep)
+        .fetch_all(conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.0).collect())
+    }
+}
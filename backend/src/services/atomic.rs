@@ -0,0 +1,184 @@
+use serde_json::{Number, Value};
+
+use crate::dto::{AtomicOperation, AtomicRequest};
+use crate::error::{AppError, Result};
+use crate::services::patching::DataPatch;
+
+fn incremented(path: &str, current: Option<&Value>, by: &Number) -> Result<Value> {
+    let current = match current {
+        None => return Ok(Value::Number(by.clone())),
+        Some(Value::Number(current)) => current,
+        Some(_) => return Err(AppError::BadRequest(format!("Value at {} is not a number", path))),
+    };
+
+    if let (Some(current), Some(by)) = (current.as_i64(), by.as_i64()) {
+        return current
+            .checked_add(by)
+            .map(Value::from)
+            .ok_or_else(|| AppError::BadRequest(format!("Value at {} would overflow", path)));
+    }
+
+    let sum = current.as_f64().unwrap_or_default() + by.as_f64().unwrap_or_default();
+    Number::from_f64(sum)
+        .map(Value::Number)
+        .ok_or_else(|| AppError::BadRequest(format!("Value at {} would overflow", path)))
+}
+
+fn array_at<'a>(path: &str, current: Option<&'a Value>) -> Result<Option<&'a Vec<Value>>> {
+    match current {
+        None => Ok(None),
+        Some(Value::Array(items)) => Ok(Some(items)),
+        Some(_) => Err(AppError::BadRequest(format!("Value at {} is not an array", path))),
+    }
+}
+
+/// Apply an atomic operation to `data` of a variable at `version`. Returns the new data,
+/// or `None` when the operation leaves it as it is. A failed comparison is a conflict.
+pub fn apply_atomic(request: &AtomicRequest, version: i32, data: &Value) -> Result<Option<Value>> {
+    let path = request.path.as_str();
+    let current = data.pointer(path);
+
+    let value = match &request.operation {
+        AtomicOperation::Increment { by } => incremented(path, current, by)?,
+        AtomicOperation::Append { value } => {
+            let mut items = array_at(path, current)?.cloned().unwrap_or_default();
+            items.push(value.clone());
+            Value::Array(items)
+        }
+        AtomicOperation::Remove { value } => {
+            let Some(items) = array_at(path, current)? else {
+                return Ok(None);
+            };
+            let kept: Vec<Value> = items.iter().filter(|item| *item != value).cloned().collect();
+            if kept.len() == items.len() {
+                return Ok(None);
+            }
+            Value::Array(kept)
+        }
+        AtomicOperation::SetIfAbsent { value } => {
+            if current.is_some() {
+                return Ok(None);
+            }
+            value.clone()
+        }
+        AtomicOperation::CompareAndSwap { expected, expected_version, value } => {
+            if let Some(expected_version) = expected_version
+                && *expected_version != version
+            {
+                return Err(AppError::Conflict(format!(
+                    "Variable is at version {}, not {}",
+                    version, expected_version
+                )));
+            }
+            if let Some(expected) = expected {
+                let expected = expected.as_ref().unwrap_or(&Value::Null);
+                if current != Some(expected) {
+                    return Err(AppError::Conflict(format!(
+                        "Value at {} does not match the expected value",
+                        path
+                    )));
+                }
+            }
+            value.clone()
+        }
+    };
+
+    DataPatch::put(data, path, value)?.apply(data).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(body: Value) -> AtomicRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_increment() {
+        let data = json!({"visits": 41, "ratio": 0.5});
+
+        let visits = request(json!({"op": "increment", "path": "/visits"}));
+        assert_eq!(
+            apply_atomic(&visits, 1, &data).unwrap(),
+            Some(json!({"visits": 42, "ratio": 0.5}))
+        );
+
+        let ratio = request(json!({"op": "increment", "path": "/ratio", "by": -0.25}));
+        assert_eq!(
+            apply_atomic(&ratio, 1, &data).unwrap(),
+            Some(json!({"visits": 41, "ratio": 0.25}))
+        );
+
+        // A missing counter starts at 0
+        let missing = request(json!({"op": "increment", "path": "/errors", "by": 3}));
+        assert_eq!(apply_atomic(&missing, 1, &data).unwrap().unwrap()["errors"], json!(3));
+
+        let overflow = request(json!({"op": "increment", "path": "/visits", "by": i64::MAX}));
+        assert!(matches!(apply_atomic(&overflow, 1, &data), Err(AppError::BadRequest(_))));
+
+        // An empty path addresses the whole data
+        let root = request(json!({"op": "increment"}));
+        assert!(matches!(apply_atomic(&root, 1, &data), Err(AppError::BadRequest(_))));
+        assert_eq!(apply_atomic(&root, 1, &json!(5)).unwrap(), Some(json!(6)));
+    }
+
+    #[test]
+    fn test_append_and_remove() {
+        let data = json!({"hosts": ["a", "b", "a"]});
+
+        let append = request(json!({"op": "append", "path": "/hosts", "value": "c"}));
+        assert_eq!(
+            apply_atomic(&append, 1, &data).unwrap(),
+            Some(json!({"hosts": ["a", "b", "a", "c"]}))
+        );
+
+        let create = request(json!({"op": "append", "path": "/ports", "value": 80}));
+        assert_eq!(apply_atomic(&create, 1, &data).unwrap().unwrap()["ports"], json!([80]));
+
+        let remove = request(json!({"op": "remove", "path": "/hosts", "value": "a"}));
+        assert_eq!(apply_atomic(&remove, 1, &data).unwrap(), Some(json!({"hosts": ["b"]})));
+
+        let absent = request(json!({"op": "remove", "path": "/hosts", "value": "z"}));
+        assert_eq!(apply_atomic(&absent, 1, &data).unwrap(), None);
+
+        let scalar = request(json!({"op": "append", "path": "/hosts/0", "value": "x"}));
+        assert!(matches!(apply_atomic(&scalar, 1, &data), Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_set_if_absent() {
+        let data = json!({"owner": "ops"});
+
+        let present = request(json!({"op": "set_if_absent", "path": "/owner", "value": "dev"}));
+        assert_eq!(apply_atomic(&present, 1, &data).unwrap(), None);
+
+        let absent = request(json!({"op": "set_if_absent", "path": "/lock", "value": "dev"}));
+        assert_eq!(
+            apply_atomic(&absent, 1, &data).unwrap(),
+            Some(json!({"owner": "ops", "lock": "dev"}))
+        );
+    }
+
+    #[test]
+    fn test_compare_and_swap() {
+        let data = json!({"leader": null, "term": 3});
+
+        let swap = request(json!({
+            "op": "compare_and_swap", "path": "/leader", "expected": null, "value": "node-1"
+        }));
+        assert_eq!(apply_atomic(&swap, 7, &data).unwrap().unwrap()["leader"], json!("node-1"));
+
+        let stale = request(json!({
+            "op": "compare_and_swap", "path": "/term", "expected": 2, "value": 4
+        }));
+        assert!(matches!(apply_atomic(&stale, 7, &data), Err(AppError::Conflict(_))));
+
+        let versioned = request(json!({
+            "op": "compare_and_swap", "path": "/term", "expected_version": 7, "value": 4
+        }));
+        assert_eq!(apply_atomic(&versioned, 7, &data).unwrap().unwrap()["term"], json!(4));
+        assert!(matches!(apply_atomic(&versioned, 8, &data), Err(AppError::Conflict(_))));
+    }
+}
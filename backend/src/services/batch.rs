@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::dto::{BatchOperation, BatchWriteOutcome, BatchWriteRequest, BatchWriteResult};
+use crate::error::{AppError, Result};
+use crate::models::{Environment, Owner, Tier, Variable};
+use crate::policy::Action;
+use crate::repositories::VariableRepository;
+use crate::services::compensation::{revert, Undo};
+use crate::services::expiry::purge_expired_key_tx;
+use crate::services::schemas::enforce_schema;
+use crate::services::trash::remove_purged;
+use crate::services::versioning::{preserve_current, prune_versions, record_version};
+use crate::storage::VariableStore;
+use crate::utils::json_validator::calculate_json_size;
+use crate::utils::validate_json_data;
+
+/// Check every operation against policy, the environment's current variables and the
+/// tier's size limit before anything is written. Returns the number of variables the
+/// batch creates and deletes.
+fn check_operations(
+    request: &BatchWriteRequest,
+    existing: &BTreeMap<String, Variable>,
+    tier: &Tier,
+    authorize: &impl Fn(Action, &str, Vec<String>) -> Result<()>,
+) -> Result<(i32, i32)> {
+    let (mut created, mut deleted) = (0, 0);
+
+    for operation in &request.operations {
+        let key = operation.key();
+        match (operation, existing.get(key)) {
+            (BatchOperation::Upsert { data, tags, .. }, current) => {
+                match current {
+                    Some(variable) => {
+                        authorize(Action::VariableWrite, key, variable.tag_list())?;
+                        if let Some(tags) = tags {
+                            authorize(Action::VariableWrite, key, tags.clone())?;
+                        }
+                    }
+                    None => {
+                        authorize(Action::VariableWrite, key, tags.clone().unwrap_or_default())?;
+                        created += 1;
+                    }
+                }
+                validate_json_data(data, tier.max_variable_size_mb)?;
+            }
+            (BatchOperation::Delete { .. }, Some(variable)) => {
+                authorize(Action::VariableDelete, key, variable.tag_list())?;
+                deleted += 1;
+            }
+            (BatchOperation::Delete { .. }, None) => {
+                return Err(AppError::NotFound(format!("Variable {} not found", key)));
+            }
+        }
+    }
+
+    Ok((created, deleted))
+}
+
+/// Write every operation on `conn`, recording storage writes in `undo`. Returns the
+/// results in request order; deleted variables move to the trash.
+#[allow(clippy::too_many_arguments)]
+async fn write_operations(
+    conn: &mut PgConnection,
+    storage: &impl VariableStore,
+    owner: Owner,
+    environment: &Environment,
+    author_id: Uuid,
+    request: &BatchWriteRequest,
+    existing: &BTreeMap<String, Variable>,
+    undo: &mut Vec<Undo>,
+) -> Result<Vec<BatchWriteResult>> {
+    let mut results = Vec::with_capacity(request.operations.len());
+    let message = request.message.as_deref();
+
+    for operation in &request.operations {
+        let key = operation.key();
+        match operation {
+            BatchOperation::Upsert { description, data, tags, is_encrypted, .. } => {
+                let size_bytes = calculate_json_size(data) as i64;
+                let tags_json = tags.as_ref().map(|tags| serde_json::json!(tags));
+
+                let (variable, outcome) = match existing.get(key) {
+                    Some(current) => {
+                        let current_data = storage.retrieve(&current.storage_path).await?;
+                        if let Some(version) =
+                            preserve_current(conn, storage, current, &current_data).await?
+                        {
+                            undo.push(Undo::Delete(version.storage_path));
+                        }
+
+                        undo.push(Undo::Restore(current.storage_path.clone(), current_data));
+                        storage.update(&current.storage_path, data).await?;
+
+                        let updated = VariableRepository::update_tx(
+                            conn,
+                            current.id,
+                            environment.id,
+                            description.as_deref(),
+                            Some(size_bytes),
+                            tags_json,
+                            None,
+                        )
+                        .await?
+                        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+                        (updated, BatchWriteOutcome::Updated)
+                    }
+                    None => {
+                        let storage_path = storage
+                            .store(owner, environment.id, key, data, *is_encrypted)
+                            .await?;
+                        undo.push(Undo::Delete(storage_path.clone()));
+
+                        let created = VariableRepository::create_tx(
+                            conn,
+                            owner,
+                            environment.id,
+                            author_id,
+                            key,
+                            description.as_deref(),
+                            size_bytes,
+                            &storage_path,
+                            *is_encrypted,
+                            tags_json,
+                        )
+                        .await?;
+                        (created, BatchWriteOutcome::Created)
+                    }
+                };
+
+                let version =
+                    record_version(conn, storage, &variable, data, Some(author_id), message)
+                        .await?;
+                undo.push(Undo::Delete(version.storage_path));
+
+                results.push(BatchWriteResult {
+                    key: key.to_string(),
+                    outcome,
+                    variable: Some(variable),
+                });
+            }
+            BatchOperation::Delete { .. } => {
+                VariableRepository::trash_tx(conn, existing[key].id, author_id).await?;
+
+                results.push(BatchWriteResult {
+                    key: key.to_string(),
+                    outcome: BatchWriteOutcome::Deleted,
+                    variable: None,
+                });
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Apply a batch of upserts and deletes to an environment in one transaction. Storage
+/// writes are reverted if any operation fails, so a batch applies entirely or not at all.
+/// Upserts of existing variables must conform to their schemas.
+/// Fails if `authorize` rejects an operation on a key with the given tags.
+#[allow(clippy::too_many_arguments)]
+pub async fn apply_batch_write(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    owner: Owner,
+    author_id: Uuid,
+    tier: &Tier,
+    environment: &Environment,
+    request: &BatchWriteRequest,
+    authorize: impl Fn(Action, &str, Vec<String>) -> Result<()>,
+) -> Result<Vec<BatchWriteResult>> {
+    let mut tx = pool.begin().await?;
+
+    let existing: BTreeMap<String, Variable> =
+        VariableRepository::lock_environment_tx(&mut tx, environment.id)
+            .await?
+            .into_iter()
+            .map(|v| (v.key.clone(), v))
+            .collect();
+
+    let (created, deleted) = check_operations(request, &existing, tier, &authorize)?;
+    for operation in &request.operations {
+        if let (BatchOperation::Upsert { data, .. }, Some(variable)) =
+            (operation, existing.get(operation.key()))
+        {
+            enforce_schema(pool, variable, data).await?;
+        }
+    }
+    if created > deleted {
+        let current = VariableRepository::new(pool.clone()).count_by_owner(owner).await?;
+        if current + created - deleted > tier.max_variables {
+            return Err(AppError::TierLimitExceeded(format!(
+                "Maximum {} variables allowed",
+                tier.max_variables
+            )));
+        }
+    }
+
+    // Expired variables give up their keys to the upserts
+    let mut purged = Vec::new();
+    for operation in &request.operations {
+        if let BatchOperation::Upsert { key, .. } = operation
+            && !existing.contains_key(key)
+        {
+            purged.extend(purge_expired_key_tx(&mut tx, environment.id, key).await?);
+        }
+    }
+
+    let mut undo = Vec::new();
+    let written = write_operations(
+        &mut tx,
+        storage,
+        owner,
+        environment,
+        author_id,
+        request,
+        &existing,
+        &mut undo,
+    )
+    .await;
+
+    let results = match written {
+        Ok(written) => written,
+        Err(e) => {
+            drop(tx);
+            revert(storage, undo).await;
+            return Err(e);
+        }
+    };
+
+    if let Err(e) = tx.commit().await {
+        revert(storage, undo).await;
+        return Err(e.into());
+    }
+    remove_purged(storage, purged).await;
+
+    for result in &results {
+        if let Some(variable) = &result.variable {
+            prune_versions(pool, storage, variable.id, tier).await;
+        }
+    }
+
+    Ok(results)
+}
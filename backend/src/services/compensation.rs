@@ -0,0 +1,22 @@
+use serde_json::Value;
+
+use crate::storage::VariableStore;
+
+/// A storage write to revert if the transaction it belongs to fails before committing
+pub enum Undo {
+    Delete(String),
+    Restore(String, Value),
+}
+
+/// Revert storage writes, most recent first
+pub async fn revert(storage: &impl VariableStore, undo: Vec<Undo>) {
+    for step in undo.into_iter().rev() {
+        let result = match &step {
+            Undo::Delete(path) => storage.delete(path).await,
+            Undo::Restore(path, data) => storage.update(path, data).await,
+        };
+        if let Err(e) = result {
+            tracing::error!("Failed to revert storage write: {}", e);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::repositories::VariableRepository;
+use crate::services::trash::{purge_tx, remove_purged};
+use crate::storage::VariableStore;
+
+/// Expired variables deleted per sweeper transaction
+const SWEEP_BATCH_SIZE: i64 = 100;
+
+/// Seconds between sweeps, from `EXPIRY_SWEEP_INTERVAL_SECONDS` (default 60)
+fn sweep_interval() -> Duration {
+    let seconds = std::env::var("EXPIRY_SWEEP_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&s: &u64| s > 0)
+        .unwrap_or(60);
+
+    Duration::from_secs(seconds)
+}
+
+/// Delete the expired variable holding `key` in an environment, which keeps its key until
+/// swept. Returns the storage paths to remove once the transaction commits.
+pub async fn purge_expired_key_tx(
+    conn: &mut PgConnection,
+    environment_id: Uuid,
+    key: &str,
+) -> Result<Vec<String>> {
+    match VariableRepository::lock_expired_key_tx(conn, environment_id, key).await? {
+        Some(expired) => purge_tx(conn, &[expired]).await,
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Delete every expired variable and return how many there were. Servers sweeping at
+/// the same time skip each other's variables.
+pub async fn sweep_expired(pool: &Pool<Postgres>, storage: &impl VariableStore) -> Result<usize> {
+    let mut swept = 0;
+
+    loop {
+        let mut tx = pool.begin().await?;
+        let expired = VariableRepository::lock_expired_tx(&mut tx, SWEEP_BATCH_SIZE).await?;
+        let paths = purge_tx(&mut tx, &expired).await?;
+        tx.commit().await?;
+        remove_purged(storage, paths).await;
+
+        swept += expired.len();
+        if (expired.len() as i64) < SWEEP_BATCH_SIZE {
+            return Ok(swept);
+        }
+    }
+}
+
+/// Periodically delete expired variables in the background
+pub fn spawn_expiry_sweeper<S: VariableStore + Clone + 'static>(pool: Pool<Postgres>, storage: S) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(sweep_interval());
+
+        loop {
+            interval.tick().await;
+            match sweep_expired(&pool, &storage).await {
+                Ok(0) => {}
+                Ok(swept) => tracing::info!("Deleted {} expired variables", swept),
+                Err(e) => tracing::warn!("Failed to delete expired variables: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{KeyRotation, Variable};
+use crate::repositories::{
+    KeyRotationRepository, ScheduledChangeRepository, VariableRepository,
+    VariableVersionRepository,
+};
+use crate::storage::VariableStore;
+
+/// Encrypted variables processed between progress updates
+const BATCH_SIZE: i64 = 100;
+
+/// Encrypted variables decrypted to verify a rotation once every data key is re-wrapped
+const VERIFY_SAMPLE_SIZE: i64 = 20;
+
+/// How often each server looks for a rotation left without progress
+const WATCHDOG_INTERVAL: Duration = Duration::from_secs(60);
+
+fn current_key_version(storage: &impl VariableStore) -> Result<i32> {
+    storage
+        .key_version()
+        .map(|version| version as i32)
+        .ok_or_else(|| AppError::BadRequest("Server-side encryption is not configured".to_string()))
+}
+
+/// Start re-wrapping every data key under the current master key, in the background.
+/// Every server must know the new master key first, or it can't read re-wrapped keys.
+pub async fn start_rotation<S: VariableStore + Clone + 'static>(
+    pool: &Pool<Postgres>,
+    storage: &S,
+    started_by: Uuid,
+) -> Result<KeyRotation> {
+    let key_version = current_key_version(storage)?;
+    let total = VariableRepository::new(pool.clone()).count_encrypted().await?;
+
+    let rotation = KeyRotationRepository::new(pool.clone())
+        .create(key_version, total, started_by)
+        .await?;
+    spawn_rotation(pool.clone(), storage.clone(), rotation.id);
+
+    Ok(rotation)
+}
+
+/// Continue a failed rotation after the last variable it processed
+pub async fn resume_rotation<S: VariableStore + Clone + 'static>(
+    pool: &Pool<Postgres>,
+    storage: &S,
+    id: Uuid,
+) -> Result<KeyRotation> {
+    let key_version = current_key_version(storage)?;
+    let rotations = KeyRotationRepository::new(pool.clone());
+
+    let Some(rotation) = rotations.resume(id, key_version).await? else {
+        return Err(match rotations.find_by_id(id).await? {
+            Some(_) => AppError::Conflict("Only failed key rotations can be resumed".to_string()),
+            None => AppError::NotFound("Key rotation not found".to_string()),
+        });
+    };
+    spawn_rotation(pool.clone(), storage.clone(), rotation.id);
+
+    Ok(rotation)
+}
+
+/// Periodically resume a rotation whose server stopped making progress, whether this
+/// server after a restart or another one
+pub fn spawn_rotation_watchdog<S: VariableStore + Clone + 'static>(pool: Pool<Postgres>, storage: S) {
+    let Ok(key_version) = current_key_version(&storage) else {
+        return;
+    };
+
+    tokio::spawn(async move {
+        let rotations = KeyRotationRepository::new(pool.clone());
+        let mut interval = tokio::time::interval(WATCHDOG_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            match rotations.claim_stale(key_version).await {
+                Ok(Some(rotation)) => {
+                    tracing::info!("Resuming key rotation {}", rotation.id);
+                    finish_rotation(&pool, &storage, rotation.id).await;
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to look for interrupted key rotations: {}", e),
+            }
+        }
+    });
+}
+
+fn spawn_rotation<S: VariableStore + Clone + 'static>(pool: Pool<Postgres>, storage: S, id: Uuid) {
+    tokio::spawn(async move { finish_rotation(&pool, &storage, id).await });
+}
+
+/// Run a rotation to the end, recording why it stopped if it fails
+async fn finish_rotation(pool: &Pool<Postgres>, storage: &impl VariableStore, id: Uuid) {
+    match run_rotation(pool, storage, id).await {
+        Ok(rotation) => tracing::info!(
+            "Key rotation {} completed: {} data keys re-wrapped, {} variables encrypted",
+            rotation.id,
+            rotation.rewrapped_keys,
+            rotation.encrypted_variables
+        ),
+        Err(e) => {
+            tracing::error!("Key rotation {} failed: {}", id, e);
+            if let Err(e) = KeyRotationRepository::new(pool.clone()).fail(id, &e.to_string()).await {
+                tracing::warn!("Failed to record failure of key rotation {}: {}", id, e);
+            }
+        }
+    }
+}
+
+async fn run_rotation(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    id: Uuid,
+) -> Result<KeyRotation> {
+    let rotations = KeyRotationRepository::new(pool.clone());
+    let variables = VariableRepository::new(pool.clone());
+
+    let rotation = rotations
+        .find_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Key rotation not found".to_string()))?;
+    let mut after = rotation.last_variable_id;
+
+    loop {
+        let batch = variables.list_encrypted_after(after, BATCH_SIZE).await?;
+        if batch.is_empty() {
+            break;
+        }
+
+        let (mut processed, mut rewrapped, mut encrypted) = (0, 0, 0);
+        for variable in &batch {
+            match rotate_variable(pool, storage, variable).await {
+                Ok((was_plain, was_rewrapped)) => {
+                    processed += 1;
+                    encrypted += was_plain as i64;
+                    rewrapped += was_rewrapped as i64;
+                    after = Some(variable.id);
+                }
+                Err(e) => {
+                    // Keep the progress made, so resuming starts at the failed variable
+                    if let Some(last) = after.filter(|_| processed > 0) {
+                        rotations.record_progress(id, last, processed, rewrapped, encrypted).await?;
+                    }
+                    return Err(AppError::InternalServer(format!(
+                        "Variable {}: {}",
+                        variable.id, e
+                    )));
+                }
+            }
+        }
+
+        if let Some(last) = after {
+            rotations.record_progress(id, last, processed, rewrapped, encrypted).await?;
+        }
+    }
+
+    let verified = verify_sample(pool, storage, rotation.key_version as u32).await?;
+    rotations.complete(id, verified).await
+}
+
+/// Encrypt the variable's data if it is still stored in plain, then re-wrap its data key.
+/// Returns whether each was needed.
+async fn rotate_variable(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    variable: &Variable,
+) -> Result<(bool, bool)> {
+    // Writers hold the row lock while they write data, so re-encrypting can't lose a write
+    let mut tx = pool.begin().await?;
+    let Some(variable) = VariableRepository::lock_tx(&mut tx, variable.id).await? else {
+        return Ok((false, false));
+    };
+    let mut version_paths =
+        VariableVersionRepository::list_storage_paths_tx(&mut tx, variable.id).await?;
+    version_paths
+        .extend(ScheduledChangeRepository::list_pending_paths_tx(&mut tx, variable.id).await?);
+
+    let encrypted = storage.encrypt_existing(&variable.storage_path, &version_paths).await?;
+    let rewrapped = storage.rewrap_data_key(&variable.storage_path).await?;
+    tx.commit().await?;
+
+    Ok((encrypted, rewrapped))
+}
+
+/// Decrypt a random sample of encrypted variables, checking their data keys are wrapped by
+/// master key `key_version` or newer. Returns how many were verified.
+async fn verify_sample(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    key_version: u32,
+) -> Result<i32> {
+    let sample = VariableRepository::new(pool.clone())
+        .sample_encrypted(VERIFY_SAMPLE_SIZE)
+        .await?;
+
+    for variable in &sample {
+        let version = storage.data_key_version(&variable.storage_path).await?;
+        if version.is_none_or(|version| version < key_version) {
+            return Err(AppError::InternalServer(format!(
+                "Verification failed: data key of variable {} is not wrapped by master key v{}",
+                variable.id, key_version
+            )));
+        }
+        storage.retrieve(&variable.storage_path).await?;
+    }
+
+    Ok(sample.len() as i32)
+}
@@ -0,0 +1,33 @@
+pub mod atomic;
+pub mod batch;
+pub mod compensation;
+pub mod expiry;
+pub mod key_rotation;
+pub mod patching;
+pub mod promotion;
+pub mod querying;
+pub mod references;
+pub mod resolution;
+pub mod scheduling;
+pub mod schemas;
+pub mod search;
+pub mod tagging;
+pub mod trash;
+pub mod versioning;
+
+pub use atomic::*;
+pub use batch::*;
+pub use compensation::*;
+pub use expiry::*;
+pub use key_rotation::*;
+pub use patching::*;
+pub use promotion::*;
+pub use querying::*;
+pub use references::*;
+pub use resolution::*;
+pub use scheduling::*;
+pub use schemas::*;
+pub use search::*;
+pub use tagging::*;
+pub use trash::*;
+pub use versioning::*;
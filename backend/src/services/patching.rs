@@ -0,0 +1,170 @@
+use json_patch::{Patch, PatchErrorKind};
+use serde_json::{json, Value};
+
+use crate::error::{AppError, Result};
+
+/// Media type of RFC 7396 JSON Merge Patch documents
+pub const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+
+/// Media type of RFC 6902 JSON Patch documents
+pub const JSON_PATCH_CONTENT_TYPE: &str = "application/json-patch+json";
+
+/// A partial change to a variable's data
+#[derive(Debug, Clone)]
+pub enum DataPatch {
+    Merge(Value),
+    Json(Patch),
+}
+
+impl DataPatch {
+    /// Parse a request body in the patch format its media type names
+    pub fn parse(content_type: &str, body: &[u8]) -> Result<Self> {
+        let media_type = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        match media_type.as_str() {
+            MERGE_PATCH_CONTENT_TYPE => Ok(Self::Merge(serde_json::from_slice(body)?)),
+            JSON_PATCH_CONTENT_TYPE => serde_json::from_slice(body)
+                .map(Self::Json)
+                .map_err(|e| AppError::BadRequest(format!("Invalid JSON Patch: {}", e))),
+            _ => Err(AppError::UnsupportedMediaType(format!(
+                "Expected {} or {}",
+                MERGE_PATCH_CONTENT_TYPE, JSON_PATCH_CONTENT_TYPE
+            ))),
+        }
+    }
+
+    /// Put `value` at `pointer` in `data`: replace what is there, or add it to its parent
+    pub fn put(data: &Value, pointer: &str, value: Value) -> Result<Self> {
+        let op = if data.pointer(pointer).is_some() { "replace" } else { "add" };
+
+        Self::operation(json!({ "op": op, "path": pointer, "value": value }))
+    }
+
+    /// Remove the value at `pointer`
+    pub fn remove(pointer: &str) -> Result<Self> {
+        Self::operation(json!({ "op": "remove", "path": pointer }))
+    }
+
+    fn operation(operation: Value) -> Result<Self> {
+        serde_json::from_value(Value::Array(vec![operation]))
+            .map(Self::Json)
+            .map_err(|e| AppError::BadRequest(format!("Invalid JSON Pointer: {}", e)))
+    }
+
+    /// Apply to `data` and return the result. A JSON Patch applies all its
+    /// operations or none; a failed `test` operation is a conflict.
+    pub fn apply(&self, data: &Value) -> Result<Value> {
+        let mut patched = data.clone();
+
+        match self {
+            Self::Merge(patch) => json_patch::merge(&mut patched, patch),
+            Self::Json(patch) => json_patch::patch(&mut patched, patch).map_err(|e| {
+                match e.kind {
+                    PatchErrorKind::TestFailed => AppError::Conflict(e.to_string()),
+                    _ => AppError::BadRequest(e.to_string()),
+                }
+            })?,
+        }
+
+        Ok(patched)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_patch() {
+        let patch = DataPatch::parse(
+            "application/merge-patch+json; charset=utf-8",
+            br#"{"db": {"port": 5433, "user": null}}"#,
+        )
+        .unwrap();
+
+        let data = json!({"db": {"host": "db", "port": 5432, "user": "app"}});
+        assert_eq!(
+            patch.apply(&data).unwrap(),
+            json!({"db": {"host": "db", "port": 5433}})
+        );
+    }
+
+    #[test]
+    fn test_json_patch_is_atomic() {
+        let data = json!({"replicas": 2, "tags": ["a"]});
+
+        let patch = DataPatch::parse(
+            JSON_PATCH_CONTENT_TYPE,
+            br#"[
+                {"op": "test", "path": "/replicas", "value": 2},
+                {"op": "replace", "path": "/replicas", "value": 3},
+                {"op": "add", "path": "/tags/-", "value": "b"}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            patch.apply(&data).unwrap(),
+            json!({"replicas": 3, "tags": ["a", "b"]})
+        );
+
+        let stale = DataPatch::parse(
+            JSON_PATCH_CONTENT_TYPE,
+            br#"[
+                {"op": "replace", "path": "/replicas", "value": 3},
+                {"op": "test", "path": "/replicas", "value": 1}
+            ]"#,
+        )
+        .unwrap();
+        assert!(matches!(stale.apply(&data), Err(AppError::Conflict(_))));
+
+        let invalid = DataPatch::parse(
+            JSON_PATCH_CONTENT_TYPE,
+            br#"[{"op": "remove", "path": "/missing"}]"#,
+        )
+        .unwrap();
+        assert!(matches!(invalid.apply(&data), Err(AppError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_parse_rejects_other_media_types() {
+        assert!(matches!(
+            DataPatch::parse("application/json", b"{}"),
+            Err(AppError::UnsupportedMediaType(_))
+        ));
+        assert!(matches!(
+            DataPatch::parse(JSON_PATCH_CONTENT_TYPE, br#"{"op": "add"}"#),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_pointer_put_and_remove() {
+        let data = json!({"db": {"hosts": ["a", "b"]}});
+
+        let replaced = DataPatch::put(&data, "/db/hosts/0", json!("c"))
+            .unwrap()
+            .apply(&data)
+            .unwrap();
+        assert_eq!(replaced, json!({"db": {"hosts": ["c", "b"]}}));
+
+        let added = DataPatch::put(&data, "/db/port", json!(5432))
+            .unwrap()
+            .apply(&data)
+            .unwrap();
+        assert_eq!(added, json!({"db": {"hosts": ["a", "b"], "port": 5432}}));
+
+        let removed = DataPatch::remove("/db/hosts/1").unwrap().apply(&data).unwrap();
+        assert_eq!(removed, json!({"db": {"hosts": ["a"]}}));
+
+        // The parent must exist
+        assert!(DataPatch::put(&data, "/cache/ttl", json!(60))
+            .unwrap()
+            .apply(&data)
+            .is_err());
+    }
+}
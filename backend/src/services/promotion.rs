@@ -0,0 +1,617 @@
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::dto::{PromotionChange, PromotionPlan, PromotionPlanRequest};
+use crate::error::{AppError, Result};
+use crate::models::{
+    Environment, EnvironmentPromotion, Owner, Project, PromotionAction, Tier, Variable,
+};
+use crate::policy::Action;
+use crate::repositories::{
+    EnvironmentPromotionRepository, ScheduledChangeRepository, VariableRepository,
+    VariableVersionRepository,
+};
+use crate::services::compensation::{revert, Undo};
+use crate::services::expiry::purge_expired_key_tx;
+use crate::services::trash::remove_purged;
+use crate::services::versioning::{preserve_current, record_version};
+use crate::storage::VariableStore;
+
+/// A variable together with its stored value
+#[derive(Debug, Clone)]
+pub struct VariableState {
+    pub variable: Variable,
+    pub data: Value,
+}
+
+fn metadata_differs(source: &Variable, target: &Variable) -> bool {
+    source.description != target.description
+        || source.tags != target.tags
+        || source.is_encrypted != target.is_encrypted
+}
+
+/// Hash the identity and version of every variable involved, so any write to
+/// either environment yields a different fingerprint
+fn fingerprint(source: &[&VariableState], target: &[&VariableState], prune: bool) -> String {
+    let mut hasher = Sha256::new();
+
+    for (label, states) in [("source", source), ("target", target)] {
+        hasher.update(format!("{}\n", label));
+        for state in states {
+            let variable = &state.variable;
+            hasher.update(format!("{}:{}:{}\n", variable.key, variable.id, variable.version));
+        }
+    }
+    hasher.update(format!("prune:{}\n", prune));
+
+    hex::encode(hasher.finalize())
+}
+
+/// Diff `source` against `target` key by key. When `keys` is given only those
+/// keys are considered; target-only keys are reported as removals.
+pub fn build_plan(
+    request: &PromotionPlanRequest,
+    source: &[VariableState],
+    target: &[VariableState],
+) -> PromotionPlan {
+    let selected = |state: &&VariableState| match &request.keys {
+        Some(keys) => keys.contains(&state.variable.key),
+        None => true,
+    };
+
+    let mut source: Vec<&VariableState> = source.iter().filter(selected).collect();
+    let mut target: Vec<&VariableState> = target.iter().filter(selected).collect();
+    source.sort_by(|a, b| a.variable.key.cmp(&b.variable.key));
+    target.sort_by(|a, b| a.variable.key.cmp(&b.variable.key));
+
+    let target_by_key: BTreeMap<&str, &VariableState> = target
+        .iter()
+        .map(|state| (state.variable.key.as_str(), *state))
+        .collect();
+
+    let mut changes = Vec::new();
+    let mut unchanged = 0;
+
+    for state in &source {
+        let variable = &state.variable;
+        match target_by_key.get(variable.key.as_str()) {
+            None => changes.push(PromotionChange {
+                key: variable.key.clone(),
+                action: PromotionAction::Add,
+                source_version: Some(variable.version),
+                target_version: None,
+                diff: None,
+                metadata_changed: false,
+            }),
+            Some(existing) => {
+                let diff = json_patch::diff(&existing.data, &state.data);
+                let metadata_changed = metadata_differs(variable, &existing.variable);
+
+                if diff.0.is_empty() && !metadata_changed {
+                    unchanged += 1;
+                    continue;
+                }
+
+                changes.push(PromotionChange {
+                    key: variable.key.clone(),
+                    action: PromotionAction::Change,
+                    source_version: Some(variable.version),
+                    target_version: Some(existing.variable.version),
+                    diff: Some(diff),
+                    metadata_changed,
+                });
+            }
+        }
+    }
+
+    for state in &target {
+        let variable = &state.variable;
+        if !source.iter().any(|s| s.variable.key == variable.key) {
+            changes.push(PromotionChange {
+                key: variable.key.clone(),
+                action: PromotionAction::Remove,
+                source_version: None,
+                target_version: Some(variable.version),
+                diff: None,
+                metadata_changed: false,
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+
+    PromotionPlan {
+        source: request.source.clone(),
+        target: request.target.clone(),
+        prune: request.prune,
+        changes,
+        unchanged,
+        fingerprint: fingerprint(&source, &target, request.prune),
+    }
+}
+
+/// Check every change of `plan` with `authorize`: the source value must be
+/// readable, and the target writable or, for pruned keys, deletable
+pub fn authorize_plan(
+    plan: &PromotionPlan,
+    source: (&Environment, &[VariableState]),
+    target: (&Environment, &[VariableState]),
+    authorize: &impl Fn(Action, &Variable, &Environment) -> Result<()>,
+) -> Result<()> {
+    let (source_env, source_states) = source;
+    let (target_env, target_states) = target;
+    let find = |states: &[VariableState], key: &str| {
+        states
+            .iter()
+            .find(|s| s.variable.key == key)
+            .map(|s| s.variable.clone())
+            .ok_or_else(|| AppError::InternalServer(format!("Planned key '{}' not loaded", key)))
+    };
+
+    for change in &plan.changes {
+        match change.action {
+            PromotionAction::Add => {
+                let variable = find(source_states, &change.key)?;
+                authorize(Action::VariableRead, &variable, source_env)?;
+                authorize(Action::VariableWrite, &variable, target_env)?;
+            }
+            PromotionAction::Change => {
+                let variable = find(source_states, &change.key)?;
+                authorize(Action::VariableRead, &variable, source_env)?;
+                let existing = find(target_states, &change.key)?;
+                authorize(Action::VariableWrite, &existing, target_env)?;
+            }
+            PromotionAction::Remove if plan.prune => {
+                let existing = find(target_states, &change.key)?;
+                authorize(Action::VariableDelete, &existing, target_env)?;
+            }
+            PromotionAction::Remove => {}
+        }
+    }
+
+    Ok(())
+}
+
+async fn load_states(
+    storage: &impl VariableStore,
+    variables: Vec<Variable>,
+) -> Result<Vec<VariableState>> {
+    let mut states = Vec::with_capacity(variables.len());
+    for variable in variables {
+        let data = storage.retrieve(&variable.storage_path).await?;
+        states.push(VariableState { variable, data });
+    }
+
+    Ok(states)
+}
+
+/// Load both environments and compute the plan without locking anything.
+/// Fails if `authorize` rejects any of the planned changes.
+pub async fn plan_promotion(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    request: &PromotionPlanRequest,
+    source: &Environment,
+    target: &Environment,
+    authorize: impl Fn(Action, &Variable, &Environment) -> Result<()>,
+) -> Result<PromotionPlan> {
+    let var_repo = VariableRepository::new(pool.clone());
+
+    let source_states = load_states(storage, var_repo.list_all(source.id).await?).await?;
+    let target_states = load_states(storage, var_repo.list_all(target.id).await?).await?;
+
+    let plan = build_plan(request, &source_states, &target_states);
+    authorize_plan(
+        &plan,
+        (source, &source_states),
+        (target, &target_states),
+        &authorize,
+    )?;
+
+    Ok(plan)
+}
+
+/// Give `promoted` the schema of the `source` variable its data was copied from
+async fn promote_schema(
+    conn: &mut PgConnection,
+    promoted: &Variable,
+    source: &Variable,
+) -> Result<Variable> {
+    if promoted.schema_id == source.schema_id && promoted.inline_schema == source.inline_schema {
+        return Ok(promoted.clone());
+    }
+
+    VariableRepository::set_schema_tx(
+        conn,
+        promoted.id,
+        source.schema_id,
+        source.inline_schema.as_ref(),
+    )
+    .await
+}
+
+/// Write every change of `plan` into the target environment on `conn`,
+/// recording storage writes in `undo` and a version for every written variable.
+/// Removed variables move to the trash. Returns the storage paths of pruned versions,
+/// which are only deleted once the transaction commits.
+#[allow(clippy::too_many_arguments)]
+async fn write_changes(
+    conn: &mut PgConnection,
+    storage: &impl VariableStore,
+    owner: Owner,
+    target: &Environment,
+    promoted_by: Uuid,
+    plan: &PromotionPlan,
+    source: &BTreeMap<&str, &VariableState>,
+    existing: &BTreeMap<&str, &VariableState>,
+    retention: Option<i64>,
+    message: &str,
+    undo: &mut Vec<Undo>,
+) -> Result<Vec<String>> {
+    let mut removed_paths = Vec::new();
+
+    for change in &plan.changes {
+        match change.action {
+            PromotionAction::Add => {
+                let state = source[change.key.as_str()];
+                let variable = &state.variable;
+
+                let storage_path = storage
+                    .store(owner, target.id, &variable.key, &state.data, variable.is_encrypted)
+                    .await?;
+                undo.push(Undo::Delete(storage_path.clone()));
+
+                let created = VariableRepository::create_tx(
+                    conn,
+                    owner,
+                    target.id,
+                    promoted_by,
+                    &variable.key,
+                    variable.description.as_deref(),
+                    variable.size_bytes,
+                    &storage_path,
+                    variable.is_encrypted,
+                    variable.tags.clone(),
+                )
+                .await?;
+                let created = promote_schema(conn, &created, variable).await?;
+
+                let version = record_version(
+                    conn,
+                    storage,
+                    &created,
+                    &state.data,
+                    Some(promoted_by),
+                    Some(message),
+                )
+                .await?;
+                undo.push(Undo::Delete(version.storage_path));
+            }
+            PromotionAction::Change => {
+                let state = source[change.key.as_str()];
+                let current = existing[change.key.as_str()];
+
+                if let Some(version) =
+                    preserve_current(conn, storage, &current.variable, &current.data).await?
+                {
+                    undo.push(Undo::Delete(version.storage_path));
+                }
+
+                // Encryption is never dropped, and an encrypted source encrypts the target
+                let encrypt = state.variable.is_encrypted && !current.variable.is_encrypted;
+                if encrypt || state.data != current.data {
+                    undo.push(Undo::Restore(
+                        current.variable.storage_path.clone(),
+                        current.data.clone(),
+                    ));
+                    if encrypt {
+                        let id = current.variable.id;
+                        let mut version_paths =
+                            VariableVersionRepository::list_storage_paths_tx(conn, id).await?;
+                        version_paths.extend(
+                            ScheduledChangeRepository::list_pending_paths_tx(conn, id).await?,
+                        );
+                        storage
+                            .encrypt_existing(&current.variable.storage_path, &version_paths)
+                            .await?;
+                    }
+                    storage
+                        .update(&current.variable.storage_path, &state.data)
+                        .await?;
+                }
+
+                let updated = VariableRepository::overwrite_tx(
+                    conn,
+                    current.variable.id,
+                    current.variable.environment_id,
+                    state.variable.description.as_deref(),
+                    state.variable.size_bytes,
+                    state.variable.is_encrypted || current.variable.is_encrypted,
+                    state.variable.tags.clone(),
+                )
+                .await?;
+                let updated = promote_schema(conn, &updated, &state.variable).await?;
+
+                let version = record_version(
+                    conn,
+                    storage,
+                    &updated,
+                    &state.data,
+                    Some(promoted_by),
+                    Some(message),
+                )
+                .await?;
+                undo.push(Undo::Delete(version.storage_path));
+
+                if let Some(keep) = retention {
+                    removed_paths
+                        .extend(VariableVersionRepository::prune_tx(conn, updated.id, keep).await?);
+                }
+            }
+            PromotionAction::Remove => {
+                let current = existing[change.key.as_str()];
+                VariableRepository::trash_tx(conn, current.variable.id, promoted_by).await?;
+            }
+        }
+    }
+
+    Ok(removed_paths)
+}
+
+/// Apply a previously planned promotion in one transaction.
+///
+/// Both environments are locked, the plan is recomputed and rejected with a
+/// conflict if its fingerprint no longer matches. Storage writes are reverted
+/// if anything fails before the commit.
+#[allow(clippy::too_many_arguments)]
+pub async fn apply_promotion(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    owner: Owner,
+    promoted_by: Uuid,
+    tier: &Tier,
+    project: &Project,
+    source: &Environment,
+    target: &Environment,
+    request: &PromotionPlanRequest,
+    expected_fingerprint: &str,
+    authorize: impl Fn(Action, &Variable, &Environment) -> Result<()>,
+) -> Result<EnvironmentPromotion> {
+    let mut tx = pool.begin().await?;
+
+    // Lock in a stable order so opposing promotions can't deadlock
+    let (source_vars, target_vars) = if source.id < target.id {
+        let s = VariableRepository::lock_environment_tx(&mut tx, source.id).await?;
+        let t = VariableRepository::lock_environment_tx(&mut tx, target.id).await?;
+        (s, t)
+    } else {
+        let t = VariableRepository::lock_environment_tx(&mut tx, target.id).await?;
+        let s = VariableRepository::lock_environment_tx(&mut tx, source.id).await?;
+        (s, t)
+    };
+
+    let source_states = load_states(storage, source_vars).await?;
+    let target_states = load_states(storage, target_vars).await?;
+
+    let plan = build_plan(request, &source_states, &target_states);
+    if plan.fingerprint != expected_fingerprint {
+        return Err(AppError::Conflict(
+            "Environments changed since the plan was made".to_string(),
+        ));
+    }
+    authorize_plan(
+        &plan,
+        (source, &source_states),
+        (target, &target_states),
+        &authorize,
+    )?;
+
+    let count = |action| plan.changes.iter().filter(|c| c.action == action).count() as i32;
+    let added = count(PromotionAction::Add);
+    let changed = count(PromotionAction::Change);
+    let removed = if plan.prune { count(PromotionAction::Remove) } else { 0 };
+
+    // Without pruning, removals are only reported
+    let mut plan = plan;
+    if !plan.prune {
+        plan.changes.retain(|c| c.action != PromotionAction::Remove);
+    }
+
+    if added > removed {
+        let var_repo = VariableRepository::new(pool.clone());
+        let current = var_repo.count_by_owner(owner).await?;
+        if current + added - removed > tier.max_variables {
+            return Err(AppError::TierLimitExceeded(format!(
+                "Maximum {} variables allowed",
+                tier.max_variables
+            )));
+        }
+    }
+
+    let source_by_key: BTreeMap<&str, &VariableState> = source_states
+        .iter()
+        .map(|s| (s.variable.key.as_str(), s))
+        .collect();
+    let target_by_key: BTreeMap<&str, &VariableState> = target_states
+        .iter()
+        .map(|s| (s.variable.key.as_str(), s))
+        .collect();
+
+    // Expired variables give up their keys to the added ones
+    let mut purged = Vec::new();
+    for change in plan.changes.iter().filter(|c| c.action == PromotionAction::Add) {
+        purged.extend(purge_expired_key_tx(&mut tx, target.id, &change.key).await?);
+    }
+
+    let mut undo = Vec::new();
+    let written = write_changes(
+        &mut tx,
+        storage,
+        owner,
+        target,
+        promoted_by,
+        &plan,
+        &source_by_key,
+        &target_by_key,
+        tier.version_retention(),
+        &format!("Promoted from {}", source.name),
+        &mut undo,
+    )
+    .await;
+
+    let removed_paths = match written {
+        Ok(paths) => paths,
+        Err(e) => {
+            drop(tx);
+            revert(storage, undo).await;
+            return Err(e);
+        }
+    };
+
+    let summary: Vec<Value> = plan
+        .changes
+        .iter()
+        .map(|c| {
+            json!({
+                "key": c.key,
+                "action": c.action,
+                "source_version": c.source_version,
+                "target_version": c.target_version,
+            })
+        })
+        .collect();
+
+    let recorded = EnvironmentPromotionRepository::create_tx(
+        &mut tx,
+        project.id,
+        source.id,
+        target.id,
+        promoted_by,
+        added,
+        changed,
+        removed,
+        Value::Array(summary),
+    )
+    .await;
+
+    let promotion = match recorded {
+        Ok(promotion) => promotion,
+        Err(e) => {
+            drop(tx);
+            revert(storage, undo).await;
+            return Err(e);
+        }
+    };
+
+    if let Err(e) = tx.commit().await {
+        revert(storage, undo).await;
+        return Err(e.into());
+    }
+    remove_purged(storage, purged).await;
+
+    for path in removed_paths {
+        if let Err(e) = storage.delete(&path).await {
+            tracing::warn!("Failed to delete data removed by promotion {}: {}", path, e);
+        }
+    }
+
+    Ok(promotion)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn state(key: &str, version: i32, data: Value) -> VariableState {
+        VariableState {
+            variable: Variable {
+                id: Uuid::new_v4(),
+                user_id: Uuid::new_v4(),
+                organization_id: None,
+                environment_id: Uuid::new_v4(),
+                key: key.to_string(),
+                description: None,
+                size_bytes: 0,
+                version,
+                storage_path: format!("{}.json", key),
+                is_encrypted: false,
+                tags: None,
+                is_public: false,
+                schema_id: None,
+                inline_schema: None,
+                expires_at: None,
+                deleted_at: None,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+            data,
+        }
+    }
+
+    fn request(keys: Option<Vec<&str>>) -> PromotionPlanRequest {
+        PromotionPlanRequest {
+            source: "staging".to_string(),
+            target: "prod".to_string(),
+            keys: keys.map(|keys| keys.into_iter().map(String::from).collect()),
+            prune: false,
+        }
+    }
+
+    #[test]
+    fn test_build_plan_classifies_keys() {
+        let source = vec![
+            state("added", 1, json!(1)),
+            state("changed", 2, json!({"host": "new"})),
+            state("same", 1, json!(true)),
+        ];
+        let target = vec![
+            state("changed", 1, json!({"host": "old"})),
+            state("same", 4, json!(true)),
+            state("removed", 1, json!("x")),
+        ];
+
+        let plan = build_plan(&request(None), &source, &target);
+
+        let actions: Vec<_> = plan.changes.iter().map(|c| (c.key.as_str(), c.action)).collect();
+        assert_eq!(
+            actions,
+            vec![
+                ("added", PromotionAction::Add),
+                ("changed", PromotionAction::Change),
+                ("removed", PromotionAction::Remove),
+            ]
+        );
+        assert_eq!(plan.unchanged, 1);
+
+        let diff = serde_json::to_value(plan.changes[1].diff.as_ref().unwrap()).unwrap();
+        assert_eq!(diff, json!([{"op": "replace", "path": "/host", "value": "new"}]));
+    }
+
+    #[test]
+    fn test_build_plan_respects_key_filter() {
+        let source = vec![state("a", 1, json!(1)), state("b", 1, json!(2))];
+        let target = vec![state("c", 1, json!(3))];
+
+        let plan = build_plan(&request(Some(vec!["a"])), &source, &target);
+
+        assert_eq!(plan.changes.len(), 1);
+        assert_eq!(plan.changes[0].key, "a");
+    }
+
+    #[test]
+    fn test_fingerprint_changes_with_target_version() {
+        let source = vec![state("a", 1, json!(1))];
+        let mut target = vec![state("a", 1, json!(0))];
+
+        let before = build_plan(&request(None), &source, &target).fingerprint;
+        target[0].variable.version = 2;
+        let after = build_plan(&request(None), &source, &target).fingerprint;
+
+        assert_ne!(before, after);
+    }
+}
@@ -0,0 +1,59 @@
+use serde_json::Value;
+use serde_json_path::JsonPath;
+
+use crate::error::{AppError, Result};
+
+/// A compiled RFC 9535 JSONPath expression projecting parts of a variable's data
+#[derive(Debug, Clone)]
+pub struct DataQuery(JsonPath);
+
+impl DataQuery {
+    pub fn parse(expression: &str) -> Result<Self> {
+        JsonPath::parse(expression)
+            .map(Self)
+            .map_err(|e| AppError::BadRequest(format!("Invalid JSONPath expression: {}", e)))
+    }
+
+    /// The values the expression selects from `data`, in document order
+    pub fn run(&self, data: &Value) -> Vec<Value> {
+        self.0.query(data).all().into_iter().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_query_projects_values() {
+        let data = json!({
+            "services": [
+                {"name": "api", "timeout_ms": 500},
+                {"name": "worker", "timeout_ms": 3000},
+                {"name": "cron"}
+            ]
+        });
+
+        let query = DataQuery::parse("$.services[*].timeout_ms").unwrap();
+        assert_eq!(query.run(&data), vec![json!(500), json!(3000)]);
+
+        let query = DataQuery::parse("$..timeout_ms").unwrap();
+        assert_eq!(query.run(&data).len(), 2);
+
+        let query = DataQuery::parse("$.services[?@.timeout_ms > 1000].name").unwrap();
+        assert_eq!(query.run(&data), vec![json!("worker")]);
+    }
+
+    #[test]
+    fn test_query_without_matches_is_empty() {
+        let query = DataQuery::parse("$.missing").unwrap();
+        assert!(query.run(&json!({"present": 1})).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_query_is_rejected() {
+        assert!(matches!(DataQuery::parse("timeout_ms"), Err(AppError::BadRequest(_))));
+        assert!(matches!(DataQuery::parse("$[?"), Err(AppError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,434 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde_json::Value;
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::dto::Referrer;
+use crate::error::{AppError, Result};
+use crate::models::{AccessibleVariable, Variable};
+use crate::repositories::VariableRepository;
+use crate::storage::VariableStore;
+
+/// Most references followed from a variable to the one whose data is finally used
+pub const MAX_REFERENCE_DEPTH: usize = 8;
+
+/// A piece of a string holding `${key}` placeholders
+#[derive(Debug, PartialEq)]
+enum Part<'a> {
+    Text(String),
+    Ref(&'a str),
+}
+
+/// Split `s` at its `${key}` placeholders. `$${` stands for a literal `${`, and a `${`
+/// without a closing brace or a key is kept as it is.
+fn parse_template(s: &str) -> Vec<Part<'_>> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = s;
+
+    while let Some(start) = rest.find('$') {
+        text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        if let Some(escaped) = after.strip_prefix("${") {
+            text.push_str("${");
+            rest = escaped;
+        } else if let Some(body) = after.strip_prefix('{')
+            && let Some(end) = body.find('}').filter(|&end| end > 0)
+        {
+            if !text.is_empty() {
+                parts.push(Part::Text(std::mem::take(&mut text)));
+            }
+            parts.push(Part::Ref(&body[..end]));
+            rest = &body[end + 1..];
+        } else {
+            text.push('$');
+            rest = after;
+        }
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push(Part::Text(text));
+    }
+    parts
+}
+
+/// The key `value` refers to when it is a `{"$ref": key}` object
+fn ref_target(value: &Value) -> Option<&str> {
+    match value {
+        Value::Object(fields) if fields.len() == 1 => fields.get("$ref")?.as_str(),
+        _ => None,
+    }
+}
+
+/// Keys referenced anywhere in `data`, by `{"$ref": key}` objects or `${key}` placeholders
+pub fn collect_references(data: &Value) -> BTreeSet<String> {
+    fn collect(value: &Value, keys: &mut BTreeSet<String>) {
+        if let Some(key) = ref_target(value) {
+            keys.insert(key.to_string());
+            return;
+        }
+
+        match value {
+            Value::String(s) => {
+                for part in parse_template(s) {
+                    if let Part::Ref(key) = part {
+                        keys.insert(key.to_string());
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| collect(item, keys)),
+            Value::Object(fields) => fields.values().for_each(|field| collect(field, keys)),
+            _ => {}
+        }
+    }
+
+    let mut keys = BTreeSet::new();
+    collect(data, &mut keys);
+    keys
+}
+
+/// Replace the references in `data`, the data of variable `key`, with the data of the
+/// variables in `values`. A string that is a single placeholder takes the referenced
+/// value as it is; elsewhere placeholders are filled with strings, numbers and booleans.
+pub fn resolve_references(
+    data: &Value,
+    key: &str,
+    values: &HashMap<String, Value>,
+) -> Result<Value> {
+    resolve_value(data, values, &mut vec![key.to_string()])
+}
+
+fn resolve_value(
+    value: &Value,
+    values: &HashMap<String, Value>,
+    stack: &mut Vec<String>,
+) -> Result<Value> {
+    if let Some(key) = ref_target(value) {
+        return resolve_key(key, values, stack);
+    }
+
+    match value {
+        Value::String(s) => {
+            let parts = parse_template(s);
+            if let [Part::Ref(key)] = parts.as_slice() {
+                return resolve_key(key, values, stack);
+            }
+
+            let mut text = String::new();
+            for part in parts {
+                match part {
+                    Part::Text(s) => text.push_str(&s),
+                    Part::Ref(key) => match resolve_key(key, values, stack)? {
+                        Value::String(s) => text.push_str(&s),
+                        value @ (Value::Number(_) | Value::Bool(_)) => {
+                            text.push_str(&value.to_string())
+                        }
+                        _ => {
+                            return Err(AppError::Validation(format!(
+                                "Variable '{}' is not a string, number or boolean and cannot be \
+                                 interpolated",
+                                key
+                            )))
+                        }
+                    },
+                }
+            }
+            Ok(Value::String(text))
+        }
+        Value::Array(items) => items
+            .iter()
+            .map(|item| resolve_value(item, values, stack))
+            .collect::<Result<_>>()
+            .map(Value::Array),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(field, value)| Ok((field.clone(), resolve_value(value, values, stack)?)))
+            .collect::<Result<_>>()
+            .map(Value::Object),
+        _ => Ok(value.clone()),
+    }
+}
+
+fn resolve_key(
+    key: &str,
+    values: &HashMap<String, Value>,
+    stack: &mut Vec<String>,
+) -> Result<Value> {
+    if stack.iter().any(|k| k == key) {
+        return Err(AppError::Validation(format!(
+            "Reference cycle: {} -> {}",
+            stack.join(" -> "),
+            key
+        )));
+    }
+    if stack.len() > MAX_REFERENCE_DEPTH {
+        return Err(AppError::Validation(format!(
+            "References nest deeper than {} levels at '{}'",
+            MAX_REFERENCE_DEPTH, key
+        )));
+    }
+    let value = values
+        .get(key)
+        .ok_or_else(|| AppError::NotFound(format!("Referenced variable '{}' not found", key)))?;
+
+    stack.push(key.to_string());
+    let resolved = resolve_value(value, values, stack);
+    stack.pop();
+
+    resolved
+}
+
+/// Refresh the keys `variable` references from `data`. Encrypted variables keep none,
+/// as a placeholder could be part of a secret.
+pub async fn index_references(
+    conn: &mut PgConnection,
+    variable: &Variable,
+    data: &Value,
+) -> Result<()> {
+    let keys: Vec<String> = if variable.is_encrypted {
+        Vec::new()
+    } else {
+        collect_references(data).into_iter().collect()
+    };
+
+    VariableRepository::set_referenced_keys_tx(conn, variable.id, &keys).await
+}
+
+/// Record the references of variables written before references were recorded and
+/// return how many were read. Variables whose data can't be read are left for the next run.
+pub async fn backfill_references(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+) -> Result<usize> {
+    const BATCH: i64 = 100;
+
+    let var_repo = VariableRepository::new(pool.clone());
+    let mut after = None;
+    let mut indexed = 0;
+
+    loop {
+        let variables = var_repo.list_unindexed_references(after, BATCH).await?;
+        let Some(last) = variables.last() else {
+            return Ok(indexed);
+        };
+        after = Some(last.id);
+
+        for variable in variables {
+            match storage.retrieve(&variable.storage_path).await {
+                Ok(data) => {
+                    let keys: Vec<String> = collect_references(&data).into_iter().collect();
+                    var_repo
+                        .backfill_referenced_keys(variable.id, variable.version, &keys)
+                        .await?;
+                    indexed += 1;
+                }
+                Err(e) => tracing::warn!("Failed to index references of {}: {}", variable.id, e),
+            }
+        }
+    }
+}
+
+/// Record the references of variables written before references were recorded, once, in
+/// the background
+pub fn spawn_reference_backfill<S: VariableStore + Clone + 'static>(
+    pool: Pool<Postgres>,
+    storage: S,
+) {
+    tokio::spawn(async move {
+        match backfill_references(&pool, &storage).await {
+            Ok(0) => {}
+            Ok(indexed) => tracing::info!("Indexed the references of {} variables", indexed),
+            Err(e) => tracing::warn!("Failed to index variable references: {}", e),
+        }
+    });
+}
+
+/// Resolve the references in `data`, the data of `variable`. References name variables
+/// of the variable's own environment; each one the resolution reaches must be found in
+/// `environment_id` or shared with `shared_with`, and pass `authorize`.
+pub async fn resolve_variable_data(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    variable: &Variable,
+    data: &Value,
+    environment_id: Uuid,
+    shared_with: Option<Uuid>,
+    authorize: impl Fn(&AccessibleVariable) -> Result<()>,
+) -> Result<Value> {
+    let var_repo = VariableRepository::new(pool.clone());
+    let mut values = HashMap::new();
+    let mut seen = HashSet::from([variable.key.clone()]);
+    let mut pending: Vec<String> = collect_references(data).into_iter().collect();
+
+    // Load the referenced variables level by level, as deep as resolution may go
+    for _ in 0..MAX_REFERENCE_DEPTH {
+        pending.retain(|key| seen.insert(key.clone()));
+        if pending.is_empty() {
+            break;
+        }
+
+        let referenced = var_repo.find_by_keys(&pending, variable.environment_id).await?;
+        let ids: Vec<Uuid> = referenced.iter().map(|v| v.id).collect();
+        let accessible = var_repo
+            .find_many(environment_id, shared_with, &ids, &[])
+            .await?;
+
+        pending.clear();
+        for referenced in referenced {
+            let Some(accessible) = accessible.iter().find(|a| a.variable.id == referenced.id)
+            else {
+                return Err(AppError::Authorization(format!(
+                    "Not allowed to read referenced variable '{}'",
+                    referenced.key
+                )));
+            };
+            authorize(accessible)?;
+
+            let value = storage.retrieve(&referenced.storage_path).await?;
+            pending.extend(collect_references(&value));
+            values.insert(referenced.key, value);
+        }
+    }
+
+    resolve_references(data, &variable.key, &values)
+}
+
+/// Variables of an environment whose data references `key`, directly or through other
+/// variables, nearest first
+pub async fn find_referrers(
+    pool: &Pool<Postgres>,
+    environment_id: Uuid,
+    key: &str,
+) -> Result<Vec<Referrer>> {
+    let var_repo = VariableRepository::new(pool.clone());
+    let mut referrers = Vec::new();
+    let mut seen = HashSet::from([key.to_string()]);
+    let mut keys = vec![key.to_string()];
+
+    for depth in 1..=MAX_REFERENCE_DEPTH {
+        if keys.is_empty() {
+            break;
+        }
+
+        let variables = var_repo.list_referrers(environment_id, &keys).await?;
+        keys.clear();
+        for variable in variables {
+            if seen.insert(variable.key.clone()) {
+                keys.push(variable.key.clone());
+                referrers.push(Referrer { variable, depth });
+            }
+        }
+    }
+
+    Ok(referrers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+        pairs.iter().map(|(key, value)| (key.to_string(), value.clone())).collect()
+    }
+
+    #[test]
+    fn test_parse_template() {
+        assert_eq!(
+            parse_template("postgres://${db.host}:${db.port}/app"),
+            vec![
+                Part::Text("postgres://".to_string()),
+                Part::Ref("db.host"),
+                Part::Text(":".to_string()),
+                Part::Ref("db.port"),
+                Part::Text("/app".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_template("$${HOME} costs $5 ${}"),
+            vec![Part::Text("${HOME} costs $5 ${}".to_string())]
+        );
+        assert_eq!(parse_template("${open"), vec![Part::Text("${open".to_string())]);
+    }
+
+    #[test]
+    fn test_collect_references() {
+        let data = json!({
+            "primary": {"$ref": "db.host"},
+            "url": "https://${api.host}/v1",
+            "plain": {"$ref": "not-a-ref", "other": 1},
+            "list": ["${db.host}"]
+        });
+
+        let keys: Vec<String> = collect_references(&data).into_iter().collect();
+        assert_eq!(keys, vec!["api.host", "db.host"]);
+    }
+
+    #[test]
+    fn test_resolve_references() {
+        let data = json!({
+            "db": {"$ref": "db"},
+            "url": "postgres://${db.host}:${db.port}/app",
+            "port": "${db.port}",
+            "literal": "$${db.host}"
+        });
+        let values = values(&[
+            ("db", json!({"host": "${db.host}"})),
+            ("db.host", json!("db.internal")),
+            ("db.port", json!(5432)),
+        ]);
+
+        assert_eq!(
+            resolve_references(&data, "app", &values).unwrap(),
+            json!({
+                "db": {"host": "db.internal"},
+                "url": "postgres://db.internal:5432/app",
+                "port": 5432,
+                "literal": "${db.host}"
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_references_rejects_cycles() {
+        let values = values(&[("a", json!({"$ref": "b"})), ("b", json!("x-${a}"))]);
+
+        let result = resolve_references(&json!({"$ref": "a"}), "app", &values);
+        assert!(matches!(result, Err(AppError::Validation(m)) if m.contains("app -> a -> b -> a")));
+
+        let own = resolve_references(&json!("${app}"), "app", &values);
+        assert!(matches!(own, Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn test_resolve_references_limits_depth() {
+        let chain: Vec<(String, Value)> = (0..=MAX_REFERENCE_DEPTH)
+            .map(|i| (format!("k{}", i), json!({"$ref": format!("k{}", i + 1)})))
+            .chain([(format!("k{}", MAX_REFERENCE_DEPTH + 1), json!(1))])
+            .collect();
+        let values: HashMap<String, Value> = chain.into_iter().collect();
+
+        let deep = resolve_references(&json!({"$ref": "k0"}), "app", &values);
+        assert!(matches!(deep, Err(AppError::Validation(m)) if m.contains("deeper")));
+
+        let shallow = resolve_references(&json!({"$ref": "k2"}), "app", &values);
+        assert_eq!(shallow.unwrap(), json!(1));
+    }
+
+    #[test]
+    fn test_resolve_references_requires_scalars_in_strings() {
+        let values = values(&[("db", json!({"host": "x"}))]);
+
+        assert!(matches!(
+            resolve_references(&json!("host=${db}"), "app", &values),
+            Err(AppError::Validation(_))
+        ));
+        assert!(matches!(
+            resolve_references(&json!("${missing}"), "app", &values),
+            Err(AppError::NotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::dto::ResolvedVariable;
+use crate::error::Result;
+use crate::models::{Environment, MergeMode, Variable};
+use crate::repositories::{EnvironmentRepository, VariableRepository};
+use crate::storage::VariableStore;
+
+/// The variables defined directly in one environment
+#[derive(Debug, Clone)]
+pub struct EnvironmentLayer {
+    pub environment_id: Uuid,
+    pub environment: String,
+    /// `(key, variable id, value)` triples
+    pub values: Vec<(String, Uuid, Value)>,
+}
+
+/// Merge `overlay` into `target`. Objects are merged field by field; any other
+/// value in `overlay` replaces the one in `target`.
+pub fn deep_merge(target: &mut Value, overlay: Value) {
+    match (target, overlay) {
+        (Value::Object(target), Value::Object(overlay)) => {
+            for (field, value) in overlay {
+                match target.get_mut(&field) {
+                    Some(existing) => deep_merge(existing, value),
+                    None => {
+                        target.insert(field, value);
+                    }
+                }
+            }
+        }
+        (target, overlay) => *target = overlay,
+    }
+}
+
+/// Compute the effective value of every key from `layers`, ordered nearest
+/// environment first. Results are sorted by key.
+pub fn resolve_layers(layers: Vec<EnvironmentLayer>, mode: MergeMode) -> Vec<ResolvedVariable> {
+    let mut resolved: BTreeMap<String, ResolvedVariable> = BTreeMap::new();
+
+    // Apply the outermost parent first so nearer environments win
+    for layer in layers.into_iter().rev() {
+        for (key, variable_id, value) in layer.values {
+            match resolved.get_mut(&key) {
+                Some(current)
+                    if mode == MergeMode::Merge && current.value.is_object() && value.is_object() =>
+                {
+                    deep_merge(&mut current.value, value);
+                    current.environment = layer.environment.clone();
+                    current.environment_id = layer.environment_id;
+                    current.variable_id = variable_id;
+                    current.merged_from.push(layer.environment.clone());
+                }
+                _ => {
+                    resolved.insert(
+                        key.clone(),
+                        ResolvedVariable {
+                            key,
+                            value,
+                            environment: layer.environment.clone(),
+                            environment_id: layer.environment_id,
+                            variable_id,
+                            merged_from: vec![layer.environment.clone()],
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    resolved.into_values().collect()
+}
+
+/// Resolve the variables visible in an environment through its parents.
+/// Variables for which `readable` returns false are skipped in every layer.
+/// Returns the inheritance chain (nearest first) alongside the resolved values.
+pub async fn resolve_environment(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    environment_id: Uuid,
+    mode: MergeMode,
+    key: Option<&str>,
+    readable: impl Fn(&Environment, &Variable) -> bool,
+) -> Result<(Vec<Environment>, Vec<ResolvedVariable>)> {
+    let env_repo = EnvironmentRepository::new(pool.clone());
+    let var_repo = VariableRepository::new(pool.clone());
+
+    let chain = env_repo.ancestry(environment_id).await?;
+
+    let mut layers = Vec::with_capacity(chain.len());
+    for environment in &chain {
+        let variables = match key {
+            Some(key) => var_repo
+                .find_by_key(key, environment.id)
+                .await?
+                .into_iter()
+                .collect(),
+            None => var_repo.list_all(environment.id).await?,
+        };
+
+        let mut values = Vec::with_capacity(variables.len());
+        for variable in variables.into_iter().filter(|v| readable(environment, v)) {
+            let data = storage.retrieve(&variable.storage_path).await?;
+            values.push((variable.key, variable.id, data));
+        }
+
+        layers.push(EnvironmentLayer {
+            environment_id: environment.id,
+            environment: environment.name.clone(),
+            values,
+        });
+    }
+
+    Ok((chain, resolve_layers(layers, mode)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_deep_merge_nested_objects() {
+        let mut base = json!({"db": {"host": "localhost", "port": 5432}, "debug": true});
+        deep_merge(&mut base, json!({"db": {"host": "prod-db"}, "debug": false}));
+
+        assert_eq!(
+            base,
+            json!({"db": {"host": "prod-db", "port": 5432}, "debug": false})
+        );
+    }
+
+    fn layer(name: &str, values: Vec<(&str, Value)>) -> EnvironmentLayer {
+        EnvironmentLayer {
+            environment_id: Uuid::new_v4(),
+            environment: name.to_string(),
+            values: values
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), Uuid::new_v4(), value))
+                .collect(),
+        }
+    }
+
+    fn chain() -> Vec<EnvironmentLayer> {
+        vec![
+            layer("prod-eu", vec![("db", json!({"host": "eu-db"}))]),
+            layer("prod", vec![("db", json!({"host": "prod-db", "pool": 20}))]),
+            layer(
+                "base",
+                vec![("db", json!({"host": "localhost", "port": 5432})), ("debug", json!(false))],
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_resolve_layers_override() {
+        let resolved = resolve_layers(chain(), MergeMode::Override);
+
+        assert_eq!(resolved.len(), 2);
+        assert_eq!(resolved[0].key, "db");
+        assert_eq!(resolved[0].value, json!({"host": "eu-db"}));
+        assert_eq!(resolved[0].environment, "prod-eu");
+        assert_eq!(resolved[0].merged_from, vec!["prod-eu"]);
+        assert_eq!(resolved[1].key, "debug");
+        assert_eq!(resolved[1].environment, "base");
+    }
+
+    #[test]
+    fn test_resolve_layers_merge() {
+        let resolved = resolve_layers(chain(), MergeMode::Merge);
+
+        assert_eq!(
+            resolved[0].value,
+            json!({"host": "eu-db", "pool": 20, "port": 5432})
+        );
+        assert_eq!(resolved[0].environment, "prod-eu");
+        assert_eq!(resolved[0].merged_from, vec!["base", "prod", "prod-eu"]);
+    }
+
+    #[test]
+    fn test_resolve_layers_merge_replaces_non_objects() {
+        let layers = vec![
+            layer("prod", vec![("hosts", json!({"primary": "a"}))]),
+            layer("base", vec![("hosts", json!(["a", "b"]))]),
+        ];
+        let resolved = resolve_layers(layers, MergeMode::Merge);
+
+        assert_eq!(resolved[0].value, json!({"primary": "a"}));
+        assert_eq!(resolved[0].merged_from, vec!["prod"]);
+    }
+
+    #[test]
+    fn test_deep_merge_replaces_arrays() {
+        let mut base = json!({"hosts": ["a", "b"]});
+        deep_merge(&mut base, json!({"hosts": ["c"]}));
+
+        assert_eq!(base, json!({"hosts": ["c"]}));
+    }
+}
@@ -0,0 +1,245 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{ScheduledChange, Tier, Variable};
+use crate::repositories::{ScheduledChangeRepository, TierRepository, VariableRepository};
+use crate::services::schemas::enforce_schema;
+use crate::services::versioning::{preserve_current, prune_versions, record_version};
+use crate::storage::VariableStore;
+use crate::utils::json_validator::calculate_json_size;
+use crate::utils::validate_json_data;
+
+/// Seconds between scheduler runs, from `SCHEDULER_INTERVAL_SECONDS` (default 15)
+fn scheduler_interval() -> Duration {
+    let seconds = std::env::var("SCHEDULER_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .filter(|&s: &u64| s > 0)
+        .unwrap_or(15);
+
+    Duration::from_secs(seconds)
+}
+
+/// Attempts at a change before an error that may go away fails it for good
+pub const MAX_SCHEDULED_ATTEMPTS: i32 = 8;
+
+/// Delay before retrying a change that failed `attempts` times: 30 seconds, doubled on
+/// each further failure up to an hour
+pub fn retry_delay(attempts: i32) -> Duration {
+    let seconds = 30u64.saturating_mul(1 << attempts.clamp(0, 16));
+    Duration::from_secs(seconds.min(60 * 60))
+}
+
+/// Whether `error` may go away on a later run, rather than rejecting the change for good
+fn is_transient(error: &AppError) -> bool {
+    matches!(error, AppError::Database(_) | AppError::Io(_) | AppError::Redis(_))
+}
+
+async fn remove_staged(storage: &impl VariableStore, change: &ScheduledChange) {
+    if let Err(e) = storage.delete(&change.storage_path).await {
+        tracing::warn!("Failed to delete data staged by change {}: {}", change.id, e);
+    }
+}
+
+/// Stage `data` to replace the data of `variable` at `apply_at`. The data is checked
+/// against the tier's size limit and the variable's schema now, and again when applied.
+#[allow(clippy::too_many_arguments)]
+pub async fn schedule_change(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    variable: &Variable,
+    tier: &Tier,
+    data: &Value,
+    apply_at: DateTime<Utc>,
+    message: Option<&str>,
+    created_by: Uuid,
+) -> Result<ScheduledChange> {
+    validate_json_data(data, tier.max_variable_size_mb)?;
+    enforce_schema(pool, variable, data).await?;
+
+    let id = Uuid::new_v4();
+    let storage_path = storage.store_scheduled(&variable.storage_path, id, data).await?;
+
+    let created = ScheduledChangeRepository::new(pool.clone())
+        .create(id, variable.id, &storage_path, apply_at, message, created_by)
+        .await;
+    if created.is_err()
+        && let Err(e) = storage.delete(&storage_path).await
+    {
+        tracing::warn!("Failed to delete data staged by change {}: {}", id, e);
+    }
+
+    created
+}
+
+/// Cancel a pending change and drop its staged data
+pub async fn cancel_change(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    variable_id: Uuid,
+    id: Uuid,
+) -> Result<ScheduledChange> {
+    let changes = ScheduledChangeRepository::new(pool.clone());
+
+    let Some(change) = changes.cancel(id, variable_id).await? else {
+        return Err(match changes.find(id, variable_id).await? {
+            Some(_) => AppError::Conflict("Only pending changes can be cancelled".to_string()),
+            None => AppError::NotFound("Scheduled change not found".to_string()),
+        });
+    };
+    remove_staged(storage, &change).await;
+
+    Ok(change)
+}
+
+/// Write a locked due change the way an update does: the staged data is checked against
+/// the tier and the variable's schema, the previous state is preserved and the write bumps
+/// the version and is recorded in the history. Returns the updated variable, the data it
+/// held before and its tier.
+async fn write_change(
+    conn: &mut PgConnection,
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    change: &ScheduledChange,
+) -> Result<(Variable, Value, Tier)> {
+    let variable = VariableRepository::lock_live_tx(conn, change.variable_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable was deleted or has expired".to_string()))?;
+    let tier = TierRepository::new(pool.clone())
+        .find_for_owner(variable.owner())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Tier not found".to_string()))?;
+
+    let data = storage.retrieve(&change.storage_path).await?;
+    validate_json_data(&data, tier.max_variable_size_mb)?;
+    enforce_schema(pool, &variable, &data).await?;
+    let size_bytes = calculate_json_size(&data) as i64;
+
+    let previous = storage.retrieve(&variable.storage_path).await?;
+    preserve_current(conn, storage, &variable, &previous).await?;
+    let updated = VariableRepository::update_tx(
+        conn,
+        variable.id,
+        variable.environment_id,
+        None,
+        Some(size_bytes),
+        None,
+        None,
+    )
+    .await?
+    .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+
+    let message = change.message.as_deref().unwrap_or("Scheduled change");
+    record_version(conn, storage, &updated, &data, change.created_by, Some(message)).await?;
+    ScheduledChangeRepository::mark_applied_tx(conn, change.id, updated.version).await?;
+    storage.update(&updated.storage_path, &data).await?;
+
+    Ok((updated, previous, tier))
+}
+
+/// Apply the pending change due soonest, if any, and return whether there was one. A
+/// change that can't be applied, such as one its variable's schema now rejects, is marked
+/// failed; one that hit an error that may go away is retried later with a backoff, and
+/// marked failed after `MAX_SCHEDULED_ATTEMPTS`. Servers running at the same time skip
+/// each other's changes.
+pub async fn apply_due_change(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+    let Some(change) = ScheduledChangeRepository::lock_due_tx(&mut tx).await? else {
+        return Ok(false);
+    };
+
+    let applied = match write_change(&mut tx, pool, storage, &change).await {
+        Ok((variable, previous, tier)) => match tx.commit().await {
+            Ok(()) => Ok((variable, tier)),
+            Err(e) => {
+                if let Err(e) = storage.update(&variable.storage_path, &previous).await {
+                    tracing::warn!("Failed to restore data of {}: {}", variable.storage_path, e);
+                }
+                Err(e.into())
+            }
+        },
+        Err(e) => {
+            drop(tx);
+            Err(e)
+        }
+    };
+
+    // Nothing was written unless the change was applied
+    match applied {
+        Ok((variable, tier)) => prune_versions(pool, storage, variable.id, &tier).await,
+        Err(e) if is_transient(&e) && change.attempts + 1 < MAX_SCHEDULED_ATTEMPTS => {
+            let delay = retry_delay(change.attempts);
+            tracing::warn!(
+                "Scheduled change {} failed, retrying in {}s: {}",
+                change.id,
+                delay.as_secs(),
+                e
+            );
+            let mut conn = pool.acquire().await?;
+            ScheduledChangeRepository::retry_tx(&mut conn, change.id, &e.to_string(), delay)
+                .await?;
+            return Ok(true);
+        }
+        Err(e) => {
+            tracing::warn!("Scheduled change {} failed: {}", change.id, e);
+            let mut conn = pool.acquire().await?;
+            ScheduledChangeRepository::mark_failed_tx(&mut conn, change.id, &e.to_string())
+                .await?;
+        }
+    }
+
+    remove_staged(storage, &change).await;
+    Ok(true)
+}
+
+/// Apply every due change and return how many were processed. Changes waiting to be
+/// retried are passed over, so one failing change doesn't hold up the rest.
+pub async fn apply_due_changes(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+) -> Result<usize> {
+    let mut processed = 0;
+    while apply_due_change(pool, storage).await? {
+        processed += 1;
+    }
+
+    Ok(processed)
+}
+
+/// Periodically apply due changes in the background
+pub fn spawn_scheduler<S: VariableStore + Clone + 'static>(pool: Pool<Postgres>, storage: S) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(scheduler_interval());
+
+        loop {
+            interval.tick().await;
+            match apply_due_changes(&pool, &storage).await {
+                Ok(0) => {}
+                Ok(applied) => tracing::info!("Processed {} scheduled changes", applied),
+                Err(e) => tracing::warn!("Failed to apply scheduled changes: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_backs_off_up_to_an_hour() {
+        assert_eq!(retry_delay(0), Duration::from_secs(30));
+        assert_eq!(retry_delay(1), Duration::from_secs(60));
+        assert_eq!(retry_delay(3), Duration::from_secs(240));
+        assert_eq!(retry_delay(MAX_SCHEDULED_ATTEMPTS), Duration::from_secs(60 * 60));
+        assert_eq!(retry_delay(i32::MAX), Duration::from_secs(60 * 60));
+    }
+}
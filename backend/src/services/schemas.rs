@@ -0,0 +1,134 @@
+use jsonschema::Validator;
+use serde_json::Value;
+use sqlx::{Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result, SchemaViolation};
+use crate::models::{Owner, Variable};
+use crate::repositories::JsonSchemaRepository;
+
+/// Compile a draft 2020-12 JSON Schema, rejecting documents that aren't valid schemas.
+/// References are only resolved within the schema itself.
+pub fn compile_schema(schema: &Value) -> Result<Validator> {
+    jsonschema::draft202012::new(schema)
+        .map_err(|e| AppError::BadRequest(format!("Invalid JSON Schema: {}", e)))
+}
+
+/// Every way `data` fails to conform to `validator`'s schema
+pub fn schema_violations(validator: &Validator, data: &Value) -> Vec<SchemaViolation> {
+    validator
+        .iter_errors(data)
+        .map(|e| SchemaViolation {
+            instance_path: e.instance_path.to_string(),
+            schema_path: e.schema_path.to_string(),
+            message: e.to_string(),
+        })
+        .collect()
+}
+
+/// Fail with the violations unless `data` conforms to `schema`
+pub fn check_schema(schema: &Value, data: &Value) -> Result<()> {
+    let violations = schema_violations(&compile_schema(schema)?, data);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::SchemaViolation(violations))
+    }
+}
+
+/// The schema a request asks data to conform to: one of `owner`'s named schemas, an
+/// inline schema, or none
+pub async fn requested_schema(
+    pool: &Pool<Postgres>,
+    owner: Owner,
+    schema_id: Option<Uuid>,
+    inline_schema: Option<&Value>,
+) -> Result<Option<Value>> {
+    match (schema_id, inline_schema) {
+        (Some(id), _) => JsonSchemaRepository::new(pool.clone())
+            .find_by_id(owner, id)
+            .await?
+            .map(|json_schema| Some(json_schema.schema))
+            .ok_or_else(|| AppError::BadRequest("Schema not found".to_string())),
+        (None, Some(schema)) => {
+            compile_schema(schema)?;
+            Ok(Some(schema.clone()))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// The schema `variable`'s data must conform to, if any
+pub async fn variable_schema(pool: &Pool<Postgres>, variable: &Variable) -> Result<Option<Value>> {
+    match (variable.schema_id, &variable.inline_schema) {
+        (Some(id), _) => JsonSchemaRepository::new(pool.clone())
+            .find_by_id(variable.owner(), id)
+            .await?
+            .map(|json_schema| Some(json_schema.schema))
+            .ok_or_else(|| AppError::NotFound("Schema not found".to_string())),
+        (None, inline_schema) => Ok(inline_schema.clone()),
+    }
+}
+
+/// Fail with the violations unless `data` conforms to `variable`'s schema
+pub async fn enforce_schema(
+    pool: &Pool<Postgres>,
+    variable: &Variable,
+    data: &Value,
+) -> Result<()> {
+    match variable_schema(pool, variable).await? {
+        Some(schema) => check_schema(&schema, data),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn service_schema() -> Value {
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": {
+                "timeout_ms": {"type": "integer", "minimum": 0},
+                "url": {"type": "string"}
+            },
+            "required": ["url"]
+        })
+    }
+
+    #[test]
+    fn test_conforming_data_passes() {
+        let data = json!({"url": "https://example.com", "timeout_ms": 500});
+        assert!(check_schema(&service_schema(), &data).is_ok());
+    }
+
+    #[test]
+    fn test_violations_are_listed() {
+        let data = json!({"timeout_ms": "slow"});
+
+        let Err(AppError::SchemaViolation(violations)) = check_schema(&service_schema(), &data)
+        else {
+            panic!("expected schema violations");
+        };
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().any(|v| v.instance_path == "/timeout_ms"));
+        assert!(violations.iter().any(|v| v.schema_path == "/required"));
+    }
+
+    #[test]
+    fn test_invalid_schema_is_rejected() {
+        assert!(matches!(
+            compile_schema(&json!({"type": 12})),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_external_references_are_not_fetched() {
+        let schema = json!({"$ref": "https://example.com/schema.json"});
+        assert!(compile_schema(&schema).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use serde_json::Value;
+use sqlx::{PgConnection, Pool, Postgres};
+
+use crate::error::Result;
+use crate::models::Variable;
+use crate::repositories::VariableRepository;
+use crate::storage::VariableStore;
+
+/// Most bytes of a variable's contents indexed for search. Postgres caps a tsvector at
+/// 1MB, and the start of a document is what matters for finding it.
+pub const MAX_INDEXED_CONTENT_BYTES: usize = 256 * 1024;
+
+/// Whether `SEARCH_INDEX_CONTENT` allows indexing variable contents (the default)
+pub fn content_indexing_enabled() -> bool {
+    std::env::var("SEARCH_INDEX_CONTENT")
+        .map(|v| !matches!(v.trim().to_ascii_lowercase().as_str(), "false" | "0" | "no"))
+        .unwrap_or(true)
+}
+
+/// The string leaves of `data`, one per line, cut to `MAX_INDEXED_CONTENT_BYTES`
+pub fn searchable_text(data: &Value) -> String {
+    fn collect(value: &Value, text: &mut String) {
+        match value {
+            Value::String(s) => {
+                text.push_str(s);
+                text.push('\n');
+            }
+            Value::Array(items) => items.iter().for_each(|item| collect(item, text)),
+            Value::Object(fields) => fields.values().for_each(|field| collect(field, text)),
+            _ => {}
+        }
+    }
+
+    let mut text = String::new();
+    collect(data, &mut text);
+
+    if text.len() > MAX_INDEXED_CONTENT_BYTES {
+        let mut end = MAX_INDEXED_CONTENT_BYTES;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
+
+/// Refresh the indexed contents of `variable` from `data`. Encrypted variables keep no
+/// indexed contents.
+pub async fn index_content(
+    conn: &mut PgConnection,
+    variable: &Variable,
+    data: &Value,
+) -> Result<()> {
+    let content = (!variable.is_encrypted && content_indexing_enabled())
+        .then(|| searchable_text(data));
+
+    VariableRepository::set_content_text_tx(conn, variable.id, content.as_deref()).await
+}
+
+/// Index the contents of variables written before contents were indexed and return how
+/// many were read. Variables whose data can't be read are left for the next run.
+pub async fn backfill_content(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+) -> Result<usize> {
+    const BATCH: i64 = 100;
+
+    if !content_indexing_enabled() {
+        return Ok(0);
+    }
+
+    let var_repo = VariableRepository::new(pool.clone());
+    let mut after = None;
+    let mut indexed = 0;
+
+    loop {
+        let variables = var_repo.list_unindexed(after, BATCH).await?;
+        let Some(last) = variables.last() else {
+            return Ok(indexed);
+        };
+        after = Some(last.id);
+
+        for variable in variables {
+            match storage.retrieve(&variable.storage_path).await {
+                Ok(data) => {
+                    let text = searchable_text(&data);
+                    var_repo
+                        .backfill_content_text(variable.id, variable.version, &text)
+                        .await?;
+                    indexed += 1;
+                }
+                Err(e) => tracing::warn!("Failed to index contents of {}: {}", variable.id, e),
+            }
+        }
+    }
+}
+
+/// Index the contents of variables written before contents were indexed, once, in the
+/// background
+pub fn spawn_content_backfill<S: VariableStore + Clone + 'static>(
+    pool: Pool<Postgres>,
+    storage: S,
+) {
+    tokio::spawn(async move {
+        match backfill_content(&pool, &storage).await {
+            Ok(0) => {}
+            Ok(indexed) => tracing::info!("Indexed the contents of {} variables", indexed),
+            Err(e) => tracing::warn!("Failed to index variable contents: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_searchable_text_collects_string_leaves() {
+        let data = json!({
+            "stripe": {"webhook_url": "https://example.com/hook", "retries": 3},
+            "regions": ["eu", "us"],
+            "enabled": true
+        });
+
+        let text = searchable_text(&data);
+        assert!(text.contains("https://example.com/hook"));
+        assert!(text.contains("eu\nus"));
+        assert!(!text.contains('3'));
+        assert!(!text.contains("true"));
+    }
+
+    #[test]
+    fn test_searchable_text_is_capped_on_a_char_boundary() {
+        let data = json!("é".repeat(MAX_INDEXED_CONTENT_BYTES));
+
+        let text = searchable_text(&data);
+        assert!(text.len() <= MAX_INDEXED_CONTENT_BYTES);
+        assert!(text.chars().all(|c| c == 'é'));
+    }
+}
@@ -0,0 +1,134 @@
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{Environment, Tier, Variable};
+use crate::policy::Action;
+use crate::repositories::VariableRepository;
+use crate::services::compensation::{revert, Undo};
+use crate::services::versioning::{preserve_current, prune_versions, record_version};
+use crate::storage::VariableStore;
+
+/// `tags` with `from` replaced by `to`, keeping the order and dropping duplicates
+pub fn renamed_tags(tags: &[String], from: &str, to: &str) -> Vec<String> {
+    let mut renamed: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = if tag == from { to } else { tag.as_str() };
+        if !renamed.iter().any(|t| t == tag) {
+            renamed.push(tag.to_string());
+        }
+    }
+    renamed
+}
+
+/// Rewrite the tags of every listed variable on `conn`, recording a version of each.
+async fn write_tags(
+    conn: &mut PgConnection,
+    storage: &impl VariableStore,
+    environment: &Environment,
+    author_id: Uuid,
+    message: &str,
+    changes: Vec<(Variable, Vec<String>)>,
+    undo: &mut Vec<Undo>,
+) -> Result<Vec<Variable>> {
+    let mut updated = Vec::with_capacity(changes.len());
+
+    for (current, tags) in changes {
+        let data = storage.retrieve(&current.storage_path).await?;
+        if let Some(version) = preserve_current(conn, storage, &current, &data).await? {
+            undo.push(Undo::Delete(version.storage_path));
+        }
+
+        let variable = VariableRepository::update_tx(
+            conn,
+            current.id,
+            environment.id,
+            None,
+            None,
+            Some(serde_json::json!(tags)),
+            None,
+        )
+        .await?
+        .ok_or_else(|| AppError::NotFound("Variable not found".to_string()))?;
+
+        let version =
+            record_version(conn, storage, &variable, &data, Some(author_id), Some(message))
+                .await?;
+        undo.push(Undo::Delete(version.storage_path));
+
+        updated.push(variable);
+    }
+
+    Ok(updated)
+}
+
+/// Replace the tags of every variable in the environment carrying `tag` with
+/// `retag(tags)`, in one transaction. Fails without changing anything if `authorize`
+/// rejects writing a variable with either its old or its new tags.
+#[allow(clippy::too_many_arguments)]
+pub async fn retag_environment(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    environment: &Environment,
+    tier: &Tier,
+    author_id: Uuid,
+    tag: &str,
+    message: &str,
+    retag: impl Fn(&[String]) -> Vec<String>,
+    authorize: impl Fn(Action, &str, Vec<String>) -> Result<()>,
+) -> Result<Vec<Variable>> {
+    let mut tx = pool.begin().await?;
+
+    let mut changes = Vec::new();
+    for variable in VariableRepository::lock_environment_tx(&mut tx, environment.id).await? {
+        let tags = variable.tag_list();
+        if !tags.iter().any(|t| t == tag) {
+            continue;
+        }
+
+        let retagged = retag(&tags);
+        authorize(Action::VariableWrite, &variable.key, tags)?;
+        authorize(Action::VariableWrite, &variable.key, retagged.clone())?;
+        changes.push((variable, retagged));
+    }
+
+    let mut undo = Vec::new();
+    let updated =
+        match write_tags(&mut tx, storage, environment, author_id, message, changes, &mut undo)
+            .await
+        {
+            Ok(updated) => updated,
+            Err(e) => {
+                drop(tx);
+                revert(storage, undo).await;
+                return Err(e);
+            }
+        };
+
+    if let Err(e) = tx.commit().await {
+        revert(storage, undo).await;
+        return Err(e.into());
+    }
+
+    for variable in &updated {
+        prune_versions(pool, storage, variable.id, tier).await;
+    }
+
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_renamed_tags() {
+        assert_eq!(renamed_tags(&tags(&["a", "b", "c"]), "b", "x"), tags(&["a", "x", "c"]));
+        assert_eq!(renamed_tags(&tags(&["a", "b"]), "a", "b"), tags(&["b"]));
+        assert_eq!(renamed_tags(&tags(&["a"]), "z", "x"), tags(&["a"]));
+    }
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+use crate::models::{Owner, Tier, Variable};
+use crate::repositories::{
+    ScheduledChangeRepository, VariableRepository, VariableVersionRepository,
+};
+use crate::services::expiry::purge_expired_key_tx;
+use crate::storage::VariableStore;
+
+/// Trashed variables purged per sweeper transaction
+const PURGE_BATCH_SIZE: i64 = 100;
+
+/// Retention is counted in days, so sweeping hourly is plenty
+const TRASH_SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+fn not_in_trash() -> AppError {
+    AppError::NotFound("Variable not found in trash".to_string())
+}
+
+/// Delete locked variables for good. Returns the storage paths of their versions and
+/// data, to remove once the transaction commits.
+pub async fn purge_tx(conn: &mut PgConnection, variables: &[Variable]) -> Result<Vec<String>> {
+    let mut paths = Vec::new();
+    for variable in variables {
+        // Versions and staged changes go before the data, which takes the shared data key
+        // with it
+        paths.extend(VariableVersionRepository::list_storage_paths_tx(conn, variable.id).await?);
+        paths.extend(ScheduledChangeRepository::list_pending_paths_tx(conn, variable.id).await?);
+        paths.push(variable.storage_path.clone());
+        VariableRepository::delete_tx(conn, variable.id).await?;
+    }
+
+    Ok(paths)
+}
+
+/// Remove the files of purged variables; a failure only leaves unreferenced files behind
+pub async fn remove_purged(storage: &impl VariableStore, paths: Vec<String>) {
+    for path in paths {
+        if let Err(e) = storage.delete(&path).await {
+            tracing::warn!("Failed to delete data of purged variable {}: {}", path, e);
+        }
+    }
+}
+
+/// Take a variable out of the trash of an environment, provided no live variable holds
+/// its key and the owner's tier has room for it.
+/// Fails if `authorize` rejects writing the variable.
+pub async fn restore_variable(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    owner: Owner,
+    tier: &Tier,
+    environment_id: Uuid,
+    id: Uuid,
+    authorize: impl Fn(&Variable) -> Result<()>,
+) -> Result<Variable> {
+    let var_repo = VariableRepository::new(pool.clone());
+    let mut tx = pool.begin().await?;
+    let variable = VariableRepository::lock_trashed_tx(&mut tx, id, environment_id)
+        .await?
+        .ok_or_else(not_in_trash)?;
+    authorize(&variable)?;
+
+    // An expired variable gives up its key
+    let purged = purge_expired_key_tx(&mut tx, environment_id, &variable.key).await?;
+
+    if var_repo.find_by_key(&variable.key, environment_id).await?.is_some() {
+        return Err(AppError::Conflict(format!(
+            "Variable key {} is in use; delete or rename that variable first",
+            variable.key
+        )));
+    }
+    if !tier.can_create_variable(var_repo.count_by_owner(owner).await?) {
+        return Err(AppError::TierLimitExceeded(format!(
+            "Maximum {} variables allowed",
+            tier.max_variables
+        )));
+    }
+
+    let variable = VariableRepository::restore_tx(&mut tx, id).await?;
+    tx.commit().await?;
+    remove_purged(storage, purged).await;
+
+    Ok(variable)
+}
+
+/// Delete a variable in the trash of an environment for good.
+/// Fails if `authorize` rejects deleting the variable.
+pub async fn purge_variable(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    environment_id: Uuid,
+    id: Uuid,
+    authorize: impl Fn(&Variable) -> Result<()>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    let variable = VariableRepository::lock_trashed_tx(&mut tx, id, environment_id)
+        .await?
+        .ok_or_else(not_in_trash)?;
+    authorize(&variable)?;
+
+    let paths = purge_tx(&mut tx, &[variable]).await?;
+    tx.commit().await?;
+    remove_purged(storage, paths).await;
+
+    Ok(())
+}
+
+/// Delete every variable in the trash of an environment for good and return how many
+/// there were. Fails, purging nothing, if `authorize` rejects deleting any of them.
+pub async fn purge_trash(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    environment_id: Uuid,
+    authorize: impl Fn(&Variable) -> Result<()>,
+) -> Result<usize> {
+    let mut tx = pool.begin().await?;
+    let trashed = VariableRepository::lock_trash_tx(&mut tx, environment_id).await?;
+    for variable in &trashed {
+        authorize(variable)?;
+    }
+
+    let paths = purge_tx(&mut tx, &trashed).await?;
+    tx.commit().await?;
+    remove_purged(storage, paths).await;
+
+    Ok(trashed.len())
+}
+
+/// Purge every trashed variable kept longer than its owner's tier retains it and return
+/// how many there were. Servers sweeping at the same time skip each other's variables.
+pub async fn sweep_trash(pool: &Pool<Postgres>, storage: &impl VariableStore) -> Result<usize> {
+    let mut swept = 0;
+
+    loop {
+        let mut tx = pool.begin().await?;
+        let purgeable = VariableRepository::lock_purgeable_tx(&mut tx, PURGE_BATCH_SIZE).await?;
+        let paths = purge_tx(&mut tx, &purgeable).await?;
+        tx.commit().await?;
+        remove_purged(storage, paths).await;
+
+        swept += purgeable.len();
+        if (purgeable.len() as i64) < PURGE_BATCH_SIZE {
+            return Ok(swept);
+        }
+    }
+}
+
+/// Periodically purge trashed variables past their retention in the background
+pub fn spawn_trash_sweeper<S: VariableStore + Clone + 'static>(pool: Pool<Postgres>, storage: S) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TRASH_SWEEP_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            match sweep_trash(&pool, &storage).await {
+                Ok(0) => {}
+                Ok(swept) => tracing::info!("Purged {} variables from the trash", swept),
+                Err(e) => tracing::warn!("Failed to purge the trash: {}", e),
+            }
+        }
+    });
+}
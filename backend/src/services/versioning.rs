@@ -0,0 +1,79 @@
+use serde_json::Value;
+use sqlx::{PgConnection, Pool, Postgres};
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::models::{Tier, Variable, VariableVersion};
+use crate::repositories::VariableVersionRepository;
+use crate::services::references::index_references;
+use crate::services::search::index_content;
+use crate::storage::VariableStore;
+
+/// Snapshot `variable` as it now stands, holding `data`, into its version history.
+/// Every write of a variable's data records a version, so this also refreshes the
+/// variable's indexed contents and the keys it references.
+pub async fn record_version(
+    conn: &mut PgConnection,
+    storage: &impl VariableStore,
+    variable: &Variable,
+    data: &Value,
+    author_id: Option<Uuid>,
+    message: Option<&str>,
+) -> Result<VariableVersion> {
+    let storage_path = storage
+        .store_version(&variable.storage_path, variable.version, data)
+        .await?;
+
+    index_content(conn, variable, data).await?;
+    index_references(conn, variable, data).await?;
+
+    VariableVersionRepository::create_tx(conn, variable, &storage_path, author_id, message).await
+}
+
+/// Snapshot the current state before overwriting it, unless history already holds it.
+/// Only variables written before history was kept lack their current version.
+pub async fn preserve_current(
+    conn: &mut PgConnection,
+    storage: &impl VariableStore,
+    variable: &Variable,
+    data: &Value,
+) -> Result<Option<VariableVersion>> {
+    if VariableVersionRepository::exists_tx(conn, variable.id, variable.version).await? {
+        return Ok(None);
+    }
+
+    record_version(conn, storage, variable, data, None, None)
+        .await
+        .map(Some)
+}
+
+/// Drop the versions `tier` doesn't retain, along with their files. Writes prune once they
+/// have committed, so a failure is only logged and the versions go on a later write.
+pub async fn prune_versions(
+    pool: &Pool<Postgres>,
+    storage: &impl VariableStore,
+    variable_id: Uuid,
+    tier: &Tier,
+) {
+    let Some(keep) = tier.version_retention() else {
+        return;
+    };
+
+    let pruned = match pool.acquire().await {
+        Ok(mut conn) => VariableVersionRepository::prune_tx(&mut conn, variable_id, keep).await,
+        Err(e) => Err(e.into()),
+    };
+    let paths = match pruned {
+        Ok(paths) => paths,
+        Err(e) => {
+            tracing::warn!("Failed to prune versions of {}: {}", variable_id, e);
+            return;
+        }
+    };
+
+    for path in paths {
+        if let Err(e) = storage.delete(&path).await {
+            tracing::warn!("Failed to delete data of pruned version {}: {}", path, e);
+        }
+    }
+}
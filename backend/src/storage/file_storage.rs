@@ -1,51 +1,166 @@
 use async_trait::async_trait;
 use serde_json::Value;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tokio::fs;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
+use crate::models::Owner;
 use crate::storage::VariableStore;
+use crate::utils::{is_sealed, DataKey, Keyring, WrappedKey};
 
+/// Stores variable data as JSON files. Data of encrypted variables is sealed with a data
+/// key of its own, kept beside it wrapped by a version of the master key.
 #[derive(Clone)]
 pub struct FileStorage {
     base_path: PathBuf,
+    keyring: Option<Arc<Keyring>>,
 }
 
 impl FileStorage {
     pub fn new(base_path: impl Into<PathBuf>) -> Self {
         Self {
             base_path: base_path.into(),
+            keyring: None,
         }
     }
 
+    /// Enable encrypted variables, wrapping their data keys with the `keyring`
+    pub fn with_keyring(mut self, keyring: Keyring) -> Self {
+        self.keyring = Some(Arc::new(keyring));
+        self
+    }
+
     pub async fn init(&self) -> Result<()> {
         fs::create_dir_all(&self.base_path).await?;
         Ok(())
     }
 
-    fn get_user_dir(&self, user_id: Uuid) -> PathBuf {
-        self.base_path.join(user_id.to_string())
+    fn relative_dir(owner: Owner, environment_id: Uuid) -> String {
+        format!("{}/{}", owner.storage_prefix(), environment_id)
+    }
+
+    /// Files kept for a variable live beside it under `{kind}/{file stem}/{name}.json`
+    fn sibling_path(storage_path: &str, kind: &str, name: &str) -> String {
+        let (dir, file) = storage_path.rsplit_once('/').unwrap_or(("", storage_path));
+        let key = file.strip_suffix(".json").unwrap_or(file);
+        format!("{}/{}/{}/{}.json", dir, kind, key, name)
+    }
+
+    /// Snapshots live under `.versions/{file stem}/{version}.json`
+    fn version_path(storage_path: &str, version: i32) -> String {
+        Self::sibling_path(storage_path, ".versions", &version.to_string())
+    }
+
+    /// Data staged to be written later lives under `.scheduled/{file stem}/{id}.json`
+    fn scheduled_path(storage_path: &str, id: Uuid) -> String {
+        Self::sibling_path(storage_path, ".scheduled", &id.simple().to_string())
+    }
+
+    /// The directory and file stem of the variable a version or staged file belongs to
+    fn sibling_owner(storage_path: &str) -> Option<(&str, &str)> {
+        let (dir, rest) = storage_path
+            .split_once("/.versions/")
+            .or_else(|| storage_path.split_once("/.scheduled/"))?;
+        Some((dir, rest.split_once('/').map_or(rest, |(key, _)| key)))
+    }
+
+    /// The data key of a variable, shared by its versions and staged data, lives under
+    /// `.keys/{file stem}.json`
+    fn key_path(storage_path: &str) -> String {
+        let (dir, key) = Self::sibling_owner(storage_path).unwrap_or_else(|| {
+            let (dir, file) = storage_path.rsplit_once('/').unwrap_or(("", storage_path));
+            (dir, file.strip_suffix(".json").unwrap_or(file))
+        });
+        format!("{}/.keys/{}.json", dir, key)
     }
 
-    fn get_variable_path(&self, user_id: Uuid, variable_key: &str) -> PathBuf {
-        self.get_user_dir(user_id).join(format!("{}.json", variable_key))
+    fn keyring(&self) -> Result<&Keyring> {
+        self.keyring
+            .as_deref()
+            .ok_or_else(|| AppError::BadRequest("Server-side encryption is not configured".to_string()))
+    }
+
+    async fn read_key_file(&self, storage_path: &str) -> Result<Option<WrappedKey>> {
+        let full_path = self.base_path.join(Self::key_path(storage_path));
+        if !full_path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(serde_json::from_slice(&fs::read(full_path).await?)?))
+    }
+
+    /// Replace the key file atomically: losing a data key loses the variable's data
+    async fn write_key_file(&self, storage_path: &str, wrapped: &WrappedKey) -> Result<()> {
+        let full_path = self.base_path.join(Self::key_path(storage_path));
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let temp_path = full_path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+        fs::write(&temp_path, serde_json::to_vec(wrapped)?).await?;
+        fs::rename(temp_path, full_path).await?;
+
+        Ok(())
+    }
+
+    /// The data key of the variable at `storage_path`, if its data is encrypted
+    async fn data_key(&self, storage_path: &str) -> Result<Option<DataKey>> {
+        match self.read_key_file(storage_path).await? {
+            Some(wrapped) => self.keyring()?.unwrap(&wrapped).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Give the variable at `storage_path` a new data key, wrapped by the current master key
+    async fn create_data_key(&self, storage_path: &str) -> Result<DataKey> {
+        let data_key = DataKey::generate();
+        let wrapped = self.keyring()?.wrap(&data_key);
+        self.write_key_file(storage_path, &wrapped).await?;
+
+        Ok(data_key)
+    }
+
+    /// Write `data` as pretty JSON, sealed when the variable has a data key
+    async fn write_data(&self, full_path: PathBuf, data: &Value, key: Option<&DataKey>) -> Result<()> {
+        let json = serde_json::to_vec_pretty(data)?;
+        let contents = match key {
+            Some(key) => key.seal(&json),
+            None => json,
+        };
+
+        fs::write(full_path, contents).await?;
+        Ok(())
     }
 }
 
 #[async_trait]
 impl VariableStore for FileStorage {
-    async fn store(&self, user_id: Uuid, variable_key: &str, data: &Value) -> Result<String> {
-        let user_dir = self.get_user_dir(user_id);
-        fs::create_dir_all(&user_dir).await?;
+    async fn store(
+        &self,
+        owner: Owner,
+        environment_id: Uuid,
+        variable_key: &str,
+        data: &Value,
+        encrypted: bool,
+    ) -> Result<String> {
+        let relative_dir = Self::relative_dir(owner, environment_id);
+        fs::create_dir_all(self.base_path.join(&relative_dir)).await?;
 
-        let file_path = self.get_variable_path(user_id, variable_key);
-        let json_string = serde_json::to_string_pretty(data)?;
+        // Relative path from base, unique so a trashed variable of the same key keeps its files
+        let storage_path =
+            format!("{}/{}.{}.json", relative_dir, variable_key, Uuid::new_v4().simple());
 
-        fs::write(&file_path, json_string).await?;
+        let data_key = if encrypted {
+            Some(self.create_data_key(&storage_path).await?)
+        } else {
+            None
+        };
+        self.write_data(self.base_path.join(&storage_path), data, data_key.as_ref())
+            .await?;
 
-        // Return relative path from base
-        Ok(format!("{}/{}.json", user_id, variable_key))
+        Ok(storage_path)
     }
 
     async fn retrieve(&self, storage_path: &str) -> Result<Value> {
@@ -55,12 +170,50 @@ impl VariableStore for FileStorage {
             return Err(AppError::NotFound("Variable data not found".to_string()));
         }
 
-        let contents = fs::read_to_string(full_path).await?;
-        let data: Value = serde_json::from_str(&contents)?;
+        let contents = fs::read(full_path).await?;
+        if !is_sealed(&contents) {
+            return Ok(serde_json::from_slice(&contents)?);
+        }
+
+        let data_key = self.data_key(storage_path).await?.ok_or_else(|| {
+            AppError::InternalServer(format!("Data key of {} is missing", storage_path))
+        })?;
+        let data: Value = serde_json::from_slice(&data_key.open(&contents)?)?;
 
         Ok(data)
     }
 
+    async fn store_version(
+        &self,
+        storage_path: &str,
+        version: i32,
+        data: &Value,
+    ) -> Result<String> {
+        let version_path = Self::version_path(storage_path, version);
+        let full_path = self.base_path.join(&version_path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let data_key = self.data_key(storage_path).await?;
+        self.write_data(full_path, data, data_key.as_ref()).await?;
+
+        Ok(version_path)
+    }
+
+    async fn store_scheduled(&self, storage_path: &str, id: Uuid, data: &Value) -> Result<String> {
+        let scheduled_path = Self::scheduled_path(storage_path, id);
+        let full_path = self.base_path.join(&scheduled_path);
+        if let Some(parent) = full_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let data_key = self.data_key(storage_path).await?;
+        self.write_data(full_path, data, data_key.as_ref()).await?;
+
+        Ok(scheduled_path)
+    }
+
     async fn update(&self, storage_path: &str, data: &Value) -> Result<()> {
         let full_path = self.base_path.join(storage_path);
 
@@ -68,8 +221,8 @@ impl VariableStore for FileStorage {
             return Err(AppError::NotFound("Variable data not found".to_string()));
         }
 
-        let json_string = serde_json::to_string_pretty(data)?;
-        fs::write(full_path, json_string).await?;
+        let data_key = self.data_key(storage_path).await?;
+        self.write_data(full_path, data, data_key.as_ref()).await?;
 
         Ok(())
     }
@@ -81,6 +234,15 @@ impl VariableStore for FileStorage {
             fs::remove_file(full_path).await?;
         }
 
+        // Versions and staged data are deleted with or before the variable, so its data key
+        // goes with it
+        if Self::sibling_owner(storage_path).is_none() {
+            let key_path = self.base_path.join(Self::key_path(storage_path));
+            if key_path.exists() {
+                fs::remove_file(key_path).await?;
+            }
+        }
+
         Ok(())
     }
 
@@ -88,6 +250,58 @@ impl VariableStore for FileStorage {
         let full_path = self.base_path.join(storage_path);
         Ok(full_path.exists())
     }
+
+    fn key_version(&self) -> Option<u32> {
+        self.keyring.as_ref().map(|keyring| keyring.current().version())
+    }
+
+    async fn data_key_version(&self, storage_path: &str) -> Result<Option<u32>> {
+        Ok(self.read_key_file(storage_path).await?.map(|wrapped| wrapped.key_version))
+    }
+
+    async fn rewrap_data_key(&self, storage_path: &str) -> Result<bool> {
+        let keyring = self.keyring()?;
+        let Some(wrapped) = self.read_key_file(storage_path).await? else {
+            return Ok(false);
+        };
+        if wrapped.key_version >= keyring.current().version() {
+            return Ok(false);
+        }
+
+        let data_key = keyring.unwrap(&wrapped)?;
+        self.write_key_file(storage_path, &keyring.wrap(&data_key)).await?;
+
+        Ok(true)
+    }
+
+    async fn encrypt_existing(&self, storage_path: &str, version_paths: &[String]) -> Result<bool> {
+        // A variable deleted meanwhile must not be left with a data key
+        if !self.base_path.join(storage_path).exists() {
+            return Ok(false);
+        }
+
+        let data_key = match self.data_key(storage_path).await? {
+            Some(data_key) => data_key,
+            None => self.create_data_key(storage_path).await?,
+        };
+
+        let mut encrypted = false;
+        for path in std::iter::once(storage_path).chain(version_paths.iter().map(String::as_str)) {
+            let full_path = self.base_path.join(path);
+            if !full_path.exists() {
+                continue;
+            }
+
+            let contents = fs::read(&full_path).await?;
+            if !is_sealed(&contents) {
+                let data: Value = serde_json::from_slice(&contents)?;
+                self.write_data(full_path, &data, Some(&data_key)).await?;
+                encrypted = true;
+            }
+        }
+
+        Ok(encrypted)
+    }
 }
 
 #[cfg(test)]
@@ -102,12 +316,12 @@ mod tests {
         let storage = FileStorage::new(temp_dir.path());
         storage.init().await.unwrap();
 
-        let user_id = Uuid::new_v4();
+        let owner = Owner::User(Uuid::new_v4());
         let variable_key = "test_var";
         let data = json!({"key": "value", "number": 42});
 
         // Store
-        let path = storage.store(user_id, variable_key, &data).await.unwrap();
+        let path = storage.store(owner, Uuid::new_v4(), variable_key, &data, false).await.unwrap();
         assert!(storage.exists(&path).await.unwrap());
 
         // Retrieve
@@ -124,4 +338,24 @@ mod tests {
         storage.delete(&path).await.unwrap();
         assert!(!storage.exists(&path).await.unwrap());
     }
+
+    #[test]
+    fn test_version_path() {
+        assert_eq!(
+            FileStorage::version_path("users/u/env/db.host.json", 3),
+            "users/u/env/.versions/db.host/3.json"
+        );
+    }
+
+    #[test]
+    fn test_sibling_paths_share_the_data_key() {
+        let id = Uuid::new_v4();
+        let scheduled = FileStorage::scheduled_path("users/u/env/db.host.json", id);
+        assert_eq!(scheduled, format!("users/u/env/.scheduled/db.host/{}.json", id.simple()));
+
+        let key_path = "users/u/env/.keys/db.host.json";
+        assert_eq!(FileStorage::key_path("users/u/env/db.host.json"), key_path);
+        assert_eq!(FileStorage::key_path("users/u/env/.versions/db.host/3.json"), key_path);
+        assert_eq!(FileStorage::key_path(&scheduled), key_path);
+    }
 }
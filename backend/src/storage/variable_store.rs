@@ -1,18 +1,36 @@
 use async_trait::async_trait;
 use serde_json::Value;
 use uuid::Uuid;
-
 use crate::error::Result;
+use crate::models::Owner;
 
 #[async_trait]
 pub trait VariableStore: Send + Sync {
-    /// Store variable data and return the storage path
-    async fn store(&self, user_id: Uuid, variable_key: &str, data: &Value) -> Result<String>;
+    /// Store variable data under the owner's environment and return the storage path.
+    /// Data of `encrypted` variables, and everything later written for them, is
+    /// encrypted at rest.
+    async fn store(
+        &self,
+        owner: Owner,
+        environment_id: Uuid,
+        variable_key: &str,
+        data: &Value,
+        encrypted: bool,
+    ) -> Result<String>;
 
-    /// Retrieve variable data from storage
+    /// Retrieve variable data from storage, decrypted
     async fn retrieve(&self, storage_path: &str) -> Result<Value>;
 
-    /// Update variable data at the given storage path
+    /// Store an immutable snapshot of the variable at `storage_path` as `version`
+    /// and return the snapshot's storage path
+    async fn store_version(&self, storage_path: &str, version: i32, data: &Value)
+        -> Result<String>;
+
+    /// Stage data to be written to the variable at `storage_path` later, encrypted if the
+    /// variable is, and return the staged data's storage path
+    async fn store_scheduled(&self, storage_path: &str, id: Uuid, data: &Value) -> Result<String>;
+
+    /// Update variable data at the given storage path, encrypted if the variable is
     async fn update(&self, storage_path: &str, data: &Value) -> Result<()>;
 
     /// Delete variable data from storage
@@ -20,4 +38,19 @@ pub trait VariableStore: Send + Sync {
 
     /// Check if data exists at the storage path
     async fn exists(&self, storage_path: &str) -> Result<bool>;
+
+    /// Version of the master key new data keys are wrapped by, if encryption is configured
+    fn key_version(&self) -> Option<u32>;
+
+    /// Version of the master key wrapping the data key of the variable at `storage_path`,
+    /// or `None` if its data is not encrypted
+    async fn data_key_version(&self, storage_path: &str) -> Result<Option<u32>>;
+
+    /// Re-wrap the data key of the variable at `storage_path` under the current master key.
+    /// Returns whether it was wrapped by an older version.
+    async fn rewrap_data_key(&self, storage_path: &str) -> Result<bool>;
+
+    /// Encrypt data written in plain for a variable since marked encrypted, including its
+    /// version snapshots and staged data. Returns whether any of it was still plain.
+    async fn encrypt_existing(&self, storage_path: &str, version_paths: &[String]) -> Result<bool>;
 }
@@ -0,0 +1,339 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, Result};
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// Prefix of sealed data, which can't be mistaken for the start of a JSON document
+pub const SEALED_PREFIX: &[u8] = b"CVSEAL1\n";
+
+fn decryption_failed() -> AppError {
+    // Never include the ciphertext or key material in the error
+    AppError::InternalServer("Failed to decrypt variable data".to_string())
+}
+
+fn parse_key(hex_key: &str) -> Result<[u8; KEY_LEN]> {
+    hex::decode(hex_key.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            AppError::InternalServer("Master key must be 32 bytes, hex encoded".to_string())
+        })
+}
+
+/// The key-encryption key wrapping the data keys of encrypted variables
+#[derive(Clone)]
+pub struct MasterKey {
+    version: u32,
+    cipher: Aes256Gcm,
+}
+
+impl fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MasterKey").field("version", &self.version).finish_non_exhaustive()
+    }
+}
+
+impl MasterKey {
+    pub fn new(version: u32, key: [u8; KEY_LEN]) -> Self {
+        Self {
+            version,
+            cipher: Aes256Gcm::new(&Key::<Aes256Gcm>::from(key)),
+        }
+    }
+
+    pub fn from_hex(version: u32, hex_key: &str) -> Result<Self> {
+        Ok(Self::new(version, parse_key(hex_key)?))
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn wrap(&self, data_key: &DataKey) -> WrappedKey {
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let wrapped = self
+            .cipher
+            .encrypt(&nonce, data_key.key.as_slice())
+            .expect("AES-GCM encrypts inputs of any practical length");
+
+        WrappedKey {
+            key_version: self.version,
+            nonce: hex::encode(nonce),
+            wrapped_key: hex::encode(wrapped),
+        }
+    }
+
+    pub fn unwrap(&self, wrapped: &WrappedKey) -> Result<DataKey> {
+        if wrapped.key_version != self.version {
+            return Err(AppError::InternalServer(format!(
+                "Data key is wrapped by unknown master key version {}",
+                wrapped.key_version
+            )));
+        }
+
+        let nonce: [u8; NONCE_LEN] = hex::decode(&wrapped.nonce)
+            .ok()
+            .and_then(|nonce| nonce.try_into().ok())
+            .ok_or_else(decryption_failed)?;
+        let ciphertext = hex::decode(&wrapped.wrapped_key).map_err(|_| decryption_failed())?;
+
+        let key = self
+            .cipher
+            .decrypt(&Nonce::from(nonce), ciphertext.as_slice())
+            .map_err(|_| decryption_failed())?;
+
+        Ok(DataKey {
+            key: key.try_into().map_err(|_| decryption_failed())?,
+        })
+    }
+}
+
+/// Every configured version of the master key. New data keys are wrapped by the newest
+/// version; data keys wrapped by older versions stay readable until they are re-wrapped.
+#[derive(Clone, Debug)]
+pub struct Keyring {
+    keys: BTreeMap<u32, MasterKey>,
+}
+
+impl Keyring {
+    pub fn new(keys: impl IntoIterator<Item = MasterKey>) -> Result<Self> {
+        let keys: BTreeMap<u32, MasterKey> =
+            keys.into_iter().map(|key| (key.version, key)).collect();
+        if keys.is_empty() {
+            return Err(AppError::InternalServer("The keyring has no master key".to_string()));
+        }
+
+        Ok(Self { keys })
+    }
+
+    /// Parse one master key per line or comma: `version:hex`, or a bare hex key as version 1
+    pub fn parse(text: &str) -> Result<Self> {
+        let keys = text
+            .split([',', '\n'])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty() && !entry.starts_with('#'))
+            .map(|entry| match entry.split_once(':') {
+                Some((version, hex_key)) => {
+                    let version = version.trim().parse().map_err(|_| {
+                        AppError::InternalServer(format!("Invalid master key version {}", version))
+                    })?;
+                    MasterKey::from_hex(version, hex_key)
+                }
+                None => MasterKey::from_hex(1, entry),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Self::new(keys)
+    }
+
+    /// Use `ENCRYPTION_MASTER_KEY` (see `parse`), or else the keys in `ENCRYPTION_KEY_FILE`,
+    /// generating that file on first use. `None` when neither is configured.
+    pub fn from_env() -> Result<Option<Self>> {
+        if let Ok(keys) = std::env::var("ENCRYPTION_MASTER_KEY") {
+            return Self::parse(&keys).map(Some);
+        }
+
+        match std::env::var("ENCRYPTION_KEY_FILE") {
+            Ok(path) => Self::from_key_file(Path::new(&path)).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Read the keys in `path`, creating it with a random version 1 key, readable only by
+    /// its owner, if it doesn't exist
+    pub fn from_key_file(path: &Path) -> Result<Self> {
+        if path.exists() {
+            return Self::parse(&std::fs::read_to_string(path)?);
+        }
+
+        let key: [u8; KEY_LEN] = Aes256Gcm::generate_key(OsRng).into();
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let line = format!("1:{}\n", hex::encode(key));
+        std::io::Write::write_all(&mut options.open(path)?, line.as_bytes())?;
+
+        Self::new([MasterKey::new(1, key)])
+    }
+
+    /// The master key new data keys are wrapped by
+    pub fn current(&self) -> &MasterKey {
+        self.keys.values().next_back().expect("a keyring is never empty")
+    }
+
+    pub fn versions(&self) -> Vec<u32> {
+        self.keys.keys().copied().collect()
+    }
+
+    pub fn wrap(&self, data_key: &DataKey) -> WrappedKey {
+        self.current().wrap(data_key)
+    }
+
+    /// Unwrap with whichever master key version wrapped the data key
+    pub fn unwrap(&self, wrapped: &WrappedKey) -> Result<DataKey> {
+        let master_key = self.keys.get(&wrapped.key_version).ok_or_else(|| {
+            AppError::InternalServer(format!(
+                "Data key is wrapped by unknown master key version {}",
+                wrapped.key_version
+            ))
+        })?;
+
+        master_key.unwrap(wrapped)
+    }
+}
+
+/// A data key as stored: encrypted under a version of the master key
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WrappedKey {
+    pub key_version: u32,
+    pub nonce: String,
+    pub wrapped_key: String,
+}
+
+/// The AES-256-GCM key sealing one variable's data and its versions
+pub struct DataKey {
+    key: [u8; KEY_LEN],
+}
+
+impl fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataKey").finish_non_exhaustive()
+    }
+}
+
+impl DataKey {
+    pub fn generate() -> Self {
+        Self {
+            key: Aes256Gcm::generate_key(OsRng).into(),
+        }
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&Key::<Aes256Gcm>::from(self.key))
+    }
+
+    /// `SEALED_PREFIX`, a random nonce and the ciphertext of `plaintext`
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = Aes256Gcm::generate_nonce(OsRng);
+        let ciphertext = self
+            .cipher()
+            .encrypt(&nonce, plaintext)
+            .expect("AES-GCM encrypts inputs of any practical length");
+
+        [SEALED_PREFIX, &nonce[..], &ciphertext[..]].concat()
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let body = sealed.strip_prefix(SEALED_PREFIX).ok_or_else(decryption_failed)?;
+        let (nonce, ciphertext) = body.split_at_checked(NONCE_LEN).ok_or_else(decryption_failed)?;
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| decryption_failed())?;
+
+        self.cipher()
+            .decrypt(&Nonce::from(nonce), ciphertext)
+            .map_err(|_| decryption_failed())
+    }
+}
+
+impl Drop for DataKey {
+    fn drop(&mut self) {
+        self.key.fill(0);
+    }
+}
+
+/// Whether stored bytes were sealed by a data key rather than written as plain JSON
+pub fn is_sealed(bytes: &[u8]) -> bool {
+    bytes.starts_with(SEALED_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_open() {
+        let data_key = DataKey::generate();
+        let sealed = data_key.seal(br#"{"password":"hunter2"}"#);
+
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.windows(7).any(|w| w == b"hunter2"));
+        assert_eq!(data_key.open(&sealed).unwrap(), br#"{"password":"hunter2"}"#);
+
+        assert!(DataKey::generate().open(&sealed).is_err());
+        assert!(!is_sealed(br#"{"password":"hunter2"}"#));
+    }
+
+    #[test]
+    fn test_wrap_and_unwrap() {
+        let master_key = MasterKey::new(1, [7; KEY_LEN]);
+        let data_key = DataKey::generate();
+        let sealed = data_key.seal(b"42");
+
+        let wrapped = master_key.wrap(&data_key);
+        assert_eq!(wrapped.key_version, 1);
+        let unwrapped = master_key.unwrap(&wrapped).unwrap();
+        assert_eq!(unwrapped.open(&sealed).unwrap(), b"42");
+
+        assert!(MasterKey::new(1, [8; KEY_LEN]).unwrap(&wrapped).is_err());
+        assert!(MasterKey::new(2, [7; KEY_LEN]).unwrap(&wrapped).is_err());
+    }
+
+    #[test]
+    fn test_master_key_from_hex() {
+        assert!(MasterKey::from_hex(1, &"ab".repeat(KEY_LEN)).is_ok());
+        assert!(MasterKey::from_hex(1, "abcd").is_err());
+        assert!(MasterKey::from_hex(1, "not hex").is_err());
+    }
+
+    #[test]
+    fn test_keyring_parse() {
+        let (v1, v2) = ("ab".repeat(KEY_LEN), "cd".repeat(KEY_LEN));
+
+        let legacy = Keyring::parse(&v1).unwrap();
+        assert_eq!(legacy.versions(), vec![1]);
+
+        let keyring = Keyring::parse(&format!("# rotated\n2:{}\n1:{}\n", v2, v1)).unwrap();
+        assert_eq!(keyring.versions(), vec![1, 2]);
+        assert_eq!(keyring.current().version(), 2);
+        assert_eq!(Keyring::parse(&format!("1:{}, 3:{}", v1, v2)).unwrap().current().version(), 3);
+
+        assert!(Keyring::parse("").is_err());
+        assert!(Keyring::parse(&format!("x:{}", v1)).is_err());
+    }
+
+    #[test]
+    fn test_keyring_unwraps_every_version() {
+        let old = Keyring::new([MasterKey::new(1, [7; KEY_LEN])]).unwrap();
+        let rotated =
+            Keyring::new([MasterKey::new(1, [7; KEY_LEN]), MasterKey::new(2, [9; KEY_LEN])])
+                .unwrap();
+
+        let data_key = DataKey::generate();
+        let wrapped_v1 = old.wrap(&data_key);
+        assert!(rotated.unwrap(&wrapped_v1).is_ok());
+
+        let wrapped_v2 = rotated.wrap(&data_key);
+        assert_eq!(wrapped_v2.key_version, 2);
+        assert!(old.unwrap(&wrapped_v2).is_err());
+    }
+
+    #[test]
+    fn test_key_file_is_generated_once() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("master.key");
+
+        let created = Keyring::from_key_file(&path).unwrap();
+        let loaded = Keyring::from_key_file(&path).unwrap();
+
+        let data_key = DataKey::generate();
+        assert!(loaded.unwrap(&created.wrap(&data_key)).is_ok());
+    }
+}
@@ -0,0 +1,69 @@
+use uuid::Uuid;
+
+use crate::models::Variable;
+
+/// Strong entity tag of a variable's current version
+pub fn variable_etag(variable: &Variable) -> String {
+    format!("\"{}-{}\"", variable.id, variable.version)
+}
+
+/// Whether an `If-None-Match` header value matches `etag`, using weak comparison
+pub fn etag_matches(header: &str, etag: &str) -> bool {
+    header.split(',').map(str::trim).any(|tag| {
+        tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag
+    })
+}
+
+/// Versions of variable `id` an `If-Match` header value accepts, or `None` when it
+/// accepts any (`*`). Weak tags never match, as `If-Match` requires strong comparison.
+pub fn if_match_versions(header: &str, id: Uuid) -> Option<Vec<i32>> {
+    let mut versions = Vec::new();
+
+    for tag in header.split(',').map(str::trim) {
+        if tag == "*" {
+            return None;
+        }
+
+        let parsed = tag
+            .strip_prefix('"')
+            .and_then(|t| t.strip_suffix('"'))
+            .and_then(|t| t.rsplit_once('-'))
+            .filter(|(tag_id, _)| *tag_id == id.to_string())
+            .and_then(|(_, version)| version.parse().ok());
+        if let Some(version) = parsed {
+            versions.push(version);
+        }
+    }
+
+    Some(versions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_matches() {
+        assert!(etag_matches("\"a-1\"", "\"a-1\""));
+        assert!(etag_matches("\"a-0\", W/\"a-1\"", "\"a-1\""));
+        assert!(etag_matches("*", "\"a-1\""));
+        assert!(!etag_matches("\"a-2\"", "\"a-1\""));
+    }
+
+    #[test]
+    fn test_if_match_versions() {
+        let id = Uuid::new_v4();
+        let other = Uuid::new_v4();
+
+        assert_eq!(if_match_versions("*", id), None);
+        assert_eq!(
+            if_match_versions(&format!("\"{id}-3\", \"{id}-5\""), id),
+            Some(vec![3, 5])
+        );
+        // Weak tags, tags of other variables and garbage match nothing
+        assert_eq!(
+            if_match_versions(&format!("W/\"{id}-3\", \"{other}-4\", junk"), id),
+            Some(vec![])
+        );
+    }
+}
@@ -70,7 +70,7 @@ mod tests {
     #[test]
     fn test_extract_key_prefix() {
         let key = "cv_abcdefgh123456789";
-        let prefix = extract_key_prefix(&key);
+        let prefix = extract_key_prefix(key);
         assert_eq!(prefix, "cv_abcdefgh");
     }
 }
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::models::UserRole;
+use crate::models::{ApiKey, User, UserRole};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
@@ -14,6 +14,18 @@ pub struct Claims {
     pub tier_id: String,  // Tier ID
     pub exp: i64,         // Expiration time
     pub iat: i64,         // Issued at
+    /// Set when the request was authenticated with an API key rather than a JWT
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key_id: Option<Uuid>,
+    /// Organization owning the API key, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization_id: Option<Uuid>,
+    /// Environment the API key is restricted to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment_id: Option<Uuid>,
+    /// Scopes of the API key, checked by policies
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scopes: Vec<String>,
 }
 
 impl Claims {
@@ -28,9 +40,28 @@ impl Claims {
             tier_id: tier_id.to_string(),
             exp: expiration.timestamp(),
             iat: now.timestamp(),
+            api_key_id: None,
+            organization_id: None,
+            environment_id: None,
+            scopes: Vec::new(),
         }
     }
 
+    /// Build request claims for a caller authenticated with one of their API keys. A key
+    /// never carries its creator's staff or admin role.
+    pub fn for_api_key(user: &User, api_key: &ApiKey) -> Self {
+        let mut claims = Self::new(user.id, user.email.clone(), UserRole::User, user.tier_id, 1);
+        claims.api_key_id = Some(api_key.id);
+        claims.organization_id = api_key.organization_id;
+        claims.environment_id = api_key.environment_id;
+        claims.scopes = api_key.scopes();
+        claims
+    }
+
+    pub fn is_api_key(&self) -> bool {
+        self.api_key_id.is_some()
+    }
+
     pub fn user_id(&self) -> Result<Uuid> {
         Uuid::parse_str(&self.sub)
             .map_err(|_| AppError::Authentication("Invalid user ID in token".to_string()))
@@ -73,7 +104,7 @@ impl JwtConfig {
             &claims,
             &EncodingKey::from_secret(self.secret.as_bytes()),
         )
-        .map_err(|e| AppError::Jwt(e))
+        .map_err(AppError::Jwt)
     }
 
     pub fn verify_token(&self, token: &str) -> Result<Claims> {
@@ -83,7 +114,7 @@ impl JwtConfig {
             &Validation::default(),
         )
         .map(|data| data.claims)
-        .map_err(|e| AppError::Jwt(e))
+        .map_err(AppError::Jwt)
     }
 }
 
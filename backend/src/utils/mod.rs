@@ -1,9 +1,15 @@
+pub mod encryption;
+pub mod etag;
 pub mod hash;
 pub mod jwt;
 pub mod json_validator;
+pub mod signing;
 pub mod validation;
 
+pub use encryption::*;
+pub use etag::*;
 pub use hash::*;
 pub use jwt::*;
 pub use json_validator::*;
+pub use signing::*;
 pub use validation::*;
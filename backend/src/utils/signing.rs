@@ -0,0 +1,65 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Signs and verifies share link URLs with HMAC-SHA256
+pub struct LinkSigner {
+    secret: Vec<u8>,
+}
+
+impl LinkSigner {
+    pub fn new(secret: impl Into<Vec<u8>>) -> Self {
+        Self {
+            secret: secret.into(),
+        }
+    }
+
+    /// Use `SHARE_LINK_SECRET`, falling back to the JWT secret
+    pub fn from_env() -> Self {
+        let secret = std::env::var("SHARE_LINK_SECRET")
+            .or_else(|_| std::env::var("JWT_SECRET"))
+            .unwrap_or_else(|_| "default-secret-change-in-production".to_string());
+
+        Self::new(secret)
+    }
+
+    fn mac(&self, link_id: Uuid, expires: i64) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(format!("{}.{}", link_id, expires).as_bytes());
+        mac
+    }
+
+    /// Hex signature over the link id and its expiry (Unix seconds)
+    pub fn sign(&self, link_id: Uuid, expires: i64) -> String {
+        hex::encode(self.mac(link_id, expires).finalize().into_bytes())
+    }
+
+    /// Check a signature in constant time
+    pub fn verify(&self, link_id: Uuid, expires: i64, signature: &str) -> bool {
+        match hex::decode(signature) {
+            Ok(bytes) => self.mac(link_id, expires).verify_slice(&bytes).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = LinkSigner::new("secret");
+        let link_id = Uuid::new_v4();
+        let signature = signer.sign(link_id, 1_700_000_000);
+
+        assert!(signer.verify(link_id, 1_700_000_000, &signature));
+        assert!(!signer.verify(link_id, 1_700_000_001, &signature));
+        assert!(!signer.verify(Uuid::new_v4(), 1_700_000_000, &signature));
+        assert!(!LinkSigner::new("other").verify(link_id, 1_700_000_000, &signature));
+        assert!(!signer.verify(link_id, 1_700_000_000, "not-hex"));
+    }
+}
@@ -38,6 +38,29 @@ pub fn validate_variable_key(key: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Validate a project or environment name, which appears as a URL path segment
+pub fn validate_namespace_name(name: &str) -> Result<(), ValidationError> {
+    if name.is_empty() {
+        return Err(ValidationError::new("Name cannot be empty"));
+    }
+
+    if name.len() > 100 {
+        return Err(ValidationError::new("Name cannot exceed 100 characters"));
+    }
+
+    // Only allow alphanumeric, underscore and hyphen
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        return Err(ValidationError::new(
+            "Name can only contain alphanumeric characters, underscore, and hyphen",
+        ));
+    }
+
+    Ok(())
+}
+
 pub fn validate_api_key_name(name: &str) -> Result<(), ValidationError> {
     if name.is_empty() {
         return Err(ValidationError::new("API key name cannot be empty"));
@@ -50,6 +73,32 @@ pub fn validate_api_key_name(name: &str) -> Result<(), ValidationError> {
     Ok(())
 }
 
+/// Validate API key scopes: an array of up to 20 names such as `pii:read`
+pub fn validate_api_key_scopes(scopes: &serde_json::Value) -> Result<(), ValidationError> {
+    let scopes = scopes
+        .as_array()
+        .ok_or_else(|| ValidationError::new("API key scopes must be an array"))?;
+
+    if scopes.len() > 20 {
+        return Err(ValidationError::new("An API key can have at most 20 scopes"));
+    }
+
+    let valid = |scope: &str| {
+        !scope.is_empty()
+            && scope.len() <= 100
+            && scope
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+    };
+    if !scopes.iter().all(|s| s.as_str().is_some_and(valid)) {
+        return Err(ValidationError::new(
+            "API key scopes can only contain alphanumeric characters, underscore, hyphen, dot, and colon",
+        ));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -70,10 +119,29 @@ mod tests {
         assert!(validate_variable_key("invalid key!").is_err());
     }
 
+    #[test]
+    fn test_validate_namespace_name() {
+        assert!(validate_namespace_name("prod-eu").is_ok());
+        assert!(validate_namespace_name("").is_err());
+        assert!(validate_namespace_name("prod.eu").is_err());
+        assert!(validate_namespace_name("a/b").is_err());
+    }
+
     #[test]
     fn test_validate_api_key_name() {
         assert!(validate_api_key_name("My API Key").is_ok());
         assert!(validate_api_key_name("").is_err());
         assert!(validate_api_key_name(&"x".repeat(101)).is_err());
     }
+
+    #[test]
+    fn test_validate_api_key_scopes() {
+        use serde_json::json;
+
+        assert!(validate_api_key_scopes(&json!(["pii:read", "deploy"])).is_ok());
+        assert!(validate_api_key_scopes(&json!([])).is_ok());
+        assert!(validate_api_key_scopes(&json!("pii:read")).is_err());
+        assert!(validate_api_key_scopes(&json!(["pii read"])).is_err());
+        assert!(validate_api_key_scopes(&json!([1])).is_err());
+    }
 }
@@ -1,3 +1,5 @@
+#![allow(dead_code)]
+
 use cloud_variables::db::create_pool;
 use sqlx::{Pool, Postgres};
 use std::env;
@@ -22,7 +24,7 @@ pub async fn setup_test_db() -> Pool<Postgres> {
 
 /// Clean up test database
 pub async fn cleanup_test_db(pool: &Pool<Postgres>) {
-    sqlx::query("TRUNCATE users, tiers, variables, api_keys, usage_stats, promotion_history CASCADE")
+    sqlx::query("TRUNCATE users, tiers, organizations, variables, api_keys, usage_stats, promotion_history CASCADE")
         .execute(pool)
         .await
         .ok();
@@ -61,7 +61,12 @@ mod auth_dto_tests {
 
 #[cfg(test)]
 mod variable_dto_tests {
-    use cloud_variables::dto::{CreateVariableRequest, UpdateVariableRequest, VariableQueryParams};
+    use cloud_variables::dto::{
+        AtomicOperation, AtomicRequest, CreateVariableRequest, GetVariableParams,
+        ScheduleChangeRequest, TrashItem, UpdateVariableRequest, UpsertVariableRequest,
+        VariableQueryParams,
+    };
+    use cloud_variables::models::{TagMatch, TrashedVariable};
     use serde_json::json;
     use validator::Validate;
 
@@ -73,6 +78,11 @@ mod variable_dto_tests {
             data: json!({"test": true}),
             tags: Some(vec!["tag1".to_string(), "tag2".to_string()]),
             is_encrypted: false,
+            message: None,
+            schema_id: None,
+            schema: None,
+            expires_at: None,
+            ttl_seconds: None,
         };
 
         assert!(request.validate().is_ok());
@@ -86,6 +96,11 @@ mod variable_dto_tests {
             data: json!({}),
             tags: None,
             is_encrypted: false,
+            message: None,
+            schema_id: None,
+            schema: None,
+            expires_at: None,
+            ttl_seconds: None,
         };
 
         let result = request.validate();
@@ -100,6 +115,11 @@ mod variable_dto_tests {
             data: json!({}),
             tags: None,
             is_encrypted: false,
+            message: None,
+            schema_id: None,
+            schema: None,
+            expires_at: None,
+            ttl_seconds: None,
         };
 
         let result = request.validate();
@@ -112,6 +132,9 @@ mod variable_dto_tests {
             description: Some("Updated description".to_string()),
             data: Some(json!({"updated": true})),
             tags: Some(vec!["new_tag".to_string()]),
+            message: Some("Point at the new cluster".to_string()),
+            expires_at: None,
+            ttl_seconds: None,
         };
 
         assert!(request.validate().is_ok());
@@ -123,18 +146,186 @@ mod variable_dto_tests {
             description: None,
             data: None,
             tags: None,
+            message: None,
+            expires_at: None,
+            ttl_seconds: None,
         };
 
         assert!(request.validate().is_ok());
     }
 
+    #[test]
+    fn test_update_variable_request_message_too_long() {
+        let request = UpdateVariableRequest {
+            description: None,
+            data: None,
+            tags: None,
+            message: Some("a".repeat(501)),
+            expires_at: None,
+            ttl_seconds: None,
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_variable_request_expiry() {
+        let now = chrono::Utc::now();
+
+        let request: CreateVariableRequest =
+            serde_json::from_value(json!({"key": "incident.flag", "data": true, "ttl_seconds": 60}))
+                .unwrap();
+        assert!(request.validate().is_ok());
+        assert_eq!(request.expiry(now), Some(now + chrono::Duration::seconds(60)));
+
+        let request: CreateVariableRequest = serde_json::from_value(json!({
+            "key": "incident.flag",
+            "data": true,
+            "expires_at": "2001-01-01T00:00:00Z"
+        }))
+        .unwrap();
+        assert!(request.validate().is_err());
+
+        let request: CreateVariableRequest = serde_json::from_value(json!({
+            "key": "incident.flag",
+            "data": true,
+            "expires_at": now + chrono::Duration::hours(1),
+            "ttl_seconds": 60
+        }))
+        .unwrap();
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_update_variable_request_expiry() {
+        let now = chrono::Utc::now();
+
+        let keep: UpdateVariableRequest = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(keep.expiry(now), None);
+
+        let clear: UpdateVariableRequest =
+            serde_json::from_value(json!({"expires_at": null})).unwrap();
+        assert!(clear.validate().is_ok());
+        assert_eq!(clear.expiry(now), Some(None));
+
+        let extend: UpdateVariableRequest =
+            serde_json::from_value(json!({"ttl_seconds": 3600})).unwrap();
+        assert_eq!(extend.expiry(now), Some(Some(now + chrono::Duration::hours(1))));
+
+        let both: UpdateVariableRequest =
+            serde_json::from_value(json!({"expires_at": null, "ttl_seconds": 3600})).unwrap();
+        assert!(both.validate().is_err());
+        let zero: UpdateVariableRequest =
+            serde_json::from_value(json!({"ttl_seconds": 0})).unwrap();
+        assert!(zero.validate().is_err());
+    }
+
+    #[test]
+    fn test_atomic_request() {
+        let increment: AtomicRequest =
+            serde_json::from_value(json!({"op": "increment", "path": "/visits"})).unwrap();
+        assert!(increment.validate().is_ok());
+        assert!(matches!(
+            increment.operation,
+            AtomicOperation::Increment { ref by } if by.as_i64() == Some(1)
+        ));
+
+        let relative: AtomicRequest =
+            serde_json::from_value(json!({"op": "append", "path": "hosts", "value": "a"}))
+                .unwrap();
+        assert!(relative.validate().is_err());
+
+        // An explicit null is an expected value, unlike a missing one
+        let swap: AtomicRequest = serde_json::from_value(
+            json!({"op": "compare_and_swap", "path": "/leader", "expected": null, "value": "a"}),
+        )
+        .unwrap();
+        assert!(swap.validate().is_ok());
+        let unguarded: AtomicRequest = serde_json::from_value(
+            json!({"op": "compare_and_swap", "path": "/leader", "value": "a"}),
+        )
+        .unwrap();
+        assert!(unguarded.validate().is_err());
+
+        assert!(serde_json::from_value::<AtomicRequest>(json!({"op": "multiply"})).is_err());
+    }
+
+    #[test]
+    fn test_trash_item_purge_at() {
+        let deleted_at = chrono::Utc::now();
+        let trashed: TrashedVariable = serde_json::from_value(json!({
+            "id": uuid::Uuid::new_v4(),
+            "user_id": uuid::Uuid::new_v4(),
+            "environment_id": uuid::Uuid::new_v4(),
+            "key": "db.host",
+            "size_bytes": 12,
+            "version": 3,
+            "storage_path": "users/u/env/db.host.json",
+            "is_encrypted": false,
+            "is_public": false,
+            "deleted_at": deleted_at,
+            "deleted_by": uuid::Uuid::new_v4(),
+            "created_at": deleted_at,
+            "updated_at": deleted_at,
+        }))
+        .unwrap();
+
+        let item = TrashItem::new(trashed.clone(), Some(7));
+        assert_eq!(item.purge_at, Some(deleted_at + chrono::Duration::days(7)));
+
+        let kept = TrashItem::new(trashed, None);
+        assert!(kept.purge_at.is_none());
+        let body = serde_json::to_value(&kept).unwrap();
+        assert_eq!(body["key"], "db.host");
+        assert!(body["deleted_by"].is_string());
+    }
+
+    #[test]
+    fn test_get_variable_params() {
+        let params: GetVariableParams = serde_json::from_value(json!({})).unwrap();
+        assert!(!params.resolve);
+
+        let params: GetVariableParams = serde_json::from_value(json!({"resolve": true})).unwrap();
+        assert!(params.resolve);
+    }
+
+    #[test]
+    fn test_schedule_change_request() {
+        let apply_at = chrono::Utc::now() + chrono::Duration::hours(1);
+        let request: ScheduleChangeRequest = serde_json::from_value(
+            json!({"data": {"replicas": 3}, "apply_at": apply_at, "message": "Scale up"}),
+        )
+        .unwrap();
+        assert!(request.validate().is_ok());
+
+        let past = chrono::Utc::now() - chrono::Duration::minutes(1);
+        let late: ScheduleChangeRequest =
+            serde_json::from_value(json!({"data": 1, "apply_at": past})).unwrap();
+        assert!(late.validate().is_err());
+
+        assert!(serde_json::from_value::<ScheduleChangeRequest>(json!({"data": 1})).is_err());
+    }
+
+    #[test]
+    fn test_upsert_variable_request_defaults() {
+        let request: UpsertVariableRequest =
+            serde_json::from_value(json!({"data": {"url": "https://example.com"}})).unwrap();
+
+        assert!(!request.is_encrypted);
+        assert!(request.validate().is_ok());
+        assert!(serde_json::from_value::<UpsertVariableRequest>(json!({})).is_err());
+    }
+
     #[test]
     fn test_variable_query_params_defaults() {
         let params = VariableQueryParams {
             page: None,
             page_size: None,
             search: None,
+            q: None,
             tags: None,
+            tag_match: None,
+            expiring_within: None,
         };
 
         assert!(params.page.is_none());
@@ -147,13 +338,36 @@ mod variable_dto_tests {
             page: Some(2),
             page_size: Some(50),
             search: Some("test".to_string()),
+            q: None,
             tags: Some("tag1,tag2".to_string()),
+            tag_match: None,
+            expiring_within: None,
         };
 
         assert_eq!(params.page, Some(2));
         assert_eq!(params.page_size, Some(50));
         assert_eq!(params.search, Some("test".to_string()));
     }
+
+    #[test]
+    fn test_variable_query_params_filter() {
+        let params = VariableQueryParams {
+            page: None,
+            page_size: None,
+            search: Some(String::new()),
+            q: Some("stripe webhook".to_string()),
+            tags: Some(" tag1, ,tag2 ".to_string()),
+            tag_match: Some(TagMatch::Any),
+            expiring_within: Some(-5),
+        };
+
+        let filter = params.filter();
+        assert!(filter.search.is_none());
+        assert_eq!(filter.query.as_deref(), Some("stripe webhook"));
+        assert_eq!(filter.tags, vec!["tag1".to_string(), "tag2".to_string()]);
+        assert_eq!(filter.tag_match, TagMatch::Any);
+        assert_eq!(filter.expiring_within, Some(0));
+    }
 }
 
 #[cfg(test)]
@@ -188,6 +402,7 @@ mod user_dto_tests {
             name: "Production Key".to_string(),
             expires_in_days: Some(30),
             permissions: None,
+            environment_id: None,
         };
 
         assert!(request.validate().is_ok());
@@ -199,6 +414,7 @@ mod user_dto_tests {
             name: "".to_string(),
             expires_in_days: None,
             permissions: None,
+            environment_id: None,
         };
 
         let result = request.validate();
@@ -211,6 +427,7 @@ mod user_dto_tests {
             name: "a".repeat(101),
             expires_in_days: None,
             permissions: None,
+            environment_id: None,
         };
 
         let result = request.validate();
@@ -223,6 +440,7 @@ mod user_dto_tests {
             name: "Permanent Key".to_string(),
             expires_in_days: None,
             permissions: None,
+            environment_id: None,
         };
 
         assert!(request.validate().is_ok());
@@ -243,6 +461,8 @@ mod tier_dto_tests {
             max_variable_size_mb: 10,
             max_requests_per_day: 10000,
             max_api_keys: 5,
+            max_versions: 20,
+            trash_retention_days: 30,
             price_monthly: 999,
         };
 
@@ -258,6 +478,8 @@ mod tier_dto_tests {
             max_variable_size_mb: 1,
             max_requests_per_day: 1,
             max_api_keys: 1,
+            max_versions: 1,
+            trash_retention_days: 30,
             price_monthly: 0,
         };
 
@@ -265,6 +487,26 @@ mod tier_dto_tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_create_tier_request_trash_retention() {
+        let mut request: CreateTierRequest = serde_json::from_value(serde_json::json!({
+            "name": "Team",
+            "max_variables": 50,
+            "max_variable_size_mb": 1,
+            "max_requests_per_day": 1000,
+            "max_api_keys": 3,
+            "price_monthly": 0,
+        }))
+        .unwrap();
+        assert_eq!(request.trash_retention_days, 30);
+
+        // -1 keeps the trash until it is emptied
+        request.trash_retention_days = -1;
+        assert!(request.validate().is_ok());
+        request.trash_retention_days = -2;
+        assert!(request.validate().is_err());
+    }
+
     #[test]
     fn test_update_tier_request_partial() {
         let request = UpdateTierRequest {
@@ -274,6 +516,8 @@ mod tier_dto_tests {
             max_variable_size_mb: None,
             max_requests_per_day: None,
             max_api_keys: None,
+            max_versions: None,
+            trash_retention_days: None,
             price_monthly: Some(1999),
             is_active: Some(true),
         };
@@ -290,6 +534,8 @@ mod tier_dto_tests {
             max_variable_size_mb: None,
             max_requests_per_day: None,
             max_api_keys: None,
+            max_versions: None,
+            trash_retention_days: None,
             price_monthly: None,
             is_active: None,
         };
@@ -361,3 +607,360 @@ mod admin_dto_tests {
         assert_eq!(params.search, Some("john".to_string()));
     }
 }
+
+#[cfg(test)]
+mod organization_dto_tests {
+    use cloud_variables::dto::{CreateInvitationRequest, CreateOrganizationRequest};
+    use cloud_variables::models::OrgRole;
+    use validator::Validate;
+
+    #[test]
+    fn test_create_organization_request_valid() {
+        let request = CreateOrganizationRequest {
+            name: "Acme".to_string(),
+        };
+
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn test_create_organization_request_empty_name() {
+        let request = CreateOrganizationRequest {
+            name: "".to_string(),
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_create_invitation_request_invalid_email() {
+        let request = CreateInvitationRequest {
+            email: "not-an-email".to_string(),
+            role: OrgRole::Editor,
+            expires_in_days: None,
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_create_invitation_request_expiry_out_of_range() {
+        let request = CreateInvitationRequest {
+            email: "member@example.com".to_string(),
+            role: OrgRole::Viewer,
+            expires_in_days: Some(90),
+        };
+
+        assert!(request.validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod project_dto_tests {
+    use cloud_variables::dto::{
+        CreateEnvironmentRequest, CreateProjectRequest, ResolveQueryParams,
+        UpdateEnvironmentRequest,
+    };
+    use cloud_variables::models::MergeMode;
+    use validator::Validate;
+
+    #[test]
+    fn test_create_project_request_valid() {
+        let request = CreateProjectRequest {
+            name: "billing-service".to_string(),
+            description: Some("Billing configuration".to_string()),
+        };
+
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn test_create_project_request_empty_name() {
+        let request = CreateProjectRequest {
+            name: "".to_string(),
+            description: None,
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_create_environment_request_name_too_long() {
+        let request = CreateEnvironmentRequest {
+            name: "e".repeat(101),
+            description: None,
+            parent: None,
+        };
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_update_environment_request_parent_null_vs_absent() {
+        let cleared: UpdateEnvironmentRequest =
+            serde_json::from_str(r#"{"parent": null}"#).unwrap();
+        assert_eq!(cleared.parent, Some(None));
+
+        let unchanged: UpdateEnvironmentRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(unchanged.parent, None);
+
+        let set: UpdateEnvironmentRequest =
+            serde_json::from_str(r#"{"parent": "prod"}"#).unwrap();
+        assert_eq!(set.parent, Some(Some("prod".to_string())));
+    }
+
+    #[test]
+    fn test_resolve_query_params_default_mode() {
+        let params: ResolveQueryParams = serde_json::from_str("{}").unwrap();
+        assert_eq!(params.mode, MergeMode::Override);
+
+        let params: ResolveQueryParams = serde_json::from_str(r#"{"mode": "merge"}"#).unwrap();
+        assert_eq!(params.mode, MergeMode::Merge);
+    }
+}
+
+#[cfg(test)]
+mod environment_promotion_dto_tests {
+    use cloud_variables::dto::{ApplyPromotionRequest, PromotionPlanRequest};
+    use validator::Validate;
+
+    #[test]
+    fn test_plan_request_defaults() {
+        let request: PromotionPlanRequest =
+            serde_json::from_str(r#"{"source": "staging", "target": "prod"}"#).unwrap();
+
+        assert!(!request.prune);
+        assert!(request.keys.is_none());
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn test_plan_request_empty_key_list() {
+        let request: PromotionPlanRequest =
+            serde_json::from_str(r#"{"source": "staging", "target": "prod", "keys": []}"#)
+                .unwrap();
+
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_apply_request_flattens_plan() {
+        let request: ApplyPromotionRequest = serde_json::from_str(
+            r#"{"source": "staging", "target": "prod", "prune": true, "fingerprint": "abc"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(request.plan.source, "staging");
+        assert!(request.plan.prune);
+        assert_eq!(request.fingerprint, "abc");
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn test_apply_request_requires_fingerprint() {
+        let request: ApplyPromotionRequest = serde_json::from_str(
+            r#"{"source": "staging", "target": "prod", "fingerprint": ""}"#,
+        )
+        .unwrap();
+
+        assert!(request.validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod variable_share_dto_tests {
+    use cloud_variables::dto::{CreateShareLinkRequest, CreateShareRequest};
+    use cloud_variables::models::SharePermission;
+    use validator::Validate;
+
+    #[test]
+    fn test_create_share_request_defaults_to_read() {
+        let request: CreateShareRequest =
+            serde_json::from_str(r#"{"email": "friend@example.com"}"#).unwrap();
+
+        assert_eq!(request.permission, SharePermission::Read);
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn test_create_share_request_invalid_email() {
+        let request: CreateShareRequest =
+            serde_json::from_str(r#"{"email": "friend", "permission": "write"}"#).unwrap();
+
+        assert_eq!(request.permission, SharePermission::Write);
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_create_share_link_request_lifetime() {
+        let request: CreateShareLinkRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(request.expires_in_minutes, 60);
+        assert!(request.validate().is_ok());
+
+        let request: CreateShareLinkRequest =
+            serde_json::from_str(r#"{"expires_in_minutes": 50000}"#).unwrap();
+        assert!(request.validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod policy_dto_tests {
+    use cloud_variables::dto::ExplainPolicyRequest;
+    use cloud_variables::policy::Action;
+
+    #[test]
+    fn test_explain_request_deserialization() {
+        let request: ExplainPolicyRequest = serde_json::from_str(
+            r#"{"action": "variable:read", "resource": {"key": "ssn", "tags": ["pii"]},
+                "api_key_scopes": ["pii:read"]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(request.action, Action::VariableRead);
+        assert_eq!(request.resource.tags, vec!["pii"]);
+        assert!(request.share.is_none());
+    }
+
+    #[test]
+    fn test_explain_request_rejects_unknown_action() {
+        let result = serde_json::from_str::<ExplainPolicyRequest>(r#"{"action": "variable:fly"}"#);
+        assert!(result.is_err());
+    }
+}
+
+#[cfg(test)]
+mod batch_dto_tests {
+    use cloud_variables::dto::{BatchGetRequest, BatchOperation, BatchWriteRequest};
+    use serde_json::json;
+    use uuid::Uuid;
+    use validator::Validate;
+
+    #[test]
+    fn test_batch_get_request_size() {
+        let request = BatchGetRequest {
+            ids: vec![Uuid::new_v4()],
+            keys: vec!["db.host".to_string()],
+        };
+        assert!(request.validate().is_ok());
+
+        let empty = BatchGetRequest { ids: vec![], keys: vec![] };
+        assert!(empty.validate().is_err());
+
+        let too_many = BatchGetRequest {
+            ids: vec![],
+            keys: (0..101).map(|i| format!("key{}", i)).collect(),
+        };
+        assert!(too_many.validate().is_err());
+    }
+
+    #[test]
+    fn test_batch_write_request_operations() {
+        let request: BatchWriteRequest = serde_json::from_value(json!({
+            "operations": [
+                {"op": "upsert", "key": "db.host", "data": "db.internal"},
+                {"op": "delete", "key": "legacy.flag"}
+            ]
+        }))
+        .unwrap();
+
+        assert!(request.validate().is_ok());
+        assert!(matches!(
+            &request.operations[0],
+            BatchOperation::Upsert { is_encrypted: false, .. }
+        ));
+        assert_eq!(request.operations[1].key(), "legacy.flag");
+    }
+
+    #[test]
+    fn test_batch_write_request_rejects_repeated_and_invalid_keys() {
+        let repeated: BatchWriteRequest = serde_json::from_value(json!({
+            "operations": [
+                {"op": "upsert", "key": "db.host", "data": 1},
+                {"op": "delete", "key": "db.host"}
+            ]
+        }))
+        .unwrap();
+        assert!(repeated.validate().is_err());
+
+        let invalid: BatchWriteRequest = serde_json::from_value(json!({
+            "operations": [{"op": "delete", "key": "not a key"}]
+        }))
+        .unwrap();
+        assert!(invalid.validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod query_dto_tests {
+    use cloud_variables::dto::TaggedDataQueryRequest;
+    use cloud_variables::models::TagMatch;
+    use serde_json::json;
+    use validator::Validate;
+
+    #[test]
+    fn test_tagged_data_query_request() {
+        let request: TaggedDataQueryRequest = serde_json::from_value(json!({
+            "path": "$..timeout_ms",
+            "tags": ["service"]
+        }))
+        .unwrap();
+        assert!(request.validate().is_ok());
+        assert_eq!(request.tag_match, TagMatch::All);
+
+        let untagged: TaggedDataQueryRequest = serde_json::from_value(json!({
+            "path": "$..timeout_ms",
+            "tags": []
+        }))
+        .unwrap();
+        assert!(untagged.validate().is_err());
+    }
+}
+
+#[cfg(test)]
+mod schema_dto_tests {
+    use cloud_variables::dto::{AttachSchemaRequest, CreateVariableRequest};
+    use serde_json::json;
+    use uuid::Uuid;
+    use validator::Validate;
+
+    #[test]
+    fn test_attach_schema_request_needs_exactly_one_schema() {
+        let named = AttachSchemaRequest { schema_id: Some(Uuid::new_v4()), schema: None };
+        assert!(named.validate().is_ok());
+
+        let inline = AttachSchemaRequest {
+            schema_id: None,
+            schema: Some(json!({"type": "object"})),
+        };
+        assert!(inline.validate().is_ok());
+
+        let neither = AttachSchemaRequest { schema_id: None, schema: None };
+        assert!(neither.validate().is_err());
+
+        let both = AttachSchemaRequest {
+            schema_id: Some(Uuid::new_v4()),
+            schema: Some(json!({"type": "object"})),
+        };
+        assert!(both.validate().is_err());
+    }
+
+    #[test]
+    fn test_create_variable_request_takes_at_most_one_schema() {
+        let request: CreateVariableRequest = serde_json::from_value(json!({
+            "key": "service.config",
+            "data": {"timeout_ms": 500},
+            "schema": {"type": "object"}
+        }))
+        .unwrap();
+        assert!(request.validate().is_ok());
+
+        let both: CreateVariableRequest = serde_json::from_value(json!({
+            "key": "service.config",
+            "data": {"timeout_ms": 500},
+            "schema_id": Uuid::new_v4(),
+            "schema": {"type": "object"}
+        }))
+        .unwrap();
+        assert!(both.validate().is_err());
+    }
+}
@@ -4,7 +4,7 @@ mod common;
 mod error_handling_tests {
     use axum::http::StatusCode;
     use axum::response::IntoResponse;
-    use cloud_variables::error::AppError;
+    use cloud_variables::error::{AppError, SchemaViolation};
 
     #[test]
     fn test_authentication_error_status() {
@@ -70,6 +70,37 @@ mod error_handling_tests {
         assert_eq!(response.status(), StatusCode::CONFLICT);
     }
 
+    #[test]
+    fn test_precondition_failed_error_status() {
+        let error = AppError::PreconditionFailed("Variable was modified".to_string());
+        let response = error.into_response();
+
+        assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[test]
+    fn test_unsupported_media_type_error_status() {
+        let error = AppError::UnsupportedMediaType("Expected a patch document".to_string());
+        let response = error.into_response();
+
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn test_schema_violation_lists_violations() {
+        let error = AppError::SchemaViolation(vec![SchemaViolation {
+            instance_path: "/timeout_ms".to_string(),
+            schema_path: "/properties/timeout_ms/type".to_string(),
+            message: "\"slow\" is not of type \"integer\"".to_string(),
+        }]);
+        let response = error.into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["violations"][0]["instance_path"], "/timeout_ms");
+    }
+
     #[test]
     fn test_internal_server_error_status() {
         let error = AppError::InternalServer("Something went wrong".to_string());
@@ -0,0 +1,85 @@
+mod common;
+
+#[cfg(test)]
+mod reject_api_key_tests {
+    use axum::{
+        body::Body,
+        extract::{FromRef, Request},
+        http::StatusCode,
+        middleware::{self, Next},
+        routing::delete,
+        Router,
+    };
+    use chrono::Utc;
+    use cloud_variables::api::delete_organization;
+    use cloud_variables::middleware::reject_api_key_middleware;
+    use cloud_variables::models::{ApiKey, User, UserRole};
+    use cloud_variables::storage::FileStorage;
+    use cloud_variables::utils::Claims;
+    use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+    use tower::ServiceExt;
+    use uuid::Uuid;
+
+    #[derive(Clone, FromRef)]
+    struct TestState {
+        pool: Pool<Postgres>,
+        storage: FileStorage,
+    }
+
+    fn api_key_claims() -> Claims {
+        let user = User {
+            id: Uuid::new_v4(),
+            email: "owner@example.com".to_string(),
+            password_hash: "hash".to_string(),
+            role: UserRole::User,
+            tier_id: Uuid::new_v4(),
+            is_active: true,
+            email_verified: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let api_key = ApiKey {
+            id: Uuid::new_v4(),
+            user_id: user.id,
+            organization_id: Some(Uuid::new_v4()),
+            environment_id: None,
+            name: "Deploy".to_string(),
+            key_hash: "hash".to_string(),
+            prefix: "cv_abcdefgh".to_string(),
+            last_used_at: None,
+            expires_at: None,
+            is_active: true,
+            permissions: None,
+            created_at: Utc::now(),
+        };
+
+        Claims::for_api_key(&user, &api_key)
+    }
+
+    #[tokio::test]
+    async fn test_api_key_cannot_delete_organization() {
+        // The pool never connects: the request must be turned away before the handler runs
+        let state = TestState {
+            pool: PgPoolOptions::new()
+                .connect_lazy("postgres://localhost/unused")
+                .unwrap(),
+            storage: FileStorage::new(tempfile::tempdir().unwrap().path()),
+        };
+        let claims = api_key_claims();
+        let app = Router::new()
+            .route("/api/orgs/{id}", delete(delete_organization))
+            .route_layer(middleware::from_fn(reject_api_key_middleware))
+            .layer(middleware::from_fn(move |mut req: Request, next: Next| {
+                req.extensions_mut().insert(claims.clone());
+                next.run(req)
+            }))
+            .with_state(state);
+
+        let request = Request::delete(format!("/api/orgs/{}", Uuid::new_v4()))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -2,7 +2,7 @@ mod common;
 
 #[cfg(test)]
 mod role_tests {
-    use cloud_variables::models::UserRole;
+    use cloud_variables::models::{Permission, UserRole};
 
     #[test]
     fn test_user_role_is_admin() {
@@ -27,6 +27,44 @@ mod role_tests {
         assert_eq!(UserRole::User, UserRole::User);
         assert_ne!(UserRole::Admin, UserRole::User);
     }
+
+    #[test]
+    fn test_admin_has_every_permission() {
+        for role in [UserRole::Support, UserRole::Billing, UserRole::Auditor] {
+            for permission in role.permissions() {
+                assert!(UserRole::Admin.has_permission(*permission));
+            }
+        }
+    }
+
+    #[test]
+    fn test_staff_role_permissions() {
+        assert!(UserRole::Support.has_permission(Permission::UsersWrite));
+        assert!(!UserRole::Support.has_permission(Permission::TiersWrite));
+        assert!(!UserRole::Support.has_permission(Permission::UsersDelete));
+
+        assert!(UserRole::Billing.has_permission(Permission::BillingWrite));
+        assert!(!UserRole::Billing.has_permission(Permission::UsersWrite));
+
+        assert!(UserRole::Auditor.has_permission(Permission::BillingRead));
+        assert!(!UserRole::Auditor.has_permission(Permission::BillingWrite));
+        assert!(!UserRole::Auditor.has_permission(Permission::UsersWrite));
+        assert!(UserRole::Auditor.has_permission(Permission::EncryptionRead));
+        assert!(!UserRole::Auditor.has_permission(Permission::EncryptionWrite));
+    }
+
+    #[test]
+    fn test_user_role_is_staff() {
+        assert!(!UserRole::User.is_staff());
+        assert!(UserRole::Auditor.is_staff());
+        assert!(!UserRole::Auditor.is_admin());
+    }
+
+    #[test]
+    fn test_permission_display() {
+        assert_eq!(Permission::UsersManageRoles.to_string(), "users:manage_roles");
+        assert_eq!(Permission::BillingRead.to_string(), "billing:read");
+    }
 }
 
 #[cfg(test)]
@@ -44,6 +82,8 @@ mod tier_tests {
             max_variable_size_mb: 1,
             max_requests_per_day: 100,
             max_api_keys: 2,
+            max_versions: 5,
+            trash_retention_days: 7,
             price_monthly: 0,
             is_active: true,
             created_at: Utc::now(),
@@ -90,6 +130,31 @@ mod tier_tests {
         assert!(!tier.is_within_rate_limit(150)); // Over limit
     }
 
+    #[test]
+    fn test_tier_version_retention() {
+        let mut tier = create_test_tier();
+        assert_eq!(tier.version_retention(), Some(5));
+
+        // The current version is always kept
+        tier.max_versions = 0;
+        assert_eq!(tier.version_retention(), Some(1));
+
+        tier.max_versions = -1;
+        assert_eq!(tier.version_retention(), None);
+    }
+
+    #[test]
+    fn test_tier_trash_retention() {
+        let mut tier = create_test_tier();
+        assert_eq!(tier.trash_retention(), Some(7));
+
+        tier.trash_retention_days = 0;
+        assert_eq!(tier.trash_retention(), Some(0));
+
+        tier.trash_retention_days = -1;
+        assert_eq!(tier.trash_retention(), None);
+    }
+
     #[test]
     fn test_tier_edge_cases() {
         let tier = Tier {
@@ -100,6 +165,8 @@ mod tier_tests {
             max_variable_size_mb: 1,
             max_requests_per_day: 1,
             max_api_keys: 1,
+            max_versions: 1,
+            trash_retention_days: 0,
             price_monthly: 0,
             is_active: true,
             created_at: Utc::now(),
@@ -114,7 +181,7 @@ mod tier_tests {
 
 #[cfg(test)]
 mod variable_tests {
-    use cloud_variables::models::Variable;
+    use cloud_variables::models::{Owner, Variable};
     use chrono::Utc;
     use uuid::Uuid;
 
@@ -122,6 +189,8 @@ mod variable_tests {
         Variable {
             id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
+            organization_id: None,
+            environment_id: Uuid::new_v4(),
             key: "test_key".to_string(),
             description: Some("Test variable".to_string()),
             size_bytes,
@@ -129,6 +198,11 @@ mod variable_tests {
             storage_path: "user_id/test_key.json".to_string(),
             is_encrypted: false,
             tags: None,
+            is_public: false,
+            schema_id: None,
+            inline_schema: None,
+            expires_at: None,
+            deleted_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -149,6 +223,16 @@ mod variable_tests {
         assert_eq!(var_0.size_in_mb(), 0);
     }
 
+    #[test]
+    fn test_variable_owner() {
+        let mut var = create_test_variable(0);
+        assert_eq!(var.owner(), Owner::User(var.user_id));
+
+        let org_id = Uuid::new_v4();
+        var.organization_id = Some(org_id);
+        assert_eq!(var.owner(), Owner::Organization(org_id));
+    }
+
     #[test]
     fn test_variable_size_in_mb_rounding() {
         // 1.5 MB should round down to 1
@@ -171,6 +255,8 @@ mod api_key_tests {
         ApiKey {
             id: Uuid::new_v4(),
             user_id: Uuid::new_v4(),
+            organization_id: None,
+            environment_id: None,
             name: "Test Key".to_string(),
             key_hash: "hashed_key".to_string(),
             prefix: "cv_abcdefgh".to_string(),
@@ -198,6 +284,15 @@ mod api_key_tests {
         assert!(key.is_expired());
     }
 
+    #[test]
+    fn test_api_key_scopes() {
+        let mut key = create_test_api_key(None);
+        assert!(key.scopes().is_empty());
+
+        key.permissions = Some(serde_json::json!(["pii:read", "deploy"]));
+        assert_eq!(key.scopes(), vec!["pii:read", "deploy"]);
+    }
+
     #[test]
     fn test_api_key_is_expired_none() {
         let key = create_test_api_key(None);
@@ -294,3 +389,129 @@ mod user_tests {
         assert_eq!(public_user.email_verified, user.email_verified);
     }
 }
+
+#[cfg(test)]
+mod organization_tests {
+    use chrono::{Duration, Utc};
+    use cloud_variables::models::{OrgRole, OrganizationInvitation, Owner};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_org_role_ordering() {
+        assert!(OrgRole::Owner > OrgRole::Admin);
+        assert!(OrgRole::Admin > OrgRole::Editor);
+        assert!(OrgRole::Editor > OrgRole::Viewer);
+    }
+
+    #[test]
+    fn test_org_role_permissions() {
+        assert!(OrgRole::Viewer.can_read());
+        assert!(!OrgRole::Viewer.can_write());
+        assert!(OrgRole::Editor.can_write());
+        assert!(!OrgRole::Editor.can_manage_members());
+        assert!(OrgRole::Admin.can_manage_members());
+        assert!(!OrgRole::Admin.is_owner());
+        assert!(OrgRole::Owner.is_owner());
+    }
+
+    #[test]
+    fn test_org_role_display() {
+        assert_eq!(OrgRole::Owner.to_string(), "owner");
+        assert_eq!(OrgRole::Viewer.to_string(), "viewer");
+    }
+
+    #[test]
+    fn test_owner_accessors() {
+        let id = Uuid::new_v4();
+
+        assert_eq!(Owner::User(id).id(), id);
+        assert_eq!(Owner::User(id).organization_id(), None);
+        assert_eq!(Owner::Organization(id).organization_id(), Some(id));
+        assert_eq!(Owner::User(id).storage_prefix(), id.to_string());
+        assert_eq!(Owner::Organization(id).storage_prefix(), format!("orgs/{}", id));
+    }
+
+    #[test]
+    fn test_invitation_is_pending() {
+        let mut invitation = OrganizationInvitation {
+            id: Uuid::new_v4(),
+            organization_id: Uuid::new_v4(),
+            email: "member@example.com".to_string(),
+            role: OrgRole::Editor,
+            invited_by: None,
+            expires_at: Utc::now() + Duration::days(7),
+            accepted_at: None,
+            created_at: Utc::now(),
+        };
+        assert!(invitation.is_pending());
+
+        invitation.accepted_at = Some(Utc::now());
+        assert!(!invitation.is_pending());
+
+        invitation.accepted_at = None;
+        invitation.expires_at = Utc::now() - Duration::days(1);
+        assert!(invitation.is_expired());
+        assert!(!invitation.is_pending());
+    }
+}
+
+#[cfg(test)]
+mod project_tests {
+    use chrono::Utc;
+    use cloud_variables::models::{Owner, Project};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_project_owner() {
+        let user_id = Uuid::new_v4();
+        let mut project = Project {
+            id: Uuid::new_v4(),
+            user_id,
+            organization_id: None,
+            name: "default".to_string(),
+            description: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        assert_eq!(project.owner(), Owner::User(user_id));
+
+        let org_id = Uuid::new_v4();
+        project.organization_id = Some(org_id);
+        assert_eq!(project.owner(), Owner::Organization(org_id));
+    }
+}
+
+#[cfg(test)]
+mod variable_share_tests {
+    use chrono::{Duration, Utc};
+    use cloud_variables::models::{SharePermission, VariableShareLink};
+    use uuid::Uuid;
+
+    #[test]
+    fn test_share_permission_can_write() {
+        assert!(!SharePermission::Read.can_write());
+        assert!(SharePermission::Write.can_write());
+    }
+
+    #[test]
+    fn test_share_permission_serialization() {
+        assert_eq!(serde_json::to_string(&SharePermission::Write).unwrap(), "\"write\"");
+    }
+
+    #[test]
+    fn test_share_link_is_expired() {
+        let mut link = VariableShareLink {
+            id: Uuid::new_v4(),
+            variable_id: Uuid::new_v4(),
+            created_by: None,
+            expires_at: Utc::now() + Duration::minutes(5),
+            use_count: 0,
+            last_used_at: None,
+            created_at: Utc::now(),
+        };
+        assert!(!link.is_expired());
+
+        link.expires_at = Utc::now() - Duration::seconds(1);
+        assert!(link.is_expired());
+    }
+}
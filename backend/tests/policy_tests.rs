@@ -0,0 +1,105 @@
+mod common;
+
+#[cfg(test)]
+mod example_policy_tests {
+    use cloud_variables::models::{OrgRole, SharePermission, UserRole};
+    use cloud_variables::policy::{
+        Action, PolicyDocument, PolicyEngine, PolicyRequest, Principal, Resource,
+    };
+    use uuid::Uuid;
+
+    fn engine() -> PolicyEngine {
+        let document = PolicyDocument::parse(include_str!("../policies/example.json")).unwrap();
+        PolicyEngine::new(document).unwrap()
+    }
+
+    fn principal(role: OrgRole) -> Principal {
+        Principal {
+            user_id: Uuid::new_v4(),
+            user_role: UserRole::User,
+            api_key: false,
+            scopes: Vec::new(),
+            org_role: Some(role),
+            share: None,
+        }
+    }
+
+    fn request(principal: Principal, action: Action, key: &str, tags: &[&str]) -> PolicyRequest {
+        PolicyRequest {
+            principal,
+            action,
+            resource: Resource {
+                key: Some(key.to_string()),
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                project: Some("app".to_string()),
+                environment: Some("production".to_string()),
+            },
+        }
+    }
+
+    #[test]
+    fn test_example_extends_default_rules() {
+        let engine = engine();
+        assert!(engine.rules().iter().any(|r| r.id == "members-read"));
+        assert!(engine.rules().iter().any(|r| r.id == "pii-requires-scope"));
+    }
+
+    #[test]
+    fn test_pii_requires_scoped_api_key() {
+        let engine = engine();
+
+        let owner = request(principal(OrgRole::Owner), Action::VariableRead, "ssn", &["pii"]);
+        let decision = engine.evaluate(&owner);
+        assert!(!decision.allowed);
+        assert_eq!(decision.determining_rules, vec!["pii-requires-scope"]);
+
+        let mut key = principal(OrgRole::Editor);
+        key.api_key = true;
+        key.scopes = vec!["deploy".to_string()];
+        let unscoped = request(key.clone(), Action::VariableRead, "ssn", &["pii"]);
+        assert!(!engine.evaluate(&unscoped).allowed);
+
+        key.scopes.push("pii:read".to_string());
+        let scoped = request(key, Action::VariableRead, "ssn", &["pii"]);
+        let decision = engine.evaluate(&scoped);
+        assert!(decision.allowed);
+        assert_eq!(decision.determining_rules, vec!["members-read"]);
+    }
+
+    #[test]
+    fn test_prod_writes_require_editor() {
+        let engine = engine();
+
+        let mut shared = principal(OrgRole::Owner);
+        shared.org_role = None;
+        shared.share = Some(SharePermission::Write);
+
+        let prod = request(shared.clone(), Action::VariableWrite, "prod.db", &[]);
+        let decision = engine.evaluate(&prod);
+        assert!(!decision.allowed);
+        assert!(decision.reason.contains("prod-writes-require-editor"));
+
+        let dev = request(shared, Action::VariableWrite, "dev.db", &[]);
+        assert!(engine.evaluate(&dev).allowed);
+
+        let editor = request(principal(OrgRole::Editor), Action::VariableWrite, "prod.db", &[]);
+        assert!(engine.evaluate(&editor).allowed);
+    }
+
+    #[test]
+    fn test_decision_explains_every_rule() {
+        let engine = engine();
+        let viewer = request(principal(OrgRole::Viewer), Action::VariableDelete, "prod.db", &[]);
+        let decision = engine.evaluate(&viewer);
+
+        assert!(!decision.allowed);
+        assert_eq!(decision.evaluations.len(), engine.rules().len());
+        let editors = decision
+            .evaluations
+            .iter()
+            .find(|e| e.rule == "editors-write")
+            .unwrap();
+        assert!(!editors.applies);
+        assert!(editors.detail.contains("editor"));
+    }
+}
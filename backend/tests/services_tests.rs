@@ -0,0 +1,73 @@
+mod common;
+
+#[cfg(test)]
+mod reference_backfill_tests {
+    use super::common::setup_test_db;
+    use cloud_variables::models::Owner;
+    use cloud_variables::repositories::{
+        EnvironmentRepository, ProjectRepository, UserRepository, VariableRepository,
+    };
+    use cloud_variables::services::{backfill_references, find_referrers};
+    use cloud_variables::storage::{FileStorage, VariableStore};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[tokio::test]
+    #[ignore = "needs a migrated database at TEST_DATABASE_URL"]
+    async fn test_backfill_lists_referrers_written_before_references_were_recorded() {
+        let pool = setup_test_db().await;
+        let dir = tempfile::tempdir().unwrap();
+        let storage = FileStorage::new(dir.path());
+
+        let free_tier = Uuid::from_u128(1);
+        let user = UserRepository::new(pool.clone())
+            .create(&format!("{}@example.com", Uuid::new_v4()), "hash", free_tier)
+            .await
+            .unwrap();
+        let owner = Owner::User(user.id);
+        let project = ProjectRepository::new(pool.clone())
+            .create(owner, user.id, "app", None)
+            .await
+            .unwrap();
+        let environment = EnvironmentRepository::new(pool.clone())
+            .create(project.id, None, "dev", None)
+            .await
+            .unwrap();
+
+        // Created without recording a version, as variables were before references were
+        let mut conn = pool.acquire().await.unwrap();
+        for (key, data) in [("db.host", json!("db.internal")), ("app", json!("${db.host}:5432"))] {
+            let path = storage.store(owner, environment.id, key, &data, false).await.unwrap();
+            VariableRepository::create_tx(
+                &mut conn,
+                owner,
+                environment.id,
+                user.id,
+                key,
+                None,
+                0,
+                &path,
+                false,
+                None,
+            )
+            .await
+            .unwrap();
+        }
+        drop(conn);
+
+        assert!(find_referrers(&pool, environment.id, "db.host").await.unwrap().is_empty());
+
+        backfill_references(&pool, &storage).await.unwrap();
+
+        let referrers = find_referrers(&pool, environment.id, "db.host").await.unwrap();
+        assert_eq!(referrers.len(), 1);
+        assert_eq!(referrers[0].variable.key, "app");
+        assert_eq!(referrers[0].depth, 1);
+
+        sqlx::query("DELETE FROM users WHERE id = $1")
+            .bind(user.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
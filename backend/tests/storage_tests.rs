@@ -2,7 +2,9 @@ mod common;
 
 #[cfg(test)]
 mod file_storage_tests {
+    use cloud_variables::models::Owner;
     use cloud_variables::storage::{FileStorage, VariableStore};
+    use cloud_variables::utils::{Keyring, MasterKey};
     use serde_json::json;
     use tempfile::TempDir;
     use uuid::Uuid;
@@ -12,6 +14,7 @@ mod file_storage_tests {
         let temp_dir = TempDir::new().unwrap();
         let storage = FileStorage::new(temp_dir.path());
         storage.init().await.unwrap();
+        let env_id = Uuid::new_v4();
 
         let user_id = Uuid::new_v4();
         let variable_key = "test_variable";
@@ -25,7 +28,7 @@ mod file_storage_tests {
 
         // Store
         let path = storage
-            .store(user_id, variable_key, &data)
+            .store(Owner::User(user_id), env_id, variable_key, &data, false)
             .await
             .expect("Failed to store");
 
@@ -42,6 +45,7 @@ mod file_storage_tests {
         let temp_dir = TempDir::new().unwrap();
         let storage = FileStorage::new(temp_dir.path());
         storage.init().await.unwrap();
+        let env_id = Uuid::new_v4();
 
         let user_id = Uuid::new_v4();
         let variable_key = "test_var";
@@ -49,7 +53,7 @@ mod file_storage_tests {
         let updated_data = json!({"version": 2, "new_field": "value"});
 
         // Store initial
-        let path = storage.store(user_id, variable_key, &initial_data).await.unwrap();
+        let path = storage.store(Owner::User(user_id), env_id, variable_key, &initial_data, false).await.unwrap();
 
         // Update
         storage.update(&path, &updated_data).await.expect("Failed to update");
@@ -65,13 +69,14 @@ mod file_storage_tests {
         let temp_dir = TempDir::new().unwrap();
         let storage = FileStorage::new(temp_dir.path());
         storage.init().await.unwrap();
+        let env_id = Uuid::new_v4();
 
         let user_id = Uuid::new_v4();
         let variable_key = "delete_test";
         let data = json!({"test": true});
 
         // Store
-        let path = storage.store(user_id, variable_key, &data).await.unwrap();
+        let path = storage.store(Owner::User(user_id), env_id, variable_key, &data, false).await.unwrap();
         assert!(storage.exists(&path).await.unwrap());
 
         // Delete
@@ -86,17 +91,18 @@ mod file_storage_tests {
         let temp_dir = TempDir::new().unwrap();
         let storage = FileStorage::new(temp_dir.path());
         storage.init().await.unwrap();
+        let env_id = Uuid::new_v4();
 
         let user_id = Uuid::new_v4();
         let variable_key = "exists_test";
         let data = json!({"exists": true});
 
         // Should not exist initially
-        let path = format!("{}/{}.json", user_id, variable_key);
+        let path = format!("{}/{}/{}.json", user_id, env_id, variable_key);
         assert!(!storage.exists(&path).await.unwrap());
 
         // Store and verify exists
-        let stored_path = storage.store(user_id, variable_key, &data).await.unwrap();
+        let stored_path = storage.store(Owner::User(user_id), env_id, variable_key, &data, false).await.unwrap();
         assert!(storage.exists(&stored_path).await.unwrap());
     }
 
@@ -126,6 +132,7 @@ mod file_storage_tests {
         let temp_dir = TempDir::new().unwrap();
         let storage = FileStorage::new(temp_dir.path());
         storage.init().await.unwrap();
+        let env_id = Uuid::new_v4();
 
         let user1 = Uuid::new_v4();
         let user2 = Uuid::new_v4();
@@ -135,8 +142,8 @@ mod file_storage_tests {
         let data2 = json!({"user": "user2"});
 
         // Store for both users with same key
-        let path1 = storage.store(user1, variable_key, &data1).await.unwrap();
-        let path2 = storage.store(user2, variable_key, &data2).await.unwrap();
+        let path1 = storage.store(Owner::User(user1), env_id, variable_key, &data1, false).await.unwrap();
+        let path2 = storage.store(Owner::User(user2), env_id, variable_key, &data2, false).await.unwrap();
 
         // Paths should be different
         assert_ne!(path1, path2);
@@ -154,6 +161,7 @@ mod file_storage_tests {
         let temp_dir = TempDir::new().unwrap();
         let storage = FileStorage::new(temp_dir.path());
         storage.init().await.unwrap();
+        let env_id = Uuid::new_v4();
 
         let user_id = Uuid::new_v4();
 
@@ -177,7 +185,7 @@ mod file_storage_tests {
         ];
 
         for (key, data) in test_cases {
-            let path = storage.store(user_id, key, &data).await.unwrap();
+            let path = storage.store(Owner::User(user_id), env_id, key, &data, false).await.unwrap();
             let retrieved = storage.retrieve(&path).await.unwrap();
             assert_eq!(retrieved, data, "Failed for type: {}", key);
         }
@@ -188,6 +196,7 @@ mod file_storage_tests {
         let temp_dir = TempDir::new().unwrap();
         let storage = FileStorage::new(temp_dir.path());
         storage.init().await.unwrap();
+        let env_id = Uuid::new_v4();
 
         let user_id = Uuid::new_v4();
         let variable_key = "large_data";
@@ -200,7 +209,7 @@ mod file_storage_tests {
         let data = json!(large_object);
 
         // Store and retrieve
-        let path = storage.store(user_id, variable_key, &data).await.unwrap();
+        let path = storage.store(Owner::User(user_id), env_id, variable_key, &data, false).await.unwrap();
         let retrieved = storage.retrieve(&path).await.unwrap();
 
         assert_eq!(retrieved, data);
@@ -211,12 +220,13 @@ mod file_storage_tests {
         let temp_dir = TempDir::new().unwrap();
         let storage = FileStorage::new(temp_dir.path());
         storage.init().await.unwrap();
+        let env_id = Uuid::new_v4();
 
         let user_id = Uuid::new_v4();
         let variable_key = "delete_twice";
         let data = json!({"test": true});
 
-        let path = storage.store(user_id, variable_key, &data).await.unwrap();
+        let path = storage.store(Owner::User(user_id), env_id, variable_key, &data, false).await.unwrap();
 
         // Delete twice should not error
         storage.delete(&path).await.expect("First delete failed");
@@ -224,4 +234,265 @@ mod file_storage_tests {
 
         assert!(!storage.exists(&path).await.unwrap());
     }
+
+    #[tokio::test]
+    async fn test_file_storage_organization_owner() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FileStorage::new(temp_dir.path());
+        storage.init().await.unwrap();
+        let env_id = Uuid::new_v4();
+
+        let id = Uuid::new_v4();
+        let variable_key = "shared_config";
+        let user_data = json!({"owner": "user"});
+        let org_data = json!({"owner": "org"});
+
+        // The same id as user and as organization must not collide
+        let user_path = storage.store(Owner::User(id), env_id, variable_key, &user_data, false).await.unwrap();
+        let org_path = storage
+            .store(Owner::Organization(id), env_id, variable_key, &org_data, false)
+            .await
+            .unwrap();
+
+        assert_ne!(user_path, org_path);
+        assert!(org_path.starts_with("orgs/"));
+
+        assert_eq!(storage.retrieve(&user_path).await.unwrap(), user_data);
+        assert_eq!(storage.retrieve(&org_path).await.unwrap(), org_data);
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_same_key_in_different_environments() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FileStorage::new(temp_dir.path());
+        storage.init().await.unwrap();
+
+        let owner = Owner::User(Uuid::new_v4());
+        let staging = Uuid::new_v4();
+        let prod = Uuid::new_v4();
+
+        let staging_path = storage
+            .store(owner, staging, "db.host", &json!("staging.db"), false)
+            .await
+            .unwrap();
+        let prod_path = storage
+            .store(owner, prod, "db.host", &json!("prod.db"), false)
+            .await
+            .unwrap();
+
+        assert_ne!(staging_path, prod_path);
+        assert_eq!(storage.retrieve(&staging_path).await.unwrap(), json!("staging.db"));
+        assert_eq!(storage.retrieve(&prod_path).await.unwrap(), json!("prod.db"));
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_versions_are_immutable() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FileStorage::new(temp_dir.path());
+        storage.init().await.unwrap();
+
+        let path = storage
+            .store(Owner::User(Uuid::new_v4()), Uuid::new_v4(), "api.url", &json!("v1"), false)
+            .await
+            .unwrap();
+        let v1 = storage.store_version(&path, 1, &json!("v1")).await.unwrap();
+
+        storage.update(&path, &json!("v2")).await.unwrap();
+        let v2 = storage.store_version(&path, 2, &json!("v2")).await.unwrap();
+
+        assert_ne!(v1, path);
+        assert_ne!(v1, v2);
+        assert_eq!(storage.retrieve(&v1).await.unwrap(), json!("v1"));
+        assert_eq!(storage.retrieve(&v2).await.unwrap(), json!("v2"));
+        assert_eq!(storage.retrieve(&path).await.unwrap(), json!("v2"));
+    }
+
+    fn keyring_storage<const N: usize>(temp_dir: &TempDir, keys: [MasterKey; N]) -> FileStorage {
+        FileStorage::new(temp_dir.path()).with_keyring(Keyring::new(keys).unwrap())
+    }
+
+    fn encrypted_storage(temp_dir: &TempDir) -> FileStorage {
+        keyring_storage(temp_dir, [MasterKey::new(1, [7u8; 32])])
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_encrypts_data_at_rest() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = encrypted_storage(&temp_dir);
+        storage.init().await.unwrap();
+
+        let data = json!({"password": "hunter2"});
+        let path = storage
+            .store(Owner::User(Uuid::new_v4()), Uuid::new_v4(), "db.password", &data, true)
+            .await
+            .unwrap();
+
+        let on_disk = std::fs::read(temp_dir.path().join(&path)).unwrap();
+        assert!(!String::from_utf8_lossy(&on_disk).contains("hunter2"));
+        assert_eq!(storage.retrieve(&path).await.unwrap(), data);
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_updates_and_versions_stay_encrypted() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = encrypted_storage(&temp_dir);
+        storage.init().await.unwrap();
+
+        let path = storage
+            .store(Owner::User(Uuid::new_v4()), Uuid::new_v4(), "api.token", &json!("first"), true)
+            .await
+            .unwrap();
+        storage.update(&path, &json!("second")).await.unwrap();
+        let version = storage.store_version(&path, 2, &json!("second")).await.unwrap();
+
+        for stored in [&path, &version] {
+            let on_disk = std::fs::read(temp_dir.path().join(stored)).unwrap();
+            assert!(!String::from_utf8_lossy(&on_disk).contains("second"));
+            assert_eq!(storage.retrieve(stored).await.unwrap(), json!("second"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_rejects_encryption_without_master_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FileStorage::new(temp_dir.path());
+        storage.init().await.unwrap();
+
+        let result = storage
+            .store(Owner::User(Uuid::new_v4()), Uuid::new_v4(), "secret", &json!("x"), true)
+            .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_cannot_decrypt_with_another_master_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = encrypted_storage(&temp_dir);
+        storage.init().await.unwrap();
+
+        let path = storage
+            .store(Owner::User(Uuid::new_v4()), Uuid::new_v4(), "secret", &json!("x"), true)
+            .await
+            .unwrap();
+
+        let other = keyring_storage(&temp_dir, [MasterKey::new(1, [8u8; 32])]);
+        assert!(other.retrieve(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_scheduled_data_stays_encrypted() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = encrypted_storage(&temp_dir);
+        storage.init().await.unwrap();
+
+        let path = storage
+            .store(Owner::User(Uuid::new_v4()), Uuid::new_v4(), "api.token", &json!("first"), true)
+            .await
+            .unwrap();
+        let staged =
+            storage.store_scheduled(&path, Uuid::new_v4(), &json!("second")).await.unwrap();
+
+        let on_disk = std::fs::read(temp_dir.path().join(&staged)).unwrap();
+        assert!(!String::from_utf8_lossy(&on_disk).contains("second"));
+        assert_eq!(storage.retrieve(&staged).await.unwrap(), json!("second"));
+
+        // Dropping the staged data keeps the variable readable
+        storage.delete(&staged).await.unwrap();
+        assert_eq!(storage.retrieve(&path).await.unwrap(), json!("first"));
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_delete_removes_data_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = encrypted_storage(&temp_dir);
+        storage.init().await.unwrap();
+
+        let owner = Owner::User(Uuid::new_v4());
+        let env_id = Uuid::new_v4();
+        let path = storage.store(owner, env_id, "secret", &json!("x"), true).await.unwrap();
+        storage.delete(&path).await.unwrap();
+
+        // A plain variable reusing the key is not encrypted with the old data key
+        let path = storage.store(owner, env_id, "secret", &json!("y"), false).await.unwrap();
+        let on_disk = std::fs::read_to_string(temp_dir.path().join(&path)).unwrap();
+        assert!(on_disk.contains('y'));
+        assert_eq!(storage.retrieve(&path).await.unwrap(), json!("y"));
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_same_key_keeps_earlier_variable() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = encrypted_storage(&temp_dir);
+        storage.init().await.unwrap();
+
+        let owner = Owner::User(Uuid::new_v4());
+        let env_id = Uuid::new_v4();
+        let trashed = storage.store(owner, env_id, "secret", &json!("x"), true).await.unwrap();
+        let trashed_version = storage.store_version(&trashed, 1, &json!("x")).await.unwrap();
+
+        // A new variable of the same key gets files of its own
+        let path = storage.store(owner, env_id, "secret", &json!("y"), false).await.unwrap();
+        assert_ne!(path, trashed);
+        let version = storage.store_version(&path, 1, &json!("y")).await.unwrap();
+        assert_ne!(version, trashed_version);
+
+        assert_eq!(storage.retrieve(&trashed).await.unwrap(), json!("x"));
+        assert_eq!(storage.retrieve(&trashed_version).await.unwrap(), json!("x"));
+        assert_eq!(storage.retrieve(&path).await.unwrap(), json!("y"));
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_rewraps_data_keys_under_new_master_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let old = keyring_storage(&temp_dir, [MasterKey::new(1, [7u8; 32])]);
+        old.init().await.unwrap();
+
+        let path = old
+            .store(Owner::User(Uuid::new_v4()), Uuid::new_v4(), "secret", &json!("x"), true)
+            .await
+            .unwrap();
+        let version = old.store_version(&path, 1, &json!("x")).await.unwrap();
+
+        // Both master keys are configured during the rotation
+        let rotated =
+            keyring_storage(&temp_dir, [MasterKey::new(1, [7u8; 32]), MasterKey::new(2, [9u8; 32])]);
+        assert_eq!(rotated.key_version(), Some(2));
+        assert_eq!(rotated.data_key_version(&path).await.unwrap(), Some(1));
+        assert_eq!(rotated.retrieve(&path).await.unwrap(), json!("x"));
+
+        assert!(rotated.rewrap_data_key(&path).await.unwrap());
+        assert!(!rotated.rewrap_data_key(&path).await.unwrap());
+        assert_eq!(rotated.data_key_version(&path).await.unwrap(), Some(2));
+
+        // Once re-wrapped, the old master key can be retired
+        let new = keyring_storage(&temp_dir, [MasterKey::new(2, [9u8; 32])]);
+        assert_eq!(new.retrieve(&path).await.unwrap(), json!("x"));
+        assert_eq!(new.retrieve(&version).await.unwrap(), json!("x"));
+        assert!(old.retrieve(&path).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_encrypts_existing_plain_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = encrypted_storage(&temp_dir);
+        storage.init().await.unwrap();
+
+        let path = storage
+            .store(Owner::User(Uuid::new_v4()), Uuid::new_v4(), "legacy", &json!("hunter2"), false)
+            .await
+            .unwrap();
+        let version = storage.store_version(&path, 1, &json!("hunter2")).await.unwrap();
+        assert_eq!(storage.data_key_version(&path).await.unwrap(), None);
+
+        let versions = vec![version.clone()];
+        assert!(storage.encrypt_existing(&path, &versions).await.unwrap());
+        assert!(!storage.encrypt_existing(&path, &versions).await.unwrap());
+
+        for stored in [&path, &version] {
+            let on_disk = std::fs::read(temp_dir.path().join(stored)).unwrap();
+            assert!(!String::from_utf8_lossy(&on_disk).contains("hunter2"));
+            assert_eq!(storage.retrieve(stored).await.unwrap(), json!("hunter2"));
+        }
+        assert_eq!(storage.data_key_version(&path).await.unwrap(), Some(1));
+    }
 }
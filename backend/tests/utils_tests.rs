@@ -2,8 +2,9 @@ mod common;
 
 #[cfg(test)]
 mod jwt_tests {
-    use cloud_variables::models::UserRole;
-    use cloud_variables::utils::JwtConfig;
+    use chrono::Utc;
+    use cloud_variables::models::{ApiKey, User, UserRole};
+    use cloud_variables::utils::{Claims, JwtConfig};
     use uuid::Uuid;
 
     #[test]
@@ -75,6 +76,60 @@ mod jwt_tests {
 
         assert_eq!(parsed_id, user_id);
     }
+
+    #[test]
+    fn test_user_token_has_no_api_key_scope() {
+        let config = JwtConfig::new("test-secret".to_string(), 24);
+
+        let token = config
+            .generate_token(
+                Uuid::new_v4(),
+                "test@example.com".to_string(),
+                UserRole::User,
+                Uuid::new_v4(),
+            )
+            .unwrap();
+
+        let claims = config.verify_token(&token).unwrap();
+
+        assert!(!claims.is_api_key());
+        assert_eq!(claims.organization_id, None);
+        assert_eq!(claims.environment_id, None);
+    }
+
+    #[test]
+    fn test_api_key_claims_drop_admin_role() {
+        let user = User {
+            id: Uuid::new_v4(),
+            email: "admin@example.com".to_string(),
+            password_hash: "hash".to_string(),
+            role: UserRole::Admin,
+            tier_id: Uuid::new_v4(),
+            is_active: true,
+            email_verified: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let api_key = ApiKey {
+            id: Uuid::new_v4(),
+            user_id: user.id,
+            organization_id: None,
+            environment_id: None,
+            name: "Deploy".to_string(),
+            key_hash: "hash".to_string(),
+            prefix: "cv_abcdefgh".to_string(),
+            last_used_at: None,
+            expires_at: None,
+            is_active: true,
+            permissions: None,
+            created_at: Utc::now(),
+        };
+
+        let claims = Claims::for_api_key(&user, &api_key);
+
+        assert!(claims.is_api_key());
+        assert_eq!(claims.role, UserRole::User);
+    }
 }
 
 #[cfg(test)]
@@ -129,7 +184,7 @@ mod hash_tests {
     #[test]
     fn test_extract_key_prefix() {
         let key = "cv_abcdefgh123456789012345";
-        let prefix = extract_key_prefix(&key);
+        let prefix = extract_key_prefix(key);
 
         assert_eq!(prefix, "cv_abcdefgh");
         assert_eq!(prefix.len(), 11);